@@ -0,0 +1,282 @@
+// Reading ANSI escape sequences, for taking in text that's been styled for a terminal
+use crate::{Color, Style};
+
+/// A piece of ANSI-escaped text.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) enum AnsiToken {
+    /// A printable character
+    Character(char),
+    /// A control character, like '\n' or '\t'
+    Control(char),
+    /// A two character escape, like ESC 7
+    Escape(char),
+    /// A control sequence: ESC [, an optional private marker (like '?'), the parameters and the final character.
+    /// Each parameter is a list of its ':' separated parts, with empty parts read as 0.
+    Csi {
+        private: Option<char>,
+        params: Vec<Vec<u32>>,
+        final_char: char
+    }
+}
+
+/// Splits text into AnsiTokens. Escape sequences may be split across calls to feed().
+/// Sequences that aren't understood (OSC, DCS, character sets, ...) are dropped.
+#[derive(Default)]
+pub(crate) struct AnsiParser {
+    // The start of an escape sequence left unfinished by the last feed()
+    pending: String
+}
+
+impl AnsiParser {
+    pub(crate) fn new() -> AnsiParser {
+        AnsiParser::default()
+    }
+
+    pub(crate) fn feed(&mut self, text: &str) -> Vec<AnsiToken> {
+        let mut input: Vec<char> = self.pending.chars().collect();
+        input.extend(text.chars());
+        self.pending.clear();
+
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < input.len() {
+            let character = input[i];
+            if character != '\x1B' {
+                if character.is_control() {
+                    tokens.push(AnsiToken::Control(character));
+                } else {
+                    tokens.push(AnsiToken::Character(character));
+                }
+                i += 1;
+                continue;
+            }
+
+            match read_escape(&input[i ..]) {
+                Some((length, token)) => {
+                    if let Some(token) = token {
+                        tokens.push(token);
+                    }
+                    i += length;
+                }
+                None => {
+                    // Wait for the rest of the sequence
+                    self.pending = input[i ..].iter().collect();
+                    break;
+                }
+            }
+        }
+
+        tokens
+    }
+}
+
+// Read an escape sequence at the start of `input`, returning its length and the token it makes, if any.
+// None if the sequence isn't finished.
+fn read_escape(input: &[char]) -> Option<(usize, Option<AnsiToken>)> {
+    match input.get(1)? {
+        '[' => {
+            let mut i = 2;
+            let private = match input.get(i)? {
+                marker @ '<' ..= '?' => {
+                    i += 1;
+                    Some(*marker)
+                }
+                _ => None
+            };
+
+            let mut params: Vec<Vec<u32>> = Vec::new();
+            let mut param: Vec<u32> = vec![0];
+            let mut has_params = false;
+            loop {
+                let character = *input.get(i)?;
+                i += 1;
+                match character {
+                    '0' ..= '9' => {
+                        let part = param.last_mut().unwrap();
+                        *part = part.saturating_mul(10).saturating_add(character as u32 - '0' as u32);
+                        has_params = true;
+                    }
+                    ':' => {
+                        param.push(0);
+                        has_params = true;
+                    }
+                    ';' => {
+                        params.push(std::mem::replace(&mut param, vec![0]));
+                        has_params = true;
+                    }
+                    // Intermediate bytes aren't used by anything that's read
+                    ' ' ..= '/' => {}
+                    '@' ..= '~' => {
+                        if has_params {
+                            params.push(param);
+                        }
+                        return Some((i, Some(AnsiToken::Csi { private, params, final_char: character })));
+                    }
+                    // Malformed, so the sequence is dropped
+                    _ => {
+                        return Some((i - 1, None));
+                    }
+                }
+            }
+        }
+        // Strings (OSC, DCS, APC, PM, SOS), ended by BEL or ESC \
+        ']' | 'P' | '_' | '^' | 'X' => {
+            let mut i = 2;
+            loop {
+                match *input.get(i)? {
+                    '\x07' => {
+                        return Some((i + 1, None));
+                    }
+                    '\x1B' => {
+                        return Some((i + 1 + usize::from(input.get(i + 1)? == &'\\'), None));
+                    }
+                    _ => {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        // Character sets and the like: intermediate bytes, then a final one
+        ' ' ..= '/' => {
+            let mut i = 2;
+            while (' ' ..= '/').contains(input.get(i)?) {
+                i += 1;
+            }
+            Some((i + 1, None))
+        }
+        character => {
+            Some((2, Some(AnsiToken::Escape(*character))))
+        }
+    }
+}
+
+// Read a 256 color or true color after 38 or 48, returning it and how many more parameters it used
+fn read_extended_color(param: &[u32], rest: &[Vec<u32>]) -> (Option<Color>, usize) {
+    let to_u8 = |value: u32| value.min(255) as u8;
+
+    if param.len() > 1 {
+        // 38:5:n, 38:2:r:g:b, or 38:2:colorspace:r:g:b
+        let color = match param[1] {
+            5 => param.get(2).map(|index| Color::Fixed(to_u8(*index))),
+            2 => {
+                let rgb = if param.len() >= 6 { &param[3 .. 6] } else { &param[2 ..] };
+                if rgb.len() >= 3 {
+                    Some(Color::Rgb(to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2])))
+                } else {
+                    None
+                }
+            }
+            _ => None
+        };
+        return (color, 0);
+    }
+
+    // 38;5;n or 38;2;r;g;b
+    let get = |i: usize| rest.get(i).map(|param| param[0]);
+    match get(0) {
+        Some(5) => (get(1).map(|index| Color::Fixed(to_u8(index))), 2.min(rest.len())),
+        Some(2) => {
+            match (get(1), get(2), get(3)) {
+                (Some(red), Some(green), Some(blue)) => (Some(Color::Rgb(to_u8(red), to_u8(green), to_u8(blue))), 4),
+                _ => (None, rest.len())
+            }
+        }
+        _ => (None, 0)
+    }
+}
+
+/// Apply the parameters of an SGR sequence (ESC [ ... m) to a style.
+pub(crate) fn apply_sgr(style: &mut Style, params: &[Vec<u32>]) {
+    if params.is_empty() {
+        *style = Style::default();
+        return;
+    }
+
+    let mut i = 0;
+    while i < params.len() {
+        let param = &params[i];
+        match param[0] {
+            0 => {
+                *style = Style::default();
+            }
+            1 => {
+                style.bold = true;
+            }
+            2 => {
+                style.dim = true;
+            }
+            3 => {
+                style.italics = true;
+            }
+            // 4:0 turns underlining off, and the other styles of underline are all shown the same
+            4 => {
+                style.underline = param.get(1) != Some(&0);
+            }
+            5 | 6 => {
+                style.blink = true;
+            }
+            7 => {
+                style.invert = true;
+            }
+            9 => {
+                style.strike = true;
+            }
+            21 => {
+                style.underline = true;
+            }
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => {
+                style.italics = false;
+            }
+            24 => {
+                style.underline = false;
+            }
+            25 => {
+                style.blink = false;
+            }
+            27 => {
+                style.invert = false;
+            }
+            29 => {
+                style.strike = false;
+            }
+            code @ 30 ..= 37 => {
+                style.foreground_color = Some(Color::NAMED[(code - 30) as usize]);
+            }
+            38 => {
+                let (color, used) = read_extended_color(param, &params[i + 1 ..]);
+                if color.is_some() {
+                    style.foreground_color = color;
+                }
+                i += used;
+            }
+            39 => {
+                style.foreground_color = None;
+            }
+            code @ 40 ..= 47 => {
+                style.background_color = Some(Color::NAMED[(code - 40) as usize]);
+            }
+            48 => {
+                let (color, used) = read_extended_color(param, &params[i + 1 ..]);
+                if color.is_some() {
+                    style.background_color = color;
+                }
+                i += used;
+            }
+            49 => {
+                style.background_color = None;
+            }
+            code @ 90 ..= 97 => {
+                style.foreground_color = Some(Color::NAMED[(code - 90 + 8) as usize]);
+            }
+            code @ 100 ..= 107 => {
+                style.background_color = Some(Color::NAMED[(code - 100 + 8) as usize]);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
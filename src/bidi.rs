@@ -0,0 +1,411 @@
+//! The Unicode bidirectional algorithm, for putting mixed left-to-right and right-to-left (Arabic, Hebrew) text
+//! into the order it's displayed in.
+//!
+//! Character classes cover the common scripts rather than the whole Unicode database, and explicit embedding,
+//! override and isolate controls are ignored.
+
+/// The base direction of a paragraph.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum TextDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+    /// Take the direction from the first strongly directional character, falling back to left-to-right
+    Auto
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum BidiClass {
+    Left,
+    Right,
+    ArabicLetter,
+    EuropeanNumber,
+    EuropeanSeparator,
+    EuropeanTerminator,
+    ArabicNumber,
+    CommonSeparator,
+    NonspacingMark,
+    BoundaryNeutral,
+    ParagraphSeparator,
+    SegmentSeparator,
+    Whitespace,
+    OtherNeutral
+}
+
+use BidiClass::*;
+
+// Paired brackets, also mirrored
+const BRACKETS: [(char, char); 11] = [
+    ('(', ')'), ('[', ']'), ('{', '}'), ('⁅', '⁆'), ('⁽', '⁾'), ('₍', '₎'),
+    ('〈', '〉'), ('⟨', '⟩'), ('⟪', '⟫'), ('《', '》'), ('【', '】')
+];
+
+// Mirrored characters that aren't paired brackets
+const MIRRORED: [(char, char); 8] = [
+    ('<', '>'), ('«', '»'), ('‹', '›'), ('≤', '≥'), ('≪', '≫'), ('⊂', '⊃'), ('⊆', '⊇'), ('∈', '∋')
+];
+
+// Deepest nesting of brackets that's paired, as in the standard
+const MAX_BRACKET_DEPTH: usize = 63;
+
+fn get_class(character: char) -> BidiClass {
+    match character {
+        '0' ..= '9' | '\u{B2}' | '\u{B3}' | '\u{B9}' | '\u{6F0}' ..= '\u{6F9}' | '\u{2070}' | '\u{2074}' ..= '\u{2079}'
+        | '\u{2080}' ..= '\u{2089}' | '\u{FF10}' ..= '\u{FF19}' => EuropeanNumber,
+
+        '+' | '-' | '\u{207A}' | '\u{207B}' | '\u{208A}' | '\u{208B}' | '\u{2212}' | '\u{FF0B}' | '\u{FF0D}' => EuropeanSeparator,
+
+        '#' | '$' | '%' | '\u{A2}' ..= '\u{A5}' | '\u{B0}' | '\u{B1}' | '\u{609}' | '\u{60A}' | '\u{66A}' | '\u{2030}' ..= '\u{2034}'
+        | '\u{20A0}' ..= '\u{20CF}' | '\u{2213}' | '\u{FF03}' ..= '\u{FF05}' => EuropeanTerminator,
+
+        ',' | '.' | '/' | ':' | '\u{A0}' | '\u{60C}' | '\u{202F}' | '\u{2044}' | '\u{FF0C}' | '\u{FF0E}' | '\u{FF0F}' | '\u{FF1A}' => CommonSeparator,
+
+        '\u{600}' ..= '\u{605}' | '\u{660}' ..= '\u{669}' | '\u{66B}' | '\u{66C}' | '\u{6DD}' | '\u{8E2}' => ArabicNumber,
+
+        '\n' | '\r' | '\u{1C}' ..= '\u{1E}' | '\u{85}' | '\u{2029}' => ParagraphSeparator,
+
+        '\t' | '\u{B}' | '\u{1F}' => SegmentSeparator,
+
+        ' ' | '\u{C}' | '\u{1680}' | '\u{2000}' ..= '\u{200A}' | '\u{2028}' | '\u{205F}' | '\u{3000}' => Whitespace,
+
+        '\u{0}' ..= '\u{8}' | '\u{E}' ..= '\u{1B}' | '\u{7F}' ..= '\u{84}' | '\u{86}' ..= '\u{9F}' | '\u{AD}' | '\u{180E}'
+        | '\u{200B}' ..= '\u{200D}' | '\u{2060}' ..= '\u{2064}' | '\u{FEFF}' => BoundaryNeutral,
+
+        // Explicit embeddings, overrides and isolates are treated as if they weren't there
+        '\u{202A}' ..= '\u{202E}' | '\u{2066}' ..= '\u{2069}' => BoundaryNeutral,
+
+        '\u{200E}' => Left,
+        '\u{200F}' => Right,
+        '\u{61C}' => ArabicLetter,
+
+        '\u{300}' ..= '\u{36F}' | '\u{483}' ..= '\u{489}' | '\u{591}' ..= '\u{5BD}' | '\u{5BF}' | '\u{5C1}' | '\u{5C2}' | '\u{5C4}'
+        | '\u{5C5}' | '\u{5C7}' | '\u{610}' ..= '\u{61A}' | '\u{64B}' ..= '\u{65F}' | '\u{670}' | '\u{6D6}' ..= '\u{6DC}'
+        | '\u{6DF}' ..= '\u{6E4}' | '\u{6E7}' | '\u{6E8}' | '\u{6EA}' ..= '\u{6ED}' | '\u{711}' | '\u{730}' ..= '\u{74A}'
+        | '\u{7A6}' ..= '\u{7B0}' | '\u{7EB}' ..= '\u{7F3}' | '\u{816}' ..= '\u{819}' | '\u{81B}' ..= '\u{823}'
+        | '\u{825}' ..= '\u{827}' | '\u{829}' ..= '\u{82D}' | '\u{859}' ..= '\u{85B}' | '\u{8D3}' ..= '\u{8E1}'
+        | '\u{8E3}' ..= '\u{8FF}' | '\u{1AB0}' ..= '\u{1AFF}' | '\u{1DC0}' ..= '\u{1DFF}' | '\u{20D0}' ..= '\u{20F0}' | '\u{FB1E}'
+        | '\u{FE00}' ..= '\u{FE0F}' | '\u{FE20}' ..= '\u{FE2F}' => NonspacingMark,
+
+        // Hebrew, NKo, Samaritan, Mandaic and the historic right-to-left scripts
+        '\u{590}' ..= '\u{5FF}' | '\u{7C0}' ..= '\u{85F}' | '\u{FB1D}' ..= '\u{FB4F}' | '\u{10800}' ..= '\u{10FFF}'
+        | '\u{1E800}' ..= '\u{1EC6F}' => Right,
+
+        // Arabic, Syriac and Thaana
+        '\u{600}' ..= '\u{7BF}' | '\u{860}' ..= '\u{8FF}' | '\u{FB50}' ..= '\u{FDFF}' | '\u{FE70}' ..= '\u{FEFE}'
+        | '\u{1EC70}' ..= '\u{1EEFF}' => ArabicLetter,
+
+        // Letter-like symbols, roman numerals and braille among the symbol blocks
+        '\u{2102}' | '\u{2107}' | '\u{210A}' ..= '\u{2113}' | '\u{2115}' | '\u{2119}' ..= '\u{211D}' | '\u{2124}' | '\u{2126}'
+        | '\u{2128}' | '\u{212A}' ..= '\u{212D}' | '\u{212F}' ..= '\u{2139}' | '\u{213C}' ..= '\u{213F}' | '\u{2145}' ..= '\u{2149}'
+        | '\u{214E}' | '\u{2160}' ..= '\u{2188}' | '\u{249C}' ..= '\u{24E9}' | '\u{2800}' ..= '\u{28FF}' => Left,
+
+        '!' | '"' | '&' | '\'' | '(' | ')' | '*' | ';' ..= '@' | '[' ..= '`' | '{' ..= '~' | '\u{A1}' | '\u{A6}' ..= '\u{A9}'
+        | '\u{AB}' | '\u{AC}' | '\u{AE}' | '\u{AF}' | '\u{B4}' | '\u{B6}' ..= '\u{B8}' | '\u{BB}' ..= '\u{BF}' | '\u{D7}' | '\u{F7}'
+        | '\u{2010}' ..= '\u{2027}' | '\u{2035}' ..= '\u{2043}' | '\u{2045}' ..= '\u{205E}' | '\u{207C}' ..= '\u{207E}'
+        | '\u{208C}' ..= '\u{208E}' | '\u{2100}' ..= '\u{2BFF}' | '\u{2E00}' ..= '\u{2E7F}' | '\u{3001}' ..= '\u{3004}'
+        | '\u{3008}' ..= '\u{3020}' | '\u{FE50}' ..= '\u{FE6F}' | '\u{FF01}' ..= '\u{FF0F}' | '\u{FF1B}' ..= '\u{FF20}' => OtherNeutral,
+
+        _ => Left
+    }
+}
+
+fn get_mirrored(character: char) -> Option<char> {
+    for (left, right) in BRACKETS.iter().chain(MIRRORED.iter()) {
+        if character == *left {
+            return Some(*right);
+        }
+        if character == *right {
+            return Some(*left);
+        }
+    }
+    None
+}
+
+// The direction a class counts as next to neutrals, with numbers counting as right-to-left
+fn get_strong(class: BidiClass) -> Option<BidiClass> {
+    match class {
+        Left => Some(Left),
+        Right | ArabicLetter | EuropeanNumber | ArabicNumber => Some(Right),
+        _ => None
+    }
+}
+
+fn is_neutral(class: BidiClass) -> bool {
+    matches!(class, ParagraphSeparator | SegmentSeparator | Whitespace | OtherNeutral)
+}
+
+/// Get the direction of the first strongly directional character, if there is one.
+/// # Example
+/// ```
+/// use wrecked::bidi::{get_base_direction, TextDirection};
+/// assert_eq!(get_base_direction("123 שלום abc"), Some(TextDirection::RightToLeft));
+/// assert_eq!(get_base_direction("(42)"), None);
+/// ```
+pub fn get_base_direction(text: &str) -> Option<TextDirection> {
+    for character in text.chars() {
+        match get_class(character) {
+            Left => {
+                return Some(TextDirection::LeftToRight);
+            }
+            Right | ArabicLetter => {
+                return Some(TextDirection::RightToLeft);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Check if a paragraph is laid out right-to-left in the given direction.
+pub(crate) fn is_rtl(direction: TextDirection, text: &[char]) -> bool {
+    match direction {
+        TextDirection::LeftToRight => false,
+        TextDirection::RightToLeft => true,
+        TextDirection::Auto => {
+            let text: String = text.iter().collect();
+            get_base_direction(&text) == Some(TextDirection::RightToLeft)
+        }
+    }
+}
+
+// Resolve the embedding level of each character of a line
+fn get_levels(text: &[char], rtl: bool) -> Vec<u8> {
+    let base: u8 = if rtl { 1 } else { 0 };
+    let embedding = if rtl { Right } else { Left };
+    let original: Vec<BidiClass> = text.iter().map(|character| get_class(*character)).collect();
+    let mut classes = original.clone();
+
+    // Boundary neutrals are left out of resolving the rest
+    let indices: Vec<usize> = (0 .. text.len()).filter(|i| original[*i] != BoundaryNeutral).collect();
+    let count = indices.len();
+
+    // W1: Marks take the class of what they're on
+    let mut previous = embedding;
+    for i in indices.iter() {
+        if classes[*i] == NonspacingMark {
+            classes[*i] = previous;
+        }
+        previous = classes[*i];
+    }
+
+    // W2, W3: Numbers after Arabic letters are Arabic numbers, and Arabic letters are right-to-left
+    let mut last_strong = embedding;
+    for i in indices.iter() {
+        match classes[*i] {
+            Left | Right => {
+                last_strong = classes[*i];
+            }
+            ArabicLetter => {
+                last_strong = ArabicLetter;
+                classes[*i] = Right;
+            }
+            EuropeanNumber if last_strong == ArabicLetter => {
+                classes[*i] = ArabicNumber;
+            }
+            _ => {}
+        }
+    }
+
+    // W4: A single separator between two numbers of the same kind joins them
+    for k in 1 .. count.saturating_sub(1) {
+        let before = classes[indices[k - 1]];
+        let after = classes[indices[k + 1]];
+        let i = indices[k];
+        match classes[i] {
+            EuropeanSeparator if before == EuropeanNumber && after == EuropeanNumber => {
+                classes[i] = EuropeanNumber;
+            }
+            CommonSeparator if before == after && (before == EuropeanNumber || before == ArabicNumber) => {
+                classes[i] = before;
+            }
+            _ => {}
+        }
+    }
+
+    // W5: Terminators next to European numbers are part of them
+    let mut k = 0;
+    while k < count {
+        if classes[indices[k]] != EuropeanTerminator {
+            k += 1;
+            continue;
+        }
+        let start = k;
+        while k < count && classes[indices[k]] == EuropeanTerminator {
+            k += 1;
+        }
+        let touches_number = (start > 0 && classes[indices[start - 1]] == EuropeanNumber)
+            || (k < count && classes[indices[k]] == EuropeanNumber);
+        if touches_number {
+            for i in indices[start .. k].iter() {
+                classes[*i] = EuropeanNumber;
+            }
+        }
+    }
+
+    // W6: Any other separators and terminators are neutral
+    // W7: European numbers in left-to-right text are left-to-right
+    let mut last_strong = embedding;
+    for i in indices.iter() {
+        match classes[*i] {
+            EuropeanSeparator | EuropeanTerminator | CommonSeparator => {
+                classes[*i] = OtherNeutral;
+            }
+            Left | Right => {
+                last_strong = classes[*i];
+            }
+            EuropeanNumber if last_strong == Left => {
+                classes[*i] = Left;
+            }
+            _ => {}
+        }
+    }
+
+    // N0: Paired brackets take the direction of what they enclose, or of what's around them
+    let mut pairs = Vec::new();
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    for (k, i) in indices.iter().copied().enumerate() {
+        if classes[i] != OtherNeutral {
+            continue;
+        }
+        if let Some((_, closing)) = BRACKETS.iter().find(|(opening, _)| *opening == text[i]) {
+            if stack.len() == MAX_BRACKET_DEPTH {
+                break;
+            }
+            stack.push((*closing, k));
+        } else if let Some(depth) = stack.iter().rposition(|(closing, _)| *closing == text[i]) {
+            pairs.push((stack[depth].1, k));
+            stack.truncate(depth);
+        }
+    }
+    pairs.sort();
+
+    let opposite = if rtl { Left } else { Right };
+    for (opening, closing) in pairs {
+        let inside: Vec<BidiClass> = indices[opening + 1 .. closing].iter().filter_map(|i| get_strong(classes[*i])).collect();
+        let direction = if inside.contains(&embedding) {
+            embedding
+        } else if inside.contains(&opposite) {
+            let context = indices[.. opening].iter().rev().find_map(|i| get_strong(classes[*i])).unwrap_or(embedding);
+            if context == opposite { opposite } else { embedding }
+        } else {
+            continue;
+        };
+
+        for bracket in [opening, closing] {
+            classes[indices[bracket]] = direction;
+            // Marks on the bracket follow it
+            for i in indices[bracket + 1 ..].iter() {
+                if original[*i] != NonspacingMark {
+                    break;
+                }
+                classes[*i] = direction;
+            }
+        }
+    }
+
+    // N1, N2: Neutrals between text of the same direction take it, any others take the paragraph's
+    let mut k = 0;
+    while k < count {
+        if !is_neutral(classes[indices[k]]) {
+            k += 1;
+            continue;
+        }
+        let start = k;
+        while k < count && is_neutral(classes[indices[k]]) {
+            k += 1;
+        }
+        let before = if start > 0 { get_strong(classes[indices[start - 1]]).unwrap_or(embedding) } else { embedding };
+        let after = if k < count { get_strong(classes[indices[k]]).unwrap_or(embedding) } else { embedding };
+        let direction = if before == after { before } else { embedding };
+        for i in indices[start .. k].iter() {
+            classes[*i] = direction;
+        }
+    }
+
+    // I1, I2: Raise the levels of text against the paragraph's direction
+    let mut levels = vec![base; text.len()];
+    let mut level = base;
+    for (i, class) in classes.iter().enumerate() {
+        if original[i] == BoundaryNeutral {
+            // Boundary neutrals go with whatever they follow
+            levels[i] = level;
+            continue;
+        }
+        level = match (rtl, class) {
+            (false, Right) => base + 1,
+            (false, EuropeanNumber) | (false, ArabicNumber) => base + 2,
+            (true, Left) | (true, EuropeanNumber) | (true, ArabicNumber) => base + 1,
+            _ => base
+        };
+        levels[i] = level;
+    }
+
+    // L1: Separators, and whitespace before them or at the end of the line, go back to the paragraph's level
+    let mut trailing = true;
+    for i in (0 .. text.len()).rev() {
+        match original[i] {
+            SegmentSeparator | ParagraphSeparator => {
+                levels[i] = base;
+                trailing = true;
+            }
+            Whitespace | BoundaryNeutral => {
+                if trailing {
+                    levels[i] = base;
+                }
+            }
+            _ => {
+                trailing = false;
+            }
+        }
+    }
+
+    levels
+}
+
+/// Put a line of text into visual order, mirroring brackets and the like in right-to-left runs.
+pub(crate) fn reorder_chars(text: &[char], rtl: bool) -> Vec<char> {
+    let levels = get_levels(text, rtl);
+    let mut cells: Vec<(char, u8)> = text.iter().copied().zip(levels.iter().copied()).collect();
+
+    // L4
+    for (character, level) in cells.iter_mut() {
+        if *level % 2 == 1 {
+            if let Some(mirrored) = get_mirrored(*character) {
+                *character = mirrored;
+            }
+        }
+    }
+
+    // L2: From the highest level down to the lowest odd one, reverse every run at that level or above
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels.iter().copied().filter(|level| level % 2 == 1).min().unwrap_or(highest + 1);
+    for level in (lowest_odd ..= highest).rev() {
+        let mut k = 0;
+        while k < cells.len() {
+            if cells[k].1 < level {
+                k += 1;
+                continue;
+            }
+            let start = k;
+            while k < cells.len() && cells[k].1 >= level {
+                k += 1;
+            }
+            cells[start .. k].reverse();
+        }
+    }
+
+    cells.into_iter().map(|(character, _)| character).collect()
+}
+
+/// Put a line of text in logical order into the order it's displayed in, left to right.
+/// # Example
+/// ```
+/// use wrecked::bidi::{reorder, TextDirection};
+/// assert_eq!(reorder("abc אבג", TextDirection::LeftToRight), "abc גבא");
+/// assert_eq!(reorder("אבג (abc)", TextDirection::RightToLeft), "(abc) גבא");
+/// assert_eq!(reorder("שלום 123", TextDirection::Auto), "123 םולש");
+/// ```
+pub fn reorder(text: &str, direction: TextDirection) -> String {
+    let text: Vec<char> = text.chars().collect();
+    reorder_chars(&text, is_rtl(direction, &text)).into_iter().collect()
+}
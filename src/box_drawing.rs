@@ -0,0 +1,198 @@
+// Arm weights used to describe box-drawing characters
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+// (character, (up, right, down, left))
+// Arcs and half-lines are only ever read from; when a merge is done, the
+// first matching glyph in this table is the one that gets written, so the
+// plain lines and junctions need to come before them.
+const BOX_CHARACTERS: [(char, (u8, u8, u8, u8)); 125] = [
+    ('─', (NONE, LIGHT, NONE, LIGHT)),
+    ('━', (NONE, HEAVY, NONE, HEAVY)),
+    ('│', (LIGHT, NONE, LIGHT, NONE)),
+    ('┃', (HEAVY, NONE, HEAVY, NONE)),
+    ('┌', (NONE, LIGHT, LIGHT, NONE)),
+    ('┍', (NONE, HEAVY, LIGHT, NONE)),
+    ('┎', (NONE, LIGHT, HEAVY, NONE)),
+    ('┏', (NONE, HEAVY, HEAVY, NONE)),
+    ('┐', (NONE, NONE, LIGHT, LIGHT)),
+    ('┑', (NONE, NONE, LIGHT, HEAVY)),
+    ('┒', (NONE, NONE, HEAVY, LIGHT)),
+    ('┓', (NONE, NONE, HEAVY, HEAVY)),
+    ('└', (LIGHT, LIGHT, NONE, NONE)),
+    ('┕', (LIGHT, HEAVY, NONE, NONE)),
+    ('┖', (HEAVY, LIGHT, NONE, NONE)),
+    ('┗', (HEAVY, HEAVY, NONE, NONE)),
+    ('┘', (LIGHT, NONE, NONE, LIGHT)),
+    ('┙', (LIGHT, NONE, NONE, HEAVY)),
+    ('┚', (HEAVY, NONE, NONE, LIGHT)),
+    ('┛', (HEAVY, NONE, NONE, HEAVY)),
+    ('├', (LIGHT, LIGHT, LIGHT, NONE)),
+    ('┝', (LIGHT, HEAVY, LIGHT, NONE)),
+    ('┞', (HEAVY, LIGHT, LIGHT, NONE)),
+    ('┟', (LIGHT, LIGHT, HEAVY, NONE)),
+    ('┠', (HEAVY, LIGHT, HEAVY, NONE)),
+    ('┡', (HEAVY, HEAVY, LIGHT, NONE)),
+    ('┢', (LIGHT, HEAVY, HEAVY, NONE)),
+    ('┣', (HEAVY, HEAVY, HEAVY, NONE)),
+    ('┤', (LIGHT, NONE, LIGHT, LIGHT)),
+    ('┥', (LIGHT, NONE, LIGHT, HEAVY)),
+    ('┦', (HEAVY, NONE, LIGHT, LIGHT)),
+    ('┧', (LIGHT, NONE, HEAVY, LIGHT)),
+    ('┨', (HEAVY, NONE, HEAVY, LIGHT)),
+    ('┩', (HEAVY, NONE, LIGHT, HEAVY)),
+    ('┪', (LIGHT, NONE, HEAVY, HEAVY)),
+    ('┫', (HEAVY, NONE, HEAVY, HEAVY)),
+    ('┬', (NONE, LIGHT, LIGHT, LIGHT)),
+    ('┭', (NONE, LIGHT, LIGHT, HEAVY)),
+    ('┮', (NONE, HEAVY, LIGHT, LIGHT)),
+    ('┯', (NONE, HEAVY, LIGHT, HEAVY)),
+    ('┰', (NONE, LIGHT, HEAVY, LIGHT)),
+    ('┱', (NONE, LIGHT, HEAVY, HEAVY)),
+    ('┲', (NONE, HEAVY, HEAVY, LIGHT)),
+    ('┳', (NONE, HEAVY, HEAVY, HEAVY)),
+    ('┴', (LIGHT, LIGHT, NONE, LIGHT)),
+    ('┵', (LIGHT, LIGHT, NONE, HEAVY)),
+    ('┶', (LIGHT, HEAVY, NONE, LIGHT)),
+    ('┷', (LIGHT, HEAVY, NONE, HEAVY)),
+    ('┸', (HEAVY, LIGHT, NONE, LIGHT)),
+    ('┹', (HEAVY, LIGHT, NONE, HEAVY)),
+    ('┺', (HEAVY, HEAVY, NONE, LIGHT)),
+    ('┻', (HEAVY, HEAVY, NONE, HEAVY)),
+    ('┼', (LIGHT, LIGHT, LIGHT, LIGHT)),
+    ('┽', (LIGHT, LIGHT, LIGHT, HEAVY)),
+    ('┾', (LIGHT, HEAVY, LIGHT, LIGHT)),
+    ('┿', (LIGHT, HEAVY, LIGHT, HEAVY)),
+    ('╀', (HEAVY, LIGHT, LIGHT, LIGHT)),
+    ('╁', (LIGHT, LIGHT, HEAVY, LIGHT)),
+    ('╂', (HEAVY, LIGHT, HEAVY, LIGHT)),
+    ('╃', (HEAVY, LIGHT, LIGHT, HEAVY)),
+    ('╄', (HEAVY, HEAVY, LIGHT, LIGHT)),
+    ('╅', (LIGHT, LIGHT, HEAVY, HEAVY)),
+    ('╆', (LIGHT, HEAVY, HEAVY, LIGHT)),
+    ('╇', (HEAVY, HEAVY, LIGHT, HEAVY)),
+    ('╈', (LIGHT, HEAVY, HEAVY, HEAVY)),
+    ('╉', (HEAVY, LIGHT, HEAVY, HEAVY)),
+    ('╊', (HEAVY, HEAVY, HEAVY, LIGHT)),
+    ('╋', (HEAVY, HEAVY, HEAVY, HEAVY)),
+    ('═', (NONE, DOUBLE, NONE, DOUBLE)),
+    ('║', (DOUBLE, NONE, DOUBLE, NONE)),
+    ('╒', (NONE, DOUBLE, LIGHT, NONE)),
+    ('╓', (NONE, LIGHT, DOUBLE, NONE)),
+    ('╔', (NONE, DOUBLE, DOUBLE, NONE)),
+    ('╕', (NONE, NONE, LIGHT, DOUBLE)),
+    ('╖', (NONE, NONE, DOUBLE, LIGHT)),
+    ('╗', (NONE, NONE, DOUBLE, DOUBLE)),
+    ('╘', (LIGHT, DOUBLE, NONE, NONE)),
+    ('╙', (DOUBLE, LIGHT, NONE, NONE)),
+    ('╚', (DOUBLE, DOUBLE, NONE, NONE)),
+    ('╛', (LIGHT, NONE, NONE, DOUBLE)),
+    ('╜', (DOUBLE, NONE, NONE, LIGHT)),
+    ('╝', (DOUBLE, NONE, NONE, DOUBLE)),
+    ('╞', (LIGHT, DOUBLE, LIGHT, NONE)),
+    ('╟', (DOUBLE, LIGHT, DOUBLE, NONE)),
+    ('╠', (DOUBLE, DOUBLE, DOUBLE, NONE)),
+    ('╡', (LIGHT, NONE, LIGHT, DOUBLE)),
+    ('╢', (DOUBLE, NONE, DOUBLE, LIGHT)),
+    ('╣', (DOUBLE, NONE, DOUBLE, DOUBLE)),
+    ('╤', (NONE, DOUBLE, LIGHT, DOUBLE)),
+    ('╥', (NONE, LIGHT, DOUBLE, LIGHT)),
+    ('╦', (NONE, DOUBLE, DOUBLE, DOUBLE)),
+    ('╧', (LIGHT, DOUBLE, NONE, DOUBLE)),
+    ('╨', (DOUBLE, LIGHT, NONE, LIGHT)),
+    ('╩', (DOUBLE, DOUBLE, NONE, DOUBLE)),
+    ('╪', (LIGHT, DOUBLE, LIGHT, DOUBLE)),
+    ('╫', (DOUBLE, LIGHT, DOUBLE, LIGHT)),
+    ('╬', (DOUBLE, DOUBLE, DOUBLE, DOUBLE)),
+    ('╭', (NONE, LIGHT, LIGHT, NONE)),
+    ('╮', (NONE, NONE, LIGHT, LIGHT)),
+    ('╯', (LIGHT, NONE, NONE, LIGHT)),
+    ('╰', (LIGHT, LIGHT, NONE, NONE)),
+    ('╴', (NONE, NONE, NONE, LIGHT)),
+    ('╵', (LIGHT, NONE, NONE, NONE)),
+    ('╶', (NONE, LIGHT, NONE, NONE)),
+    ('╷', (NONE, NONE, LIGHT, NONE)),
+    ('╸', (NONE, NONE, NONE, HEAVY)),
+    ('╹', (HEAVY, NONE, NONE, NONE)),
+    ('╺', (NONE, HEAVY, NONE, NONE)),
+    ('╻', (NONE, NONE, HEAVY, NONE)),
+    ('╼', (NONE, HEAVY, NONE, LIGHT)),
+    ('╽', (LIGHT, NONE, HEAVY, NONE)),
+    ('╾', (NONE, LIGHT, NONE, HEAVY)),
+    ('╿', (HEAVY, NONE, LIGHT, NONE)),
+    ('┄', (NONE, LIGHT, NONE, LIGHT)),
+    ('┅', (NONE, HEAVY, NONE, HEAVY)),
+    ('┆', (LIGHT, NONE, LIGHT, NONE)),
+    ('┇', (HEAVY, NONE, HEAVY, NONE)),
+    ('┈', (NONE, LIGHT, NONE, LIGHT)),
+    ('┉', (NONE, HEAVY, NONE, HEAVY)),
+    ('┊', (LIGHT, NONE, LIGHT, NONE)),
+    ('┋', (HEAVY, NONE, HEAVY, NONE)),
+    ('╌', (NONE, LIGHT, NONE, LIGHT)),
+    ('╍', (NONE, HEAVY, NONE, HEAVY)),
+    ('╎', (LIGHT, NONE, LIGHT, NONE)),
+    ('╏', (HEAVY, NONE, HEAVY, NONE))
+];
+
+fn get_arms(character: char) -> Option<(u8, u8, u8, u8)> {
+    BOX_CHARACTERS.iter()
+        .find(|(c, _)| *c == character)
+        .map(|(_, arms)| *arms)
+}
+
+fn get_box_character(arms: (u8, u8, u8, u8)) -> Option<char> {
+    BOX_CHARACTERS.iter()
+        .find(|(_, a)| *a == arms)
+        .map(|(c, _)| *c)
+}
+
+/// Check if the character is one that can be joined with other box-drawing characters.
+pub fn is_box_character(character: char) -> bool {
+    get_arms(character).is_some()
+}
+
+/// Combine two overlapping box-drawing characters into a single junction.
+/// Where both characters have an arm in the same direction, the arm of the character on top wins.
+/// Returns None if either character isn't a box-drawing character.
+pub fn merge_box_characters(top: char, bottom: char) -> Option<char> {
+    let top_arms = get_arms(top)?;
+    let bottom_arms = get_arms(bottom)?;
+
+    let pick = |a: u8, b: u8| if a != NONE { a } else { b };
+    let merged = (
+        pick(top_arms.0, bottom_arms.0),
+        pick(top_arms.1, bottom_arms.1),
+        pick(top_arms.2, bottom_arms.2),
+        pick(top_arms.3, bottom_arms.3)
+    );
+
+    if merged == top_arms {
+        return Some(top);
+    }
+
+    match get_box_character(merged) {
+        Some(character) => {
+            Some(character)
+        }
+        None => {
+            // There are no glyphs that mix double and heavy lines,
+            // so redraw the arms in the weight of the top character.
+            let weight = [top_arms.0, top_arms.1, top_arms.2, top_arms.3].iter()
+                .copied()
+                .find(|w| *w != NONE)
+                .unwrap_or(LIGHT);
+
+            let restyle = |a: u8| if a != NONE { weight } else { NONE };
+            let restyled = (
+                restyle(merged.0),
+                restyle(merged.1),
+                restyle(merged.2),
+                restyle(merged.3)
+            );
+
+            Some(get_box_character(restyled).unwrap_or(top))
+        }
+    }
+}
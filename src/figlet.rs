@@ -0,0 +1,353 @@
+//! FIGlet fonts, for drawing large banner text out of ordinary characters.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::WreckedError;
+
+/// How the characters of a banner are fitted together horizontally.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FigLayout {
+    /// Each character keeps its full width
+    FullWidth,
+    /// Characters are moved together until they touch
+    Kerning,
+    /// Characters are moved together until they overlap by one column, merging the overlapping characters
+    /// with the font's smushing rules
+    Smushing
+}
+
+// Horizontal smushing rules, from the font's layout
+const SMUSH_EQUAL: u8 = 1;
+const SMUSH_UNDERSCORE: u8 = 2;
+const SMUSH_HIERARCHY: u8 = 4;
+const SMUSH_PAIR: u8 = 8;
+const SMUSH_BIG_X: u8 = 16;
+const SMUSH_HARDBLANK: u8 = 32;
+
+// Characters that every font defines after the printable ASCII ones: Ä Ö Ü ä ö ü ß
+const DEUTSCH_CODES: [u32; 7] = [196, 214, 220, 228, 246, 252, 223];
+
+/// A font in the FIGlet (.flf) format.
+/// # Example
+/// ```
+/// use wrecked::figlet::{FigFont, FigLayout};
+///
+/// let mut font = FigFont::small();
+/// assert_eq!(font.render("Hi"), vec![
+///     "█ █ ▀█▀ ",
+///     "█▀█  █  ",
+///     "▀ ▀ ▀▀▀ "
+/// ]);
+///
+/// font.set_layout(FigLayout::Kerning);
+/// assert_eq!(font.measure("Hi"), (7, 3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FigFont {
+    hardblank: char,
+    height: usize,
+    baseline: usize,
+    layout: FigLayout,
+    smush_rules: u8,
+    characters: HashMap<char, Vec<Vec<char>>>
+}
+
+impl FigFont {
+    /// Parse a font from the contents of a .flf file.
+    pub fn from_bytes(data: &[u8]) -> Result<FigFont, WreckedError> {
+        let text = String::from_utf8_lossy(data);
+        let mut lines = text.lines();
+
+        let header = lines.next().unwrap_or("");
+        if !header.starts_with("flf2a") {
+            return Err(WreckedError::BadFont("Not a FIGlet font".to_string()));
+        }
+        let hardblank = header.chars().nth(5).unwrap_or('$');
+        let fields: Vec<i64> = header.split_whitespace()
+            .skip(1)
+            .map(|field| field.parse().map_err(|_| WreckedError::BadFont(format!("Bad header field {:?}", field))))
+            .collect::<Result<_, _>>()?;
+        if fields.len() < 5 {
+            return Err(WreckedError::BadFont("Header is missing fields".to_string()));
+        }
+
+        let height = fields[0].max(1) as usize;
+        // Every character takes `height` lines, so a taller height can't be right
+        if height > text.lines().count() {
+            return Err(WreckedError::BadFont(format!("Height {} is taller than the font", height)));
+        }
+        let baseline = fields[1].clamp(1, height as i64) as usize;
+        let (layout, smush_rules) = match fields.get(6) {
+            Some(full_layout) => {
+                let rules = (full_layout & 63) as u8;
+                if full_layout & 128 != 0 {
+                    (FigLayout::Smushing, rules)
+                } else if full_layout & 64 != 0 {
+                    (FigLayout::Kerning, rules)
+                } else {
+                    (FigLayout::FullWidth, rules)
+                }
+            }
+            None => {
+                match fields[3] {
+                    layout if layout < 0 => (FigLayout::FullWidth, 0),
+                    0 => (FigLayout::Kerning, 0),
+                    layout => (FigLayout::Smushing, (layout & 63) as u8)
+                }
+            }
+        };
+
+        // Stops at the end of the font, however many comment lines the header claims
+        lines.by_ref().take(fields[4].max(0) as usize).for_each(drop);
+
+        let mut font = FigFont {
+            hardblank,
+            height,
+            baseline,
+            layout,
+            smush_rules,
+            characters: HashMap::new()
+        };
+
+        // The required characters are in order, without tags
+        for code in (32 .. 127).chain(DEUTSCH_CODES) {
+            match font.read_character(&mut lines) {
+                Some(rows) => {
+                    if let Some(character) = char::from_u32(code) {
+                        font.characters.insert(character, rows);
+                    }
+                }
+                None => {
+                    return Ok(font);
+                }
+            }
+        }
+
+        // Any others are each preceded by a line starting with their code
+        while let Some(line) = lines.next() {
+            let tag = line.split_whitespace().next().unwrap_or("");
+            let code = if let Some(hex) = tag.strip_prefix("0x").or_else(|| tag.strip_prefix("0X")) {
+                u32::from_str_radix(hex, 16).ok()
+            } else if tag.len() > 1 && tag.starts_with('0') {
+                u32::from_str_radix(&tag[1 ..], 8).ok()
+            } else {
+                // Negative codes aren't Unicode characters, so they're read past
+                tag.parse::<u32>().ok()
+            };
+
+            match font.read_character(&mut lines) {
+                Some(rows) => {
+                    if let Some(character) = code.and_then(char::from_u32) {
+                        font.characters.insert(character, rows);
+                    }
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        Ok(font)
+    }
+
+    /// Load a .flf font file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<FigFont, WreckedError> {
+        match fs::read(path.as_ref()) {
+            Ok(data) => FigFont::from_bytes(&data),
+            Err(error) => Err(WreckedError::BadFont(format!("Couldn't read {}: {}", path.as_ref().display(), error)))
+        }
+    }
+
+    /// A built-in font of full blocks, 5 rows high.
+    pub fn block() -> FigFont {
+        FigFont::from_bytes(include_bytes!("fonts/block.flf")).expect("Built-in font is valid")
+    }
+
+    /// A built-in font of half blocks, 3 rows high.
+    pub fn small() -> FigFont {
+        FigFont::from_bytes(include_bytes!("fonts/small.flf")).expect("Built-in font is valid")
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the number of rows from the top of a character to the baseline.
+    pub fn get_baseline(&self) -> usize {
+        self.baseline
+    }
+
+    pub fn get_layout(&self) -> FigLayout {
+        self.layout
+    }
+
+    /// Override the font's layout. Smushing uses the font's own rules, or overlaps characters if it has none.
+    pub fn set_layout(&mut self, layout: FigLayout) {
+        self.layout = layout;
+    }
+
+    pub fn has_character(&self, character: char) -> bool {
+        self.characters.contains_key(&character)
+    }
+
+    /// Get the rows of a banner. Each line of `text` is rendered below the previous one, and characters
+    /// the font doesn't have are left out.
+    pub fn render(&self, text: &str) -> Vec<String> {
+        let mut rows = Vec::new();
+        for line in text.split('\n') {
+            for row in self.render_line(line) {
+                rows.push(row.into_iter().map(|character| if character == self.hardblank { ' ' } else { character }).collect());
+            }
+        }
+        rows
+    }
+
+    /// Get the width and height of a banner.
+    pub fn measure(&self, text: &str) -> (usize, usize) {
+        let rows = self.render(text);
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        (width, rows.len())
+    }
+
+    // Render a single line of text, keeping the hardblanks
+    fn render_line(&self, line: &str) -> Vec<Vec<char>> {
+        let mut output: Vec<Vec<char>> = vec![Vec::new(); self.height];
+        let mut previous_width = 0;
+
+        for character in line.chars() {
+            let glyph = match self.characters.get(&character) {
+                Some(glyph) => glyph,
+                None => {
+                    continue;
+                }
+            };
+            let width = glyph.iter().map(|row| row.len()).max().unwrap_or(0);
+
+            let overlap = if output[0].is_empty() || self.layout == FigLayout::FullWidth {
+                0
+            } else {
+                self.get_overlap(&output, glyph, previous_width, width)
+            };
+
+            for (row, glyph_row) in output.iter_mut().zip(glyph.iter()) {
+                let start = row.len() - overlap;
+                for (offset, right) in glyph_row.iter().take(overlap).enumerate() {
+                    let left = row[start + offset];
+                    row[start + offset] = self.smush(left, *right, previous_width, width).unwrap_or(*right);
+                }
+                row.extend(glyph_row.iter().skip(overlap));
+            }
+            previous_width = width;
+        }
+
+        output
+    }
+
+    // Get how many columns a character can be moved into the end of the output
+    fn get_overlap(&self, output: &[Vec<char>], glyph: &[Vec<char>], previous_width: usize, width: usize) -> usize {
+        let mut overlap = width;
+        for (row, glyph_row) in output.iter().zip(glyph.iter()) {
+            // The last visible character of the output, and the first of the glyph
+            let end = row.iter().rposition(|character| *character != ' ');
+            let start = glyph_row.iter().position(|character| *character != ' ').unwrap_or(glyph_row.len());
+
+            let mut amount = start + row.len() - 1 - end.unwrap_or(0);
+            match (end, glyph_row.get(start)) {
+                (None, _) => {
+                    amount += 1;
+                }
+                (Some(end), Some(right)) => {
+                    if self.smush(row[end], *right, previous_width, width).is_some() {
+                        amount += 1;
+                    }
+                }
+                (Some(_), None) => {}
+            }
+            overlap = overlap.min(amount);
+        }
+        overlap.min(output[0].len())
+    }
+
+    // Merge two overlapping characters, or None if they can't be merged
+    fn smush(&self, left: char, right: char, previous_width: usize, width: usize) -> Option<char> {
+        if left == ' ' {
+            return Some(right);
+        }
+        if right == ' ' {
+            return Some(left);
+        }
+        if self.layout != FigLayout::Smushing || previous_width < 2 || width < 2 {
+            return None;
+        }
+
+        let hardblank = self.hardblank;
+        if self.smush_rules == 0 {
+            // Universal smushing: the later character wins, except over a hardblank's neighbour
+            return if right == hardblank { Some(left) } else { Some(right) };
+        }
+
+        if left == hardblank || right == hardblank {
+            return if left == right && self.smush_rules & SMUSH_HARDBLANK != 0 { Some(hardblank) } else { None };
+        }
+        if self.smush_rules & SMUSH_EQUAL != 0 && left == right {
+            return Some(left);
+        }
+        if self.smush_rules & SMUSH_UNDERSCORE != 0 {
+            let replacements = "|/\\[]{}()<>";
+            if left == '_' && replacements.contains(right) {
+                return Some(right);
+            }
+            if right == '_' && replacements.contains(left) {
+                return Some(left);
+            }
+        }
+        if self.smush_rules & SMUSH_HIERARCHY != 0 {
+            let classes = ["|", "/\\", "[]", "{}", "()", "<>"];
+            let class = |character: char| classes.iter().position(|class| class.contains(character));
+            if let (Some(left_class), Some(right_class)) = (class(left), class(right)) {
+                if left_class > right_class {
+                    return Some(left);
+                }
+                if right_class > left_class {
+                    return Some(right);
+                }
+            }
+        }
+        if self.smush_rules & SMUSH_PAIR != 0 {
+            let pairs = ["[]", "][", "{}", "}{", "()", ")("];
+            if pairs.iter().any(|pair| pair.starts_with(left) && pair.ends_with(right)) {
+                return Some('|');
+            }
+        }
+        if self.smush_rules & SMUSH_BIG_X != 0 {
+            match (left, right) {
+                ('/', '\\') => return Some('|'),
+                ('\\', '/') => return Some('Y'),
+                ('>', '<') => return Some('X'),
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    // Read the rows of a character, without their endmarks. None if the font ends first.
+    fn read_character<'a, I: Iterator<Item = &'a str>>(&self, lines: &mut I) -> Option<Vec<Vec<char>>> {
+        let mut rows = Vec::with_capacity(self.height);
+        for _ in 0 .. self.height {
+            let line = lines.next()?.trim_end();
+            let endmark = line.chars().last();
+            let row: Vec<char> = match endmark {
+                Some(endmark) => line.trim_end_matches(endmark).chars().collect(),
+                None => Vec::new()
+            };
+            rows.push(row);
+        }
+
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, ' ');
+        }
+        Some(rows)
+    }
+}
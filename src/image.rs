@@ -0,0 +1,538 @@
+//! Raster images, drawn into rects two pixels to a cell with half blocks.
+use std::cmp;
+use crate::{Color, WreckedError};
+
+/// How an image is scaled to fit the rect it's drawn in.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum FitMode {
+    /// Fill the rect, ignoring the image's aspect ratio
+    Stretch,
+    /// Show the whole image as large as it fits, centered, leaving the cells around it untouched
+    #[default]
+    Contain,
+    /// Fill the rect keeping the aspect ratio, cropping the edges of the image that don't fit
+    Cover
+}
+
+/// The colors an image is drawn with.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Palette {
+    /// 24-bit colors
+    #[default]
+    TrueColor,
+    /// The 240 colors of the 256 color palette that don't depend on the terminal's theme
+    Ansi256,
+    /// The 16 named colors
+    Ansi16
+}
+
+/// An image made of 8-bit RGB pixels, stored row by row.
+/// # Example
+/// ```
+/// use wrecked::image::RgbImage;
+///
+/// let image = RgbImage::from_pnm(b"P3\n# A red and a blue pixel\n2 1\n255\n255 0 0  0 0 255\n").ok().unwrap();
+/// assert_eq!(image.get_size(), (2, 1));
+/// assert_eq!(image.get_pixel(1, 0), Some((0, 0, 255)));
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RgbImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<(u8, u8, u8)>
+}
+
+impl RgbImage {
+    /// Create a black image.
+    pub fn new(width: usize, height: usize) -> RgbImage {
+        RgbImage {
+            width,
+            height,
+            pixels: vec![(0, 0, 0); width * height]
+        }
+    }
+
+    /// Create an image from its pixels, row by row. Returns None if there aren't exactly `width` x `height` pixels.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<(u8, u8, u8)>) -> Option<RgbImage> {
+        if pixels.len() == width * height {
+            Some(RgbImage { width, height, pixels })
+        } else {
+            None
+        }
+    }
+
+    /// Decode a binary (P6) or plain text (P3) PPM image. Greymaps (P5 and P2) are also accepted.
+    pub fn from_pnm(data: &[u8]) -> Result<RgbImage, WreckedError> {
+        let mut reader = PnmReader { data, position: 0 };
+        let (binary, channels) = match reader.next_token() {
+            Some(b"P3") => (false, 3),
+            Some(b"P6") => (true, 3),
+            Some(b"P2") => (false, 1),
+            Some(b"P5") => (true, 1),
+            _ => {
+                return Err(WreckedError::BadImage("Not a PPM or PGM image".to_string()));
+            }
+        };
+
+        let width = reader.next_number()?;
+        let height = reader.next_number()?;
+        let maxval = reader.next_number()?;
+        if maxval == 0 || maxval > 65535 {
+            return Err(WreckedError::BadImage(format!("Invalid maximum value {}", maxval)));
+        }
+
+        let sample_count = width.checked_mul(height)
+            .and_then(|count| count.checked_mul(channels))
+            .ok_or_else(|| WreckedError::BadImage("Image is too large".to_string()))?;
+
+        let mut samples = Vec::with_capacity(cmp::min(sample_count, data.len()));
+        if binary {
+            // A single whitespace character separates the header from the raster
+            let mut position = reader.position + 1;
+            let sample_size = if maxval < 256 { 1 } else { 2 };
+            for _ in 0 .. sample_count {
+                let sample = match data.get(position .. position + sample_size) {
+                    Some([value]) => *value as usize,
+                    Some([high, low]) => (*high as usize) << 8 | *low as usize,
+                    _ => {
+                        return Err(WreckedError::BadImage("Image data is truncated".to_string()));
+                    }
+                };
+                samples.push(sample);
+                position += sample_size;
+            }
+        } else {
+            for _ in 0 .. sample_count {
+                samples.push(reader.next_number()?);
+            }
+        }
+
+        let scale = |sample: usize| ((cmp::min(sample, maxval) * 255 + maxval / 2) / maxval) as u8;
+        let pixels = samples.chunks(channels).map(|pixel| {
+            match pixel {
+                [red, green, blue] => (scale(*red), scale(*green), scale(*blue)),
+                _ => (scale(pixel[0]), scale(pixel[0]), scale(pixel[0]))
+            }
+        }).collect();
+
+        Ok(RgbImage { width, height, pixels })
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<(u8, u8, u8)> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Set a single pixel. Pixels outside the image are ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: (u8, u8, u8)) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+}
+
+// Reads the whitespace-separated fields of a PNM header and plain text raster, skipping comments
+struct PnmReader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> PnmReader<'a> {
+    fn next_token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.data.get(self.position) {
+                Some(b'#') => {
+                    while !matches!(self.data.get(self.position), Some(b'\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => {
+                    self.position += 1;
+                }
+                Some(_) => {
+                    break;
+                }
+                None => {
+                    return None;
+                }
+            }
+        }
+
+        let start = self.position;
+        while matches!(self.data.get(self.position), Some(byte) if !byte.is_ascii_whitespace() && *byte != b'#') {
+            self.position += 1;
+        }
+        Some(&self.data[start .. self.position])
+    }
+
+    fn next_number(&mut self) -> Result<usize, WreckedError> {
+        let token = self.next_token()
+            .ok_or_else(|| WreckedError::BadImage("Image data is truncated".to_string()))?;
+
+        std::str::from_utf8(token).ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| WreckedError::BadImage(format!("Expected a number, found {:?}", String::from_utf8_lossy(token))))
+    }
+}
+
+// xterm's default values for the named colors
+const NAMED_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255)
+];
+
+// Levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+type Rgb = [f32; 3];
+
+fn get_distance(a: Rgb, b: (u8, u8, u8)) -> f32 {
+    let (red, green, blue) = (a[0] - b.0 as f32, a[1] - b.1 as f32, a[2] - b.2 as f32);
+    red * red + green * green + blue * blue
+}
+
+// Find the color of the palette closest to `rgb`, and the value it's displayed as
+fn get_nearest(palette: Palette, rgb: Rgb) -> (Color, (u8, u8, u8)) {
+    match palette {
+        Palette::TrueColor => {
+            let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+            let (red, green, blue) = (channel(rgb[0]), channel(rgb[1]), channel(rgb[2]));
+            (Color::Rgb(red, green, blue), (red, green, blue))
+        }
+        Palette::Ansi256 => {
+            let level = |value: f32| {
+                (0 .. CUBE_LEVELS.len())
+                    .min_by_key(|index| (CUBE_LEVELS[*index] as f32 - value).abs() as u32)
+                    .unwrap_or(0)
+            };
+            let (red, green, blue) = (level(rgb[0]), level(rgb[1]), level(rgb[2]));
+            let cube = (CUBE_LEVELS[red], CUBE_LEVELS[green], CUBE_LEVELS[blue]);
+
+            let average = (rgb[0] + rgb[1] + rgb[2]) / 3.0;
+            let grey_index = ((average - 8.0) / 10.0).round().clamp(0.0, 23.0) as u8;
+            let grey_level = 8 + grey_index * 10;
+            let grey = (grey_level, grey_level, grey_level);
+
+            if get_distance(rgb, grey) < get_distance(rgb, cube) {
+                (Color::Fixed(232 + grey_index), grey)
+            } else {
+                (Color::Fixed(16 + (red * 36 + green * 6 + blue) as u8), cube)
+            }
+        }
+        Palette::Ansi16 => {
+            let index = (0 .. NAMED_RGB.len())
+                .min_by(|a, b| get_distance(rgb, NAMED_RGB[*a]).total_cmp(&get_distance(rgb, NAMED_RGB[*b])))
+                .unwrap_or(0);
+            (Color::NAMED[index], NAMED_RGB[index])
+        }
+    }
+}
+
+// Scale the image onto a grid of `width` x `height` pixels, averaging the source pixels under each one.
+// Pixels the image doesn't cover are None.
+fn resample(image: &RgbImage, fit_mode: FitMode, width: usize, height: usize) -> Vec<Option<Rgb>> {
+    let mut grid = vec![None; width * height];
+    if image.width == 0 || image.height == 0 || width == 0 || height == 0 {
+        return grid;
+    }
+
+    let (image_width, image_height) = (image.width as f32, image.height as f32);
+    let horizontal_scale = width as f32 / image_width;
+    let vertical_scale = height as f32 / image_height;
+
+    // Area of the grid that's drawn to, and the area of the image shown in it
+    let (target_width, target_height, source) = match fit_mode {
+        FitMode::Stretch => {
+            (width, height, (0.0, 0.0, image_width, image_height))
+        }
+        FitMode::Contain => {
+            let scale = horizontal_scale.min(vertical_scale);
+            let target_width = ((image_width * scale).round() as usize).clamp(1, width);
+            let target_height = ((image_height * scale).round() as usize).clamp(1, height);
+            (target_width, target_height, (0.0, 0.0, image_width, image_height))
+        }
+        FitMode::Cover => {
+            let scale = horizontal_scale.max(vertical_scale);
+            let (source_width, source_height) = (width as f32 / scale, height as f32 / scale);
+            (width, height, ((image_width - source_width) / 2.0, (image_height - source_height) / 2.0, source_width, source_height))
+        }
+    };
+    let offset_x = (width - target_width) / 2;
+    let offset_y = (height - target_height) / 2;
+
+    // Range of source pixels under a target pixel, along one axis
+    let get_span = |index: usize, target: usize, start: f32, length: f32, limit: usize| {
+        let from = start + index as f32 * length / target as f32;
+        let to = start + (index + 1) as f32 * length / target as f32;
+        let first = cmp::min(from.floor().max(0.0) as usize, limit - 1);
+        let last = cmp::min(cmp::max(to.ceil() as usize, first + 1), limit);
+        first .. last
+    };
+
+    for y in 0 .. target_height {
+        let rows = get_span(y, target_height, source.1, source.3, image.height);
+        for x in 0 .. target_width {
+            let columns = get_span(x, target_width, source.0, source.2, image.width);
+
+            let mut total = [0.0; 3];
+            let mut count = 0.0;
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let (red, green, blue) = image.pixels[row * image.width + column];
+                    total[0] += red as f32;
+                    total[1] += green as f32;
+                    total[2] += blue as f32;
+                    count += 1.0;
+                }
+            }
+
+            grid[(offset_y + y) * width + offset_x + x] = Some([total[0] / count, total[1] / count, total[2] / count]);
+        }
+    }
+
+    grid
+}
+
+// Reduce the grid to the colors of the palette, optionally spreading the error of each pixel to its
+// neighbours with Floyd-Steinberg dithering
+fn quantize(grid: &mut [Option<Rgb>], width: usize, palette: Palette, dither: bool) -> Vec<Option<Color>> {
+    let mut colors = vec![None; grid.len()];
+    for index in 0 .. grid.len() {
+        let rgb = match grid[index] {
+            Some(rgb) => rgb,
+            None => {
+                continue;
+            }
+        };
+
+        let (color, shown) = get_nearest(palette, rgb);
+        colors[index] = Some(color);
+        if !dither || palette == Palette::TrueColor {
+            continue;
+        }
+
+        let error = [rgb[0] - shown.0 as f32, rgb[1] - shown.1 as f32, rgb[2] - shown.2 as f32];
+        let (x, y) = (index % width, index / width);
+        let neighbours = [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
+        for (offset_x, offset_y, weight) in neighbours {
+            let neighbour_x = x as isize + offset_x;
+            if neighbour_x < 0 || neighbour_x as usize >= width {
+                continue;
+            }
+            let neighbour = (y + offset_y) * width + neighbour_x as usize;
+            if let Some(Some(rgb)) = grid.get_mut(neighbour) {
+                for channel in 0 .. 3 {
+                    rgb[channel] += error[channel] * weight / 16.0;
+                }
+            }
+        }
+    }
+
+    colors
+}
+
+/// A cell of a drawn image: the character, and its foreground and background colors.
+pub(crate) type ImageCell = (char, Option<Color>, Option<Color>);
+
+/// Get the cells of `width` x `height` characters showing the image, row by row.
+/// Cells the image doesn't cover are None.
+pub(crate) fn get_image_cells(image: &RgbImage, fit_mode: FitMode, palette: Palette, dither: bool, width: usize, height: usize) -> Vec<Option<ImageCell>> {
+    let mut grid = resample(image, fit_mode, width, height * 2);
+    let colors = quantize(&mut grid, width, palette, dither);
+
+    let mut cells = Vec::with_capacity(width * height);
+    for y in 0 .. height {
+        for x in 0 .. width {
+            let top = colors[y * 2 * width + x];
+            let bottom = colors[(y * 2 + 1) * width + x];
+            cells.push(match (top, bottom) {
+                (Some(_), _) => Some(('▀', top, bottom)),
+                (None, Some(_)) => Some(('▄', bottom, None)),
+                (None, None) => None
+            });
+        }
+    }
+
+    cells
+}
+
+/// Escape sequences used to show bitmaps with real pixels, for terminals that support them.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GraphicsProtocol {
+    /// DEC Sixel graphics, with up to 256 colors
+    Sixel,
+    /// The kitty terminal graphics protocol, with 24-bit color
+    Kitty
+}
+
+// Get the value of a color of the 256 color palette
+fn get_fixed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0 ..= 15 => NAMED_RGB[index as usize],
+        16 ..= 231 => {
+            let index = (index - 16) as usize;
+            (CUBE_LEVELS[index / 36], CUBE_LEVELS[(index / 6) % 6], CUBE_LEVELS[index % 6])
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// Add a run of a sixel character, using the repeat introducer if it's shorter
+fn push_sixel_run(output: &mut String, character: char, count: usize) {
+    if count > 3 {
+        output.push_str(&format!("!{}{}", count, character));
+    } else {
+        for _ in 0 .. count {
+            output.push(character);
+        }
+    }
+}
+
+/// Encode the image, scaled to `width` x `height` pixels, as a Sixel escape sequence using the 256 color palette.
+pub(crate) fn encode_sixel(image: &RgbImage, width: usize, height: usize) -> String {
+    let mut grid = resample(image, FitMode::Stretch, width, height);
+    let colors = quantize(&mut grid, width, Palette::Ansi256, true);
+
+    // Each color used gets a register, in the order they first appear
+    let mut registers: Vec<u8> = Vec::new();
+    let pixels: Vec<usize> = colors.iter().map(|color| {
+        let index = match color {
+            Some(Color::Fixed(index)) => *index,
+            _ => 0
+        };
+        match registers.iter().position(|register| *register == index) {
+            Some(register) => register,
+            None => {
+                registers.push(index);
+                registers.len() - 1
+            }
+        }
+    }).collect();
+
+    let mut output = format!("\x1BPq\"1;1;{};{}", width, height);
+    let percent = |value: u8| (value as usize * 100 + 127) / 255;
+    for (register, index) in registers.iter().enumerate() {
+        let (red, green, blue) = get_fixed_rgb(*index);
+        output.push_str(&format!("#{};2;{};{};{}", register, percent(red), percent(green), percent(blue)));
+    }
+
+    // Each band is 6 pixels high, and is drawn once for each color in it
+    for band in (0 .. height).step_by(6) {
+        let rows = band .. cmp::min(band + 6, height);
+        let mut band_registers: Vec<usize> = rows.clone()
+            .flat_map(|y| pixels[y * width .. (y + 1) * width].iter().copied())
+            .collect();
+        band_registers.sort_unstable();
+        band_registers.dedup();
+
+        for (i, register) in band_registers.iter().enumerate() {
+            if i > 0 {
+                // Back to the start of the band
+                output.push('$');
+            }
+            output.push_str(&format!("#{}", register));
+
+            let mut line = String::new();
+            let mut run = ('?', 0);
+            for x in 0 .. width {
+                let mut bits = 0;
+                for y in rows.clone() {
+                    if pixels[y * width + x] == *register {
+                        bits |= 1 << (y - band);
+                    }
+                }
+                let character = char::from(63 + bits);
+                if character == run.0 {
+                    run.1 += 1;
+                } else {
+                    push_sixel_run(&mut line, run.0, run.1);
+                    run = (character, 1);
+                }
+            }
+            // Trailing empty sixels can be left off
+            if run.0 != '?' {
+                push_sixel_run(&mut line, run.0, run.1);
+            }
+            output.push_str(&line);
+        }
+
+        if rows.end < height {
+            output.push('-');
+        }
+    }
+
+    output.push_str("\x1B\\");
+    output
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+        for i in 0 .. 4 {
+            if i <= chunk.len() {
+                output.push(BASE64_ALPHABET[(group >> (18 - i * 6)) & 63] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+// The kitty protocol limits each escape sequence to this much of the base64 encoded image
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Encode the image as kitty graphics protocol escape sequences, which transmit it with the given id and
+/// display it scaled to `columns` x `rows` cells at the cursor.
+pub(crate) fn encode_kitty(image: &RgbImage, image_id: usize, columns: usize, rows: usize) -> String {
+    let data: Vec<u8> = image.pixels.iter().flat_map(|(red, green, blue)| [*red, *green, *blue]).collect();
+    let encoded = encode_base64(&data);
+    let chunks: Vec<&str> = encoded.as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or(""))
+        .collect();
+
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            // C=1 keeps the cursor where it is, q=2 stops the terminal from replying
+            output.push_str(&format!(
+                "\x1B_Ga=T,f=24,s={},v={},c={},r={},i={},C=1,q=2,m={};{}\x1B\\",
+                image.width, image.height, columns, rows, image_id, more, chunk
+            ));
+        } else {
+            output.push_str(&format!("\x1B_Gm={};{}\x1B\\", more, chunk));
+        }
+    }
+    output
+}
+
+/// Get the kitty graphics protocol escape sequence that removes an image and frees its data.
+pub(crate) fn get_kitty_delete(image_id: usize) -> String {
+    format!("\x1B_Ga=d,d=I,i={},q=2\x1B\\", image_id)
+}
@@ -0,0 +1,328 @@
+use std::str;
+
+/// Modifier keys held down during a key press or mouse event.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool
+}
+
+impl Modifiers {
+    pub fn none() -> Modifiers {
+        Modifiers::default()
+    }
+
+    pub fn is_none(&self) -> bool {
+        !self.shift && !self.alt && !self.ctrl
+    }
+
+    // xterm encodes modifiers as 1 + a bitmask
+    fn from_xterm(code: usize) -> Modifiers {
+        let mask = code.saturating_sub(1);
+        Modifiers {
+            shift: mask & 1 != 0,
+            alt: mask & 2 != 0,
+            ctrl: mask & 4 != 0
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8)
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+    None
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MouseAction {
+    Press,
+    Release,
+    Drag,
+    Move
+}
+
+/// A mouse event. x and y are absolute (ROOT) coordinates, starting at 0.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct MouseEvent {
+    pub action: MouseAction,
+    pub button: MouseButton,
+    pub x: isize,
+    pub y: isize,
+    pub modifiers: Modifiers
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum InputEvent {
+    Key(Key, Modifiers),
+    Mouse(MouseEvent)
+}
+
+impl InputEvent {
+    /// Shorthand for an InputEvent::Key with no modifiers
+    pub fn key(key: Key) -> InputEvent {
+        InputEvent::Key(key, Modifiers::none())
+    }
+
+    /// Shorthand for an InputEvent::Key with the ctrl modifier
+    pub fn ctrl(key: Key) -> InputEvent {
+        InputEvent::Key(key, Modifiers { ctrl: true, ..Modifiers::none() })
+    }
+}
+
+/// Convert raw bytes read from the terminal into input events.
+/// Mouse events are only reported once RectManager::enable_mouse() has been called.
+/// # Example
+/// ```
+/// use wrecked::input::{parse_input, InputEvent, Key, Modifiers};
+/// let events = parse_input(b"a\x1B[A\x1B[1;5C");
+/// assert_eq!(events, vec![
+///     InputEvent::key(Key::Char('a')),
+///     InputEvent::key(Key::Up),
+///     InputEvent::ctrl(Key::Right)
+/// ]);
+/// ```
+pub fn parse_input(bytes: &[u8]) -> Vec<InputEvent> {
+    let mut events = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (event, length) = parse_event(&bytes[i ..]);
+        if let Some(event) = event {
+            events.push(event);
+        }
+        i += std::cmp::max(length, 1);
+    }
+
+    events
+}
+
+// Parse a single event from the start of the buffer, returning the number of bytes used
+fn parse_event(bytes: &[u8]) -> (Option<InputEvent>, usize) {
+    match bytes[0] {
+        0x1B => {
+            match bytes.get(1) {
+                Some(b'[') => {
+                    parse_csi(bytes)
+                }
+                Some(b'O') => {
+                    parse_ss3(bytes)
+                }
+                Some(0x1B) | None => {
+                    (Some(InputEvent::key(Key::Escape)), 1)
+                }
+                Some(_) => {
+                    // Alt + key
+                    let (event, length) = parse_event(&bytes[1 ..]);
+                    match event {
+                        Some(InputEvent::Key(key, mut modifiers)) => {
+                            modifiers.alt = true;
+                            (Some(InputEvent::Key(key, modifiers)), length + 1)
+                        }
+                        _ => {
+                            (Some(InputEvent::key(Key::Escape)), 1)
+                        }
+                    }
+                }
+            }
+        }
+        b'\r' | b'\n' => {
+            (Some(InputEvent::key(Key::Enter)), 1)
+        }
+        b'\t' => {
+            (Some(InputEvent::key(Key::Tab)), 1)
+        }
+        0x7F | 0x08 => {
+            (Some(InputEvent::key(Key::Backspace)), 1)
+        }
+        0x00 => {
+            (Some(InputEvent::ctrl(Key::Char(' '))), 1)
+        }
+        byte if byte < 0x20 => {
+            (Some(InputEvent::ctrl(Key::Char((byte + b'a' - 1) as char))), 1)
+        }
+        byte => {
+            // Multi-byte utf8 character
+            let length = if byte >= 0xF0 {
+                4
+            } else if byte >= 0xE0 {
+                3
+            } else if byte >= 0xC0 {
+                2
+            } else {
+                1
+            };
+
+            match bytes.get(0 .. length).and_then(|b| str::from_utf8(b).ok()) {
+                Some(string) => {
+                    (string.chars().next().map(|c| InputEvent::key(Key::Char(c))), length)
+                }
+                None => {
+                    (None, 1)
+                }
+            }
+        }
+    }
+}
+
+// ESC O <final>; sent for F1-F4 and by terminals in application cursor mode
+fn parse_ss3(bytes: &[u8]) -> (Option<InputEvent>, usize) {
+    let key = match bytes.get(2) {
+        Some(b'A') => Some(Key::Up),
+        Some(b'B') => Some(Key::Down),
+        Some(b'C') => Some(Key::Right),
+        Some(b'D') => Some(Key::Left),
+        Some(b'H') => Some(Key::Home),
+        Some(b'F') => Some(Key::End),
+        Some(b'P') => Some(Key::F(1)),
+        Some(b'Q') => Some(Key::F(2)),
+        Some(b'R') => Some(Key::F(3)),
+        Some(b'S') => Some(Key::F(4)),
+        _ => None
+    };
+
+    match key {
+        Some(key) => {
+            (Some(InputEvent::key(key)), 3)
+        }
+        None => {
+            (Some(InputEvent::key(Key::Escape)), 1)
+        }
+    }
+}
+
+// ESC [ <params> <final>
+fn parse_csi(bytes: &[u8]) -> (Option<InputEvent>, usize) {
+    let mut end = 2;
+    while end < bytes.len() && !(0x40 ..= 0x7E).contains(&bytes[end]) {
+        end += 1;
+    }
+
+    if end >= bytes.len() {
+        // Incomplete sequence. Treat it as a lone escape
+        return (Some(InputEvent::key(Key::Escape)), 1);
+    }
+
+    let final_byte = bytes[end];
+    let length = end + 1;
+    let raw_params = str::from_utf8(&bytes[2 .. end]).unwrap_or("");
+
+    if let Some(mouse_params) = raw_params.strip_prefix('<') {
+        return (parse_sgr_mouse(mouse_params, final_byte), length);
+    }
+
+    let params: Vec<usize> = raw_params.split(';')
+        .map(|p| p.parse::<usize>().unwrap_or(1))
+        .collect();
+    let modifiers = Modifiers::from_xterm(*params.get(1).unwrap_or(&1));
+
+    let key = match final_byte {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        b'P' => Some(Key::F(1)),
+        b'Q' => Some(Key::F(2)),
+        b'R' => Some(Key::F(3)),
+        b'S' => Some(Key::F(4)),
+        b'Z' => Some(Key::BackTab),
+        b'~' => {
+            match params.first() {
+                Some(1) | Some(7) => Some(Key::Home),
+                Some(2) => Some(Key::Insert),
+                Some(3) => Some(Key::Delete),
+                Some(4) | Some(8) => Some(Key::End),
+                Some(5) => Some(Key::PageUp),
+                Some(6) => Some(Key::PageDown),
+                Some(n @ 11 ..= 15) => Some(Key::F((n - 10) as u8)),
+                Some(n @ 17 ..= 21) => Some(Key::F((n - 11) as u8)),
+                Some(n @ 23 ..= 24) => Some(Key::F((n - 12) as u8)),
+                _ => None
+            }
+        }
+        _ => None
+    };
+
+    (key.map(|key| InputEvent::Key(key, modifiers)), length)
+}
+
+// ESC [ < button ; x ; y (M|m)
+fn parse_sgr_mouse(params: &str, final_byte: u8) -> Option<InputEvent> {
+    let values: Vec<usize> = params.split(';')
+        .filter_map(|p| p.parse::<usize>().ok())
+        .collect();
+
+    if values.len() != 3 {
+        return None;
+    }
+
+    let code = values[0];
+    let modifiers = Modifiers {
+        shift: code & 4 != 0,
+        alt: code & 8 != 0,
+        ctrl: code & 16 != 0
+    };
+
+    let button = if code & 64 != 0 {
+        if code & 1 == 0 {
+            MouseButton::WheelUp
+        } else {
+            MouseButton::WheelDown
+        }
+    } else {
+        match code & 3 {
+            0 => MouseButton::Left,
+            1 => MouseButton::Middle,
+            2 => MouseButton::Right,
+            _ => MouseButton::None
+        }
+    };
+
+    let action = if final_byte == b'm' {
+        MouseAction::Release
+    } else if code & 32 != 0 {
+        if button == MouseButton::None {
+            MouseAction::Move
+        } else {
+            MouseAction::Drag
+        }
+    } else {
+        MouseAction::Press
+    };
+
+    Some(InputEvent::Mouse(MouseEvent {
+        action,
+        button,
+        // Terminals count from 1
+        x: values[1] as isize - 1,
+        y: values[2] as isize - 1,
+        modifiers
+    }))
+}
@@ -0,0 +1,557 @@
+use std::cmp;
+
+/// How much space a child rect takes up along the direction of its parent's Layout.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Constraint {
+    /// Exactly this many cells
+    Fixed(usize),
+    /// A percentage of the space available to the layout
+    Percentage(usize),
+    /// At least this many cells, growing like Fill(1) if there is space left
+    Min(usize),
+    /// At most this many cells, growing like Fill(1) until the limit is reached
+    Max(usize),
+    /// Share whatever space is left over, proportionally to the given weight
+    Fill(usize)
+}
+
+/// The direction in which a Layout places its children.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Direction {
+    Horizontal,
+    Vertical
+}
+
+/// Spacing around each side of a rectangle. Used for both padding and margins.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Edges {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize
+}
+
+impl Edges {
+    pub fn new(top: usize, right: usize, bottom: usize, left: usize) -> Edges {
+        Edges { top, right, bottom, left }
+    }
+
+    /// The same spacing on every side
+    pub fn uniform(size: usize) -> Edges {
+        Edges::new(size, size, size, size)
+    }
+
+    fn horizontal(&self) -> usize {
+        self.left + self.right
+    }
+
+    fn vertical(&self) -> usize {
+        self.top + self.bottom
+    }
+}
+
+/// Splits a rectangle into a row or column of child rectangles.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::layout::{Layout, Direction, Constraint};
+/// let mut rectmanager = RectManager::new();
+/// let container = rectmanager.new_rect(ROOT).ok().unwrap();
+/// let sidebar = rectmanager.new_rect(container).ok().unwrap();
+/// let body = rectmanager.new_rect(container).ok().unwrap();
+///
+/// rectmanager.set_constraint(sidebar, Constraint::Fixed(10));
+/// rectmanager.set_constraint(body, Constraint::Fill(1));
+/// rectmanager.set_layout(container, Layout::new(Direction::Horizontal));
+///
+/// // Children are resized whenever the container is.
+/// rectmanager.resize(container, 40, 10);
+/// assert_eq!(rectmanager.get_rect_size(sidebar), Some((10, 10)));
+/// assert_eq!(rectmanager.get_rect_size(body), Some((30, 10)));
+/// assert_eq!(rectmanager.get_relative_offset(body), Some((10, 0)));
+///
+/// rectmanager.kill();
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Layout {
+    pub direction: Direction,
+    /// Space kept clear inside the edges of the parent
+    pub padding: Edges,
+    /// Number of cells between each child
+    pub spacing: usize
+}
+
+impl Layout {
+    pub fn new(direction: Direction) -> Layout {
+        Layout {
+            direction,
+            padding: Edges::default(),
+            spacing: 0
+        }
+    }
+
+    /// Calculate the (x, y, width, height) of each child given the size of the parent.
+    /// Each child is described by its constraint and its margins.
+    pub fn split(&self, width: usize, height: usize, children: &[(Constraint, Edges)]) -> Vec<(isize, isize, usize, usize)> {
+        let inner_width = width.saturating_sub(self.padding.horizontal());
+        let inner_height = height.saturating_sub(self.padding.vertical());
+
+        let (main_length, cross_length) = match self.direction {
+            Direction::Horizontal => (inner_width, inner_height),
+            Direction::Vertical => (inner_height, inner_width)
+        };
+
+        let gaps = self.spacing * children.len().saturating_sub(1);
+        let lengths = solve_constraints(
+            main_length.saturating_sub(gaps),
+            &children.iter().map(|(constraint, _)| *constraint).collect::<Vec<Constraint>>()
+        );
+
+        let mut output = Vec::new();
+        let mut cursor = 0;
+        for ((_, margin), length) in children.iter().zip(lengths.iter()) {
+            let (x, y, child_width, child_height) = match self.direction {
+                Direction::Horizontal => {
+                    (cursor, 0, *length, cross_length)
+                }
+                Direction::Vertical => {
+                    (0, cursor, cross_length, *length)
+                }
+            };
+
+            output.push((
+                (self.padding.left + x + margin.left) as isize,
+                (self.padding.top + y + margin.top) as isize,
+                child_width.saturating_sub(margin.horizontal()),
+                child_height.saturating_sub(margin.vertical())
+            ));
+
+            cursor += length + self.spacing;
+        }
+
+        output
+    }
+}
+
+/// Divide `length` cells between the given constraints.
+/// If the constraints can't all be satisfied, the last ones are shrunk first.
+pub fn solve_constraints(length: usize, constraints: &[Constraint]) -> Vec<usize> {
+    let mut sizes = vec![0; constraints.len()];
+    // (index, weight, upper limit)
+    let mut flexible: Vec<(usize, usize, Option<usize>)> = Vec::new();
+
+    for (i, constraint) in constraints.iter().enumerate() {
+        match constraint {
+            Constraint::Fixed(n) => {
+                sizes[i] = *n;
+            }
+            Constraint::Percentage(p) => {
+                sizes[i] = (length * p) / 100;
+            }
+            Constraint::Min(n) => {
+                sizes[i] = *n;
+                flexible.push((i, 1, None));
+            }
+            Constraint::Max(n) => {
+                flexible.push((i, 1, Some(*n)));
+            }
+            Constraint::Fill(weight) => {
+                flexible.push((i, *weight, None));
+            }
+        }
+    }
+
+    let used: usize = sizes.iter().sum();
+    if used > length {
+        let mut overflow = used - length;
+        for size in sizes.iter_mut().rev() {
+            let cut = std::cmp::min(*size, overflow);
+            *size -= cut;
+            overflow -= cut;
+        }
+        return sizes;
+    }
+
+    let mut remaining = length - used;
+    // Hand out the remaining space by weight. Anything that hits its limit is
+    // capped and the rest is redistributed among the others.
+    while remaining > 0 && !flexible.is_empty() {
+        let total_weight: usize = flexible.iter().map(|(_, weight, _)| *weight).sum();
+        if total_weight == 0 {
+            break;
+        }
+
+        let mut shares = Vec::new();
+        let mut handed_out = 0;
+        for (_, weight, _) in flexible.iter() {
+            let share = (remaining * weight) / total_weight;
+            handed_out += share;
+            shares.push(share);
+        }
+
+        // Spread the rounding error across the first few weighted children
+        let mut leftover = remaining - handed_out;
+        for (share, (_, weight, _)) in shares.iter_mut().zip(flexible.iter()) {
+            if leftover == 0 {
+                break;
+            }
+            if *weight > 0 {
+                *share += 1;
+                leftover -= 1;
+            }
+        }
+
+        let mut capped = false;
+        for (share, (index, _, limit)) in shares.iter().zip(flexible.iter()) {
+            if let Some(limit) = limit {
+                if sizes[*index] + share > *limit {
+                    remaining -= limit - sizes[*index];
+                    sizes[*index] = *limit;
+                    capped = true;
+                }
+            }
+        }
+
+        if capped {
+            flexible.retain(|(index, _, limit)| {
+                match limit {
+                    Some(limit) => sizes[*index] < *limit,
+                    None => true
+                }
+            });
+        } else {
+            for (share, (index, _, _)) in shares.iter().zip(flexible.iter()) {
+                sizes[*index] += share;
+            }
+            remaining = 0;
+        }
+    }
+
+    sizes
+}
+
+/// The size of a single row or column of a Grid.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Track {
+    /// Exactly this many cells
+    Fixed(usize),
+    /// Share whatever space is left over, proportionally to the given weight
+    Fraction(usize),
+    /// Just big enough to fit the contents of the rects placed in it
+    Auto
+}
+
+/// Where a child rect is placed within its parent's Grid.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct GridCell {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize
+}
+
+impl GridCell {
+    pub fn new(row: usize, column: usize) -> GridCell {
+        GridCell {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1
+        }
+    }
+
+    pub fn spanning(row: usize, column: usize, row_span: usize, column_span: usize) -> GridCell {
+        GridCell {
+            row,
+            column,
+            row_span,
+            column_span
+        }
+    }
+}
+
+/// Places child rects into the cells of a grid of rows and columns.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::layout::{Grid, GridCell, Track};
+/// let mut rectmanager = RectManager::new();
+/// let dashboard = rectmanager.new_rect(ROOT).ok().unwrap();
+/// let banner = rectmanager.new_rect(dashboard).ok().unwrap();
+/// let panel = rectmanager.new_rect(dashboard).ok().unwrap();
+///
+/// rectmanager.set_grid_cell(banner, GridCell::spanning(0, 0, 1, 2));
+/// rectmanager.set_grid_cell(panel, GridCell::new(1, 1));
+/// rectmanager.set_grid(dashboard, Grid::new(
+///     vec![Track::Fraction(1), Track::Fraction(1)],
+///     vec![Track::Fixed(3), Track::Fraction(1)]
+/// ));
+///
+/// rectmanager.resize(dashboard, 40, 20);
+/// assert_eq!(rectmanager.get_rect_size(banner), Some((40, 3)));
+/// assert_eq!(rectmanager.get_rect_size(panel), Some((20, 17)));
+/// assert_eq!(rectmanager.get_relative_offset(panel), Some((20, 3)));
+///
+/// rectmanager.kill();
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Grid {
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    /// Space kept clear inside the edges of the parent
+    pub padding: Edges,
+    /// Number of cells between each column
+    pub column_gap: usize,
+    /// Number of cells between each row
+    pub row_gap: usize
+}
+
+impl Grid {
+    pub fn new(columns: Vec<Track>, rows: Vec<Track>) -> Grid {
+        Grid {
+            columns,
+            rows,
+            padding: Edges::default(),
+            column_gap: 0,
+            row_gap: 0
+        }
+    }
+
+    /// Calculate the (x, y, width, height) of each child given the size of the parent.
+    /// Each child is described by its cell, its margins and the size of its contents (used by Auto tracks).
+    /// Cells that fall outside of the grid are clamped to its last row and column.
+    pub fn place(&self, width: usize, height: usize, children: &[(GridCell, Edges, (usize, usize))]) -> Vec<(isize, isize, usize, usize)> {
+        let column_sizes = solve_tracks(
+            width.saturating_sub(self.padding.horizontal()),
+            &self.columns,
+            self.column_gap,
+            &children.iter().map(|(cell, margin, content)| {
+                (cell.column, cell.column_span, content.0 + margin.horizontal())
+            }).collect::<Vec<(usize, usize, usize)>>()
+        );
+
+        let row_sizes = solve_tracks(
+            height.saturating_sub(self.padding.vertical()),
+            &self.rows,
+            self.row_gap,
+            &children.iter().map(|(cell, margin, content)| {
+                (cell.row, cell.row_span, content.1 + margin.vertical())
+            }).collect::<Vec<(usize, usize, usize)>>()
+        );
+
+        let column_offsets = track_offsets(&column_sizes, self.column_gap);
+        let row_offsets = track_offsets(&row_sizes, self.row_gap);
+
+        let mut output = Vec::new();
+        for (cell, margin, _) in children.iter() {
+            let (x, cell_width) = span_area(&column_sizes, &column_offsets, self.column_gap, cell.column, cell.column_span);
+            let (y, cell_height) = span_area(&row_sizes, &row_offsets, self.row_gap, cell.row, cell.row_span);
+
+            output.push((
+                (self.padding.left + x + margin.left) as isize,
+                (self.padding.top + y + margin.top) as isize,
+                cell_width.saturating_sub(margin.horizontal()),
+                cell_height.saturating_sub(margin.vertical())
+            ));
+        }
+
+        output
+    }
+}
+
+// items are (first track, span, content length)
+fn solve_tracks(length: usize, tracks: &[Track], gap: usize, items: &[(usize, usize, usize)]) -> Vec<usize> {
+    let mut sizes = vec![0; tracks.len()];
+    let mut total_weight = 0;
+
+    for (i, track) in tracks.iter().enumerate() {
+        match track {
+            Track::Fixed(n) => {
+                sizes[i] = *n;
+            }
+            Track::Auto => {
+                // Only rects sitting in a single track are measured
+                for (first, span, content) in items.iter() {
+                    if *span <= 1 && clamp_index(*first, tracks.len()) == i {
+                        sizes[i] = std::cmp::max(sizes[i], *content);
+                    }
+                }
+            }
+            Track::Fraction(weight) => {
+                total_weight += weight;
+            }
+        }
+    }
+
+    let used: usize = sizes.iter().sum::<usize>() + (gap * tracks.len().saturating_sub(1));
+    let mut remaining = length.saturating_sub(used);
+    if total_weight > 0 {
+        let mut leftover_weight = total_weight;
+        for (i, track) in tracks.iter().enumerate() {
+            if let Track::Fraction(weight) = track {
+                // Give each fraction its share of what's left, so rounding errors end up in the last one
+                let share = (remaining * weight).checked_div(leftover_weight).unwrap_or(0);
+                sizes[i] = share;
+                remaining -= share;
+                leftover_weight -= weight;
+            }
+        }
+    }
+
+    sizes
+}
+
+fn clamp_index(index: usize, length: usize) -> usize {
+    std::cmp::min(index, length.saturating_sub(1))
+}
+
+fn track_offsets(sizes: &[usize], gap: usize) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut cursor = 0;
+    for size in sizes.iter() {
+        offsets.push(cursor);
+        cursor += size + gap;
+    }
+    offsets
+}
+
+// Get the offset and length covered by a span of tracks, including the gaps between them
+fn span_area(sizes: &[usize], offsets: &[usize], gap: usize, first: usize, span: usize) -> (usize, usize) {
+    if sizes.is_empty() {
+        return (0, 0);
+    }
+
+    let first = clamp_index(first, sizes.len());
+    let last = clamp_index(first + std::cmp::max(span, 1) - 1, sizes.len());
+
+    let mut length = 0;
+    for size in sizes[first ..= last].iter() {
+        length += size;
+    }
+    length += gap * (last - first);
+
+    (offsets[first], length)
+}
+
+/// Which edge (or the center) of its parent a rect keeps its distance to when the parent is resized.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HorizontalAnchor {
+    Left,
+    Right,
+    Center
+}
+
+/// Which edge (or the center) of its parent a rect keeps its distance to when the parent is resized.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum VerticalAnchor {
+    Top,
+    Bottom,
+    Center
+}
+
+/// How a rect follows its parent when the parent is resized.
+/// Stretching keeps the distance to both edges, so the rect grows and shrinks with its parent.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::layout::{Anchor, HorizontalAnchor, VerticalAnchor};
+/// let mut rectmanager = RectManager::new();
+/// let window = rectmanager.new_rect(ROOT).ok().unwrap();
+/// rectmanager.resize(window, 40, 20);
+///
+/// // A status bar along the bottom of the window
+/// let footer = rectmanager.new_rect(window).ok().unwrap();
+/// rectmanager.resize(footer, 40, 1);
+/// rectmanager.set_position(footer, 0, 19);
+///
+/// let mut anchor = Anchor::new(HorizontalAnchor::Left, VerticalAnchor::Bottom);
+/// anchor.stretch_horizontal = true;
+/// rectmanager.set_anchor(footer, anchor);
+///
+/// rectmanager.resize(window, 60, 30);
+/// assert_eq!(rectmanager.get_relative_offset(footer), Some((0, 29)));
+/// assert_eq!(rectmanager.get_rect_size(footer), Some((60, 1)));
+///
+/// rectmanager.kill();
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Anchor {
+    pub horizontal: HorizontalAnchor,
+    pub vertical: VerticalAnchor,
+    pub stretch_horizontal: bool,
+    pub stretch_vertical: bool
+}
+
+impl Anchor {
+    pub fn new(horizontal: HorizontalAnchor, vertical: VerticalAnchor) -> Anchor {
+        Anchor {
+            horizontal,
+            vertical,
+            stretch_horizontal: false,
+            stretch_vertical: false
+        }
+    }
+
+    /// Calculate the new (x, y, width, height) of a rect when its parent changes size.
+    /// `margins` are the distances from the rect's right and bottom edges to its parent's, which stretching keeps.
+    pub fn follow(&self, area: (isize, isize, usize, usize), margins: (isize, isize), old_size: (usize, usize), new_size: (usize, usize)) -> (isize, isize, usize, usize) {
+        let (x, width) = follow_axis(
+            area.0,
+            area.2,
+            margins.0,
+            old_size.0,
+            new_size.0,
+            self.stretch_horizontal,
+            match self.horizontal {
+                HorizontalAnchor::Left => AxisAnchor::Start,
+                HorizontalAnchor::Right => AxisAnchor::End,
+                HorizontalAnchor::Center => AxisAnchor::Center
+            }
+        );
+
+        let (y, height) = follow_axis(
+            area.1,
+            area.3,
+            margins.1,
+            old_size.1,
+            new_size.1,
+            self.stretch_vertical,
+            match self.vertical {
+                VerticalAnchor::Top => AxisAnchor::Start,
+                VerticalAnchor::Bottom => AxisAnchor::End,
+                VerticalAnchor::Center => AxisAnchor::Center
+            }
+        );
+
+        (x, y, width, height)
+    }
+}
+
+enum AxisAnchor {
+    Start,
+    End,
+    Center
+}
+
+fn follow_axis(position: isize, length: usize, margin: isize, old_length: usize, new_length: usize, stretch: bool, anchor: AxisAnchor) -> (isize, usize) {
+    let delta = new_length as isize - old_length as isize;
+
+    if stretch {
+        // Measured from the margin rather than the current length, which may have been cut off at 0
+        (position, cmp::max(0, new_length as isize - position - margin) as usize)
+    } else {
+        match anchor {
+            AxisAnchor::Start => {
+                (position, length)
+            }
+            AxisAnchor::End => {
+                (position + delta, length)
+            }
+            AxisAnchor::Center => {
+                // Keep the same distance from being perfectly centered
+                let drift = position - ((old_length as isize - length as isize) / 2);
+                (((new_length as isize - length as isize) / 2) + drift, length)
+            }
+        }
+    }
+}
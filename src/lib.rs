@@ -1,5 +1,7 @@
 use std::cmp::{self, PartialOrd};
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::io::{self, Write};
 use std::error::Error;
 use std::fmt::{self, Display};
@@ -9,6 +11,32 @@ use terminal_size::{terminal_size, Width, Height};
 mod platform;
 use platform::TermType;
 
+mod box_drawing;
+
+mod ansi;
+use ansi::{AnsiParser, AnsiToken};
+
+pub mod vt;
+
+pub mod input;
+pub mod widgets;
+
+pub mod layout;
+use layout::{Layout, Constraint, Edges, Grid, GridCell, Anchor};
+
+pub mod image;
+use image::{RgbImage, FitMode, Palette, GraphicsProtocol};
+
+pub mod figlet;
+use figlet::FigFont;
+
+pub mod text;
+use text::{TextOptions, Overflow};
+
+pub mod bidi;
+use bidi::TextDirection;
+use widgets::Alignment;
+
 pub mod tests;
 
 pub fn get_terminal_size() -> (u16, u16) {
@@ -26,6 +54,9 @@ pub fn get_terminal_size() -> (u16, u16) {
 pub enum WreckedError {
     AllGood,
     BadColor,
+    BadImage(String), // Reason the image couldn't be decoded
+    BadFont(String), // Reason the font couldn't be loaded
+    ProcessFailure(String), // Reason a child process couldn't be started or talked to
     InvalidUtf8,
     StringTooLong(usize, (isize, isize), String), // Rect_id, position, string
     NotFound(usize),
@@ -49,28 +80,64 @@ impl Error for WreckedError {}
 /// enum versions of the ANSI color codes
 #[derive(PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Debug)]
 pub enum Color {
-    BLACK = 0,
-    RED = 1,
-    GREEN = 2,
-    YELLOW = 3,
-    BLUE = 4,
-    MAGENTA = 5,
-    CYAN = 6,
-    WHITE = 7,
-    BRIGHTBLACK = 8 | 0,
-    BRIGHTRED = 8 | 1,
-    BRIGHTGREEN = 8 | 2,
-    BRIGHTYELLOW = 8 | 3,
-    BRIGHTBLUE = 8 | 4,
-    BRIGHTMAGENTA = 8 | 5,
-    BRIGHTCYAN = 8 | 6,
-    BRIGHTWHITE = 8 | 7
+    BLACK,
+    RED,
+    GREEN,
+    YELLOW,
+    BLUE,
+    MAGENTA,
+    CYAN,
+    WHITE,
+    BRIGHTBLACK,
+    BRIGHTRED,
+    BRIGHTGREEN,
+    BRIGHTYELLOW,
+    BRIGHTBLUE,
+    BRIGHTMAGENTA,
+    BRIGHTCYAN,
+    BRIGHTWHITE,
+    /// An entry of the 256 color palette
+    Fixed(u8),
+    /// A 24-bit color, for terminals that support true color
+    Rgb(u8, u8, u8)
+}
+
+impl Color {
+    /// The 16 named colors, in palette order
+    pub const NAMED: [Color; 16] = [
+        Color::BLACK, Color::RED, Color::GREEN, Color::YELLOW,
+        Color::BLUE, Color::MAGENTA, Color::CYAN, Color::WHITE,
+        Color::BRIGHTBLACK, Color::BRIGHTRED, Color::BRIGHTGREEN, Color::BRIGHTYELLOW,
+        Color::BRIGHTBLUE, Color::BRIGHTMAGENTA, Color::BRIGHTCYAN, Color::BRIGHTWHITE
+    ];
+
+    // SGR parameters to use this color as the foreground (or background)
+    fn get_ansi_codes(&self, background: bool) -> Vec<u8> {
+        let offset = if background { 10 } else { 0 };
+        match self {
+            Color::Fixed(index) => {
+                vec![38 + offset, 5, *index]
+            }
+            Color::Rgb(red, green, blue) => {
+                vec![38 + offset, 2, *red, *green, *blue]
+            }
+            named => {
+                let index = Color::NAMED.iter().position(|color| color == named).unwrap_or(0) as u8;
+                if index & 8 == 8 {
+                    vec![90 + offset + (index & 7)]
+                } else {
+                    vec![30 + offset + (index & 7)]
+                }
+            }
+        }
+    }
 }
 
 /// Structure to manage text effects instead of having disparate flags
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 struct EffectsHandler {
     bold: bool,
+    dim: bool,
     underline: bool,
     invert: bool,
     italics: bool,
@@ -84,6 +151,7 @@ impl fmt::Debug for EffectsHandler {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EffectsHandler")
          .field("bold", &self.bold)
+         .field("dim", &self.dim)
          .field("underline", &self.underline)
          .field("invert", &self.invert)
          .field("strike", &self.strike)
@@ -98,6 +166,7 @@ impl EffectsHandler {
     pub fn new() -> EffectsHandler {
         EffectsHandler {
             bold: false,
+            dim: false,
             underline: false,
             invert: false,
             italics: false,
@@ -110,6 +179,7 @@ impl EffectsHandler {
 
     pub fn is_plain(&self) -> bool {
         !self.bold
+        && !self.dim
         && !self.underline
         && !self.invert
         && !self.italics
@@ -121,6 +191,7 @@ impl EffectsHandler {
 
     pub fn clear(&mut self) {
         self.bold = false;
+        self.dim = false;
         self.underline = false;
         self.invert = false;
         self.italics = false;
@@ -135,6 +206,9 @@ impl EffectsHandler {
         if self.bold {
             output += 1;
         }
+        if self.dim {
+            output += 1;
+        }
         if self.underline {
             output += 1;
         }
@@ -162,6 +236,121 @@ impl EffectsHandler {
 }
 
 
+/// Text effects that can be applied to individual characters of a rectangle,
+/// in place of the effects of the rectangle itself.
+/// # Example
+/// ```
+/// use wrecked::{Style, Color};
+/// let warning = Style {
+///     bold: true,
+///     foreground_color: Some(Color::YELLOW),
+///     ..Style::default()
+/// };
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Style {
+    pub bold: bool,
+    pub dim: bool,
+    pub underline: bool,
+    pub invert: bool,
+    pub italics: bool,
+    pub strike: bool,
+    pub blink: bool,
+    pub background_color: Option<Color>,
+    pub foreground_color: Option<Color>
+}
+
+impl From<Style> for EffectsHandler {
+    fn from(style: Style) -> EffectsHandler {
+        EffectsHandler {
+            bold: style.bold,
+            dim: style.dim,
+            underline: style.underline,
+            invert: style.invert,
+            italics: style.italics,
+            strike: style.strike,
+            blink: style.blink,
+            background_color: style.background_color,
+            foreground_color: style.foreground_color
+        }
+    }
+}
+
+impl From<EffectsHandler> for Style {
+    fn from(effects: EffectsHandler) -> Style {
+        Style {
+            bold: effects.bold,
+            dim: effects.dim,
+            underline: effects.underline,
+            invert: effects.invert,
+            italics: effects.italics,
+            strike: effects.strike,
+            blink: effects.blink,
+            background_color: effects.background_color,
+            foreground_color: effects.foreground_color
+        }
+    }
+}
+
+/// An output that keeps everything written to it, so what the RectManager draws can be checked without a terminal.
+/// Clones share the same buffer.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT, HeadlessSink};
+/// let mut rectmanager = RectManager::new();
+/// rectmanager.resize(ROOT, 2, 1);
+/// let sink = HeadlessSink::new();
+/// rectmanager.set_output(sink.clone());
+///
+/// rectmanager.set_string(ROOT, 0, 0, "Hi");
+/// rectmanager.render();
+/// assert_eq!(sink.take(), b"\x1B[?25l\x1B[1;1HHi\x1B[0m\x1B[1;1H");
+///
+/// rectmanager.kill();
+/// ```
+#[derive(Clone, Default)]
+pub struct HeadlessSink {
+    buffer: Rc<RefCell<Vec<u8>>>
+}
+
+impl HeadlessSink {
+    pub fn new() -> HeadlessSink {
+        HeadlessSink::default()
+    }
+
+    /// Get everything written since the last call, emptying the buffer.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer.borrow_mut())
+    }
+}
+
+impl Write for HeadlessSink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// A bitmap shown over a rect with a GraphicsProtocol, as it was last sent to the terminal
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+struct DrawnGraphic {
+    // Absolute position and size in cells
+    area: (isize, isize, usize, usize),
+    generation: usize,
+    protocol: GraphicsProtocol
+}
+
+impl DrawnGraphic {
+    fn contains(&self, position: &(isize, isize)) -> bool {
+        let (x, y, width, height) = self.area;
+        position.0 >= x && position.1 >= y && position.0 < x + width as isize && position.1 < y + height as isize
+    }
+}
+
 /// This is the id of the top-level rectangle that is instantiated when a new RectManager is created.
 pub const ROOT: usize = 0;
 
@@ -194,7 +383,21 @@ pub struct RectManager {
     // characters at the same coordinate.
     top_cache: HashMap<(isize, isize), (char, EffectsHandler)>,
     _termref: Option<TermType>,
-    default_character: char
+    default_character: char,
+    mouse_enabled: bool,
+    // (rect_id, x, y) the terminal's cursor is placed at after drawing
+    cursor: Option<(usize, isize, isize)>,
+    // Absolute position of the cursor as of the last draw
+    drawn_cursor: Option<(isize, isize)>,
+    // Where frames are written. stdout if None
+    output: Option<Box<dyn Write>>,
+    graphics_protocol: Option<GraphicsProtocol>,
+    // Size of a cell in pixels, used to scale Sixel images
+    cell_pixel_size: (usize, usize),
+    // Bitmaps shown over rects, and a generation that changes each time one is set
+    bitmaps: HashMap<usize, (RgbImage, usize)>,
+    bitmap_generation: usize,
+    drawn_graphics: HashMap<usize, DrawnGraphic>
 }
 
 impl RectManager {
@@ -217,7 +420,16 @@ impl RectManager {
             rects: HashMap::new(),
             top_cache: HashMap::new(),
             _termref: termref,
-            default_character: ' '
+            default_character: ' ',
+            mouse_enabled: false,
+            cursor: None,
+            drawn_cursor: None,
+            output: None,
+            graphics_protocol: None,
+            cell_pixel_size: (10, 20),
+            bitmaps: HashMap::new(),
+            bitmap_generation: 0,
+            drawn_graphics: HashMap::new()
         };
 
         rectmanager.new_orphan().expect("Couldn't Create ROOT rect");
@@ -238,6 +450,40 @@ impl RectManager {
         }
     }
 
+    // Like write(), but without the trailing newline, which would move the cursor
+    fn write_unterminated(input: &str) -> Result<(), WreckedError> {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        match handle.write_all(input.as_bytes()).and_then(|_| handle.flush()) {
+            Ok(_) => {
+                Ok(())
+            }
+            Err(_e) => {
+                Err(WreckedError::StdoutFailure(input.to_string()))
+            }
+        }
+    }
+
+    // Write part of a frame to the output, or stdout if none has been set
+    fn write_output(&mut self, input: &str) -> Result<(), WreckedError> {
+        match self.output.as_mut() {
+            Some(output) => {
+                match output.write_all(input.as_bytes()).and_then(|_| output.flush()) {
+                    Ok(_) => Ok(()),
+                    Err(_e) => Err(WreckedError::StdoutFailure(input.to_string()))
+                }
+            }
+            None => {
+                RectManager::write_unterminated(input)
+            }
+        }
+    }
+
+    /// Send what's drawn to the given output instead of stdout, eg a HeadlessSink for testing.
+    /// Setting up and restoring the console, including turning mouse reporting on and off, still uses stdout.
+    pub fn set_output<W: Write + 'static>(&mut self, output: W) {
+        self.output = Some(Box::new(output));
+    }
 
     /// If the ROOT rectangle dimensions to not match up to the console dimensions, then resize to fit.
     /// Returns true if a resize was made.
@@ -251,6 +497,7 @@ impl RectManager {
             self.resize(ROOT, w as usize, h as usize).expect("Unable to fit ROOT rect to terminal");
 
             self.top_cache.drain();
+            self.drawn_graphics.clear();
             for (_, child) in self.rects.iter_mut() {
                 child.flag_refresh();
             }
@@ -302,6 +549,8 @@ impl RectManager {
     /// ```
     pub fn kill(&mut self) -> Result<(), WreckedError> {
         let mut last_error = Ok(());
+        self.hide_cursor();
+        self.bitmaps.clear();
         match self.clear_children(ROOT) {
             Ok(_) => {}
             Err(e) => { last_error = Err(e); }
@@ -314,6 +563,10 @@ impl RectManager {
             Ok(_) => {}
             Err(e) => { last_error = Err(e); }
         }
+        match self.clear_character_styles(ROOT) {
+            Ok(_) => {}
+            Err(e) => { last_error = Err(e); }
+        }
         match self.render() {
             Ok(_) => {}
             Err(e) => { last_error = Err(e); }
@@ -322,6 +575,13 @@ impl RectManager {
         // Even if it fails, we want to try clearing out all the rects
         // that are drawn, and reset the screen, to try to make failure
         // as easy to read as possible.
+        if self.mouse_enabled {
+            match self.disable_mouse() {
+                Ok(_) => {}
+                Err(e) => { last_error = Err(e); }
+            }
+        }
+
         self.restore_console_state();
 
         RectManager::write("\x1B[2A").expect("Couldn't restore cursor position");
@@ -330,6 +590,22 @@ impl RectManager {
         last_error
     }
 
+    /// Have the terminal report mouse clicks, drags and scrolling as input.
+    /// Use input::parse_input() to turn what's read from stdin into events.
+    pub fn enable_mouse(&mut self) -> Result<(), WreckedError> {
+        // Button events, drag events and SGR encoded coordinates
+        RectManager::write_unterminated("\x1B[?1000h\x1B[?1002h\x1B[?1006h")?;
+        self.mouse_enabled = true;
+        Ok(())
+    }
+
+    /// Stop the terminal from reporting mouse input.
+    pub fn disable_mouse(&mut self) -> Result<(), WreckedError> {
+        RectManager::write_unterminated("\x1B[?1006l\x1B[?1002l\x1B[?1000l")?;
+        self.mouse_enabled = false;
+        Ok(())
+    }
+
     /// Gets the height of the RectManager
     pub fn get_height(&self) -> usize {
         let (_, height) = self.get_rect_size(ROOT).unwrap();
@@ -394,17 +670,11 @@ impl RectManager {
                 if new_effects.is_plain() {
                     ansi_code_list.push(0);
                 } else {
-                    let mut tmp_color_n;
                     // ForeGround
                     if new_effects.foreground_color != active_effects.foreground_color {
                         match new_effects.foreground_color {
                             Some(fg_color) => {
-                                tmp_color_n = fg_color as u8;
-                                if tmp_color_n & 8 == 8 {
-                                    ansi_code_list.push(90 + (tmp_color_n & 7));
-                                } else {
-                                    ansi_code_list.push(30 + (tmp_color_n & 7));
-                                }
+                                ansi_code_list.extend(fg_color.get_ansi_codes(false));
                             }
                             None => {
                                 ansi_code_list.push(39);
@@ -416,12 +686,7 @@ impl RectManager {
                     if new_effects.background_color != active_effects.background_color {
                         match new_effects.background_color {
                             Some(bg_color) => {
-                                tmp_color_n = bg_color as u8;
-                                if tmp_color_n & 8 == 8 {
-                                    ansi_code_list.push(100 + (tmp_color_n & 7));
-                                } else {
-                                    ansi_code_list.push(40 + (tmp_color_n & 7));
-                                }
+                                ansi_code_list.extend(bg_color.get_ansi_codes(true));
                             }
                             None => {
                                 ansi_code_list.push(49);
@@ -429,12 +694,23 @@ impl RectManager {
                         }
                     }
 
-                    // Bold
-                    if new_effects.bold != active_effects.bold {
-                        if new_effects.bold {
-                            ansi_code_list.push(1); // on
-                        } else {
+                    // Bold & Dim (which share an 'off' code)
+                    if new_effects.bold != active_effects.bold || new_effects.dim != active_effects.dim {
+                        if (active_effects.bold && !new_effects.bold) || (active_effects.dim && !new_effects.dim) {
                             ansi_code_list.push(22); // off
+                            if new_effects.bold {
+                                ansi_code_list.push(1);
+                            }
+                            if new_effects.dim {
+                                ansi_code_list.push(2);
+                            }
+                        } else {
+                            if new_effects.bold && !active_effects.bold {
+                                ansi_code_list.push(1); // on
+                            }
+                            if new_effects.dim && !active_effects.dim {
+                                ansi_code_list.push(2); // on
+                            }
                         }
                     }
 
@@ -466,6 +742,15 @@ impl RectManager {
                     }
 
                     // Strike
+                    if new_effects.strike != active_effects.strike {
+                        if new_effects.strike {
+                            ansi_code_list.push(9); // on
+                        } else {
+                            ansi_code_list.push(29); // off
+                        }
+                    }
+
+                    // Blink
                     if new_effects.blink != active_effects.blink {
                         if new_effects.blink {
                             ansi_code_list.push(5); // on
@@ -500,6 +785,11 @@ impl RectManager {
 
         let mut update_top_cache;
         for (pos, val) in full_display_map.iter() {
+            // Cells under a bitmap would be drawn over it
+            if self.drawn_graphics.values().any(|graphic| graphic.contains(pos)) {
+                continue;
+            }
+
             update_top_cache = false;
             match self.top_cache.get(&pos) {
                 Some(char_pair) => {
@@ -605,8 +895,8 @@ impl RectManager {
                     Some(parent) => {
                         match parent.get_child_position(working_id) {
                             Some(pos) => {
-                                x += pos.0;
-                                y += pos.1;
+                                x += pos.0 - parent.scroll.0;
+                                y += pos.1 - parent.scroll.1;
                                 working_id = parent.rect_id;
                             }
                             None => {
@@ -760,15 +1050,126 @@ impl RectManager {
     /// rectmanager.kill();
     /// ```
     pub fn draw(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let cursor = self.get_cursor_position();
+        let cursor_string = match cursor {
+            Some((x, y)) => {
+                format!("\x1B[{};{}H\x1B[?25h", y + 1, x + 1)
+            }
+            None => {
+                "\x1B[1;1H".to_string()
+            }
+        };
+
+        // Graphics need to be settled first so the cells they cover are left out of the cell diff
+        let graphics_string = self.build_graphics_string();
+
         match self.build_latest_rect_string(rect_id) {
             Some(renderstring) => {
-                RectManager::write(&format!("{}\x1B[0m\x1B[1;1H", renderstring))?;
+                // Keep the cursor hidden while it jumps around the screen
+                self.write_output(&format!("\x1B[?25l{}\x1B[0m{}", renderstring, graphics_string))?;
+                self.write_output(&cursor_string)?;
+            }
+            None if !graphics_string.is_empty() => {
+                self.write_output(&format!("\x1B[?25l{}", graphics_string))?;
+                self.write_output(&cursor_string)?;
+            }
+            None => {
+                if cursor != self.drawn_cursor {
+                    if cursor.is_none() {
+                        self.write_output("\x1B[?25l")?;
+                    }
+                    self.write_output(&cursor_string)?;
+                }
             }
-            None => ()
         }
+
+        self.drawn_cursor = cursor;
         Ok(())
     }
 
+    /// Show the terminal's cursor at the given position of a rectangle once it's drawn.
+    /// The cursor is hidden while the position is outside of the visible area of the rectangle, or the rectangle isn't displayed.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.resize(ROOT, 20, 10);
+    /// let rect_id = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(rect_id, 10, 1);
+    /// rectmanager.set_position(rect_id, 2, 3);
+    ///
+    /// rectmanager.set_cursor(rect_id, 4, 0);
+    /// assert_eq!(rectmanager.get_cursor_position(), Some((6, 3)));
+    ///
+    /// rectmanager.hide_cursor();
+    /// assert_eq!(rectmanager.get_cursor_position(), None);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_cursor(&mut self, rect_id: usize, x: isize, y: isize) -> Result<(), WreckedError> {
+        if !self.has_rect(rect_id) {
+            Err(WreckedError::NotFound(rect_id))
+        } else {
+            self.cursor = Some((rect_id, x, y));
+            Ok(())
+        }
+    }
+
+    /// Get the rect and position the terminal's cursor was last set to.
+    pub fn get_cursor(&self) -> Option<(usize, isize, isize)> {
+        self.cursor
+    }
+
+    /// Stop showing the terminal's cursor.
+    pub fn hide_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Get the absolute position the terminal's cursor will be displayed at, if it's visible.
+    pub fn get_cursor_position(&self) -> Option<(isize, isize)> {
+        let (rect_id, x, y) = self.cursor?;
+        let rect = self.get_rect(rect_id)?;
+
+        // Position in the visible area of the rect
+        let visible_x = x - rect.scroll.0;
+        let visible_y = y - rect.scroll.1;
+        if visible_x < 0 || visible_y < 0 || visible_x >= rect.width as isize || visible_y >= rect.height as isize {
+            return None;
+        }
+
+        // Every rect between here and ROOT needs to be enabled,
+        // and the position needs to be inside each of them.
+        let (offset_x, offset_y) = self.get_absolute_offset(rect_id)?;
+        let (absolute_x, absolute_y) = (offset_x + visible_x, offset_y + visible_y);
+        let mut working_id = rect_id;
+        loop {
+            let working_rect = self.get_rect(working_id)?;
+            if !working_rect.enabled {
+                return None;
+            }
+
+            let (rect_x, rect_y) = self.get_absolute_offset(working_id)?;
+            if absolute_x < rect_x || absolute_y < rect_y || absolute_x >= rect_x + working_rect.width as isize || absolute_y >= rect_y + working_rect.height as isize {
+                return None;
+            }
+
+            match working_rect.parent {
+                Some(parent_id) => {
+                    working_id = parent_id;
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        if working_id == ROOT {
+            Some((absolute_x, absolute_y))
+        } else {
+            None
+        }
+    }
+
     /// Resize a rectangle
     /// # Example
     /// ```
@@ -780,8 +1181,10 @@ impl RectManager {
     /// assert_eq!((10, 10), rectmanager.get_rect_size(rect_id).unwrap());
     /// ```
     pub fn resize(&mut self, rect_id: usize, width: usize, height: usize) -> Result<(), WreckedError> {
+        let old_size = self.get_rect_size(rect_id);
+
         // First check if there will be an artifact left by the resize
-        let has_artifact = match self.get_rect_size(rect_id) {
+        let has_artifact = match old_size {
             Some((old_width, old_height)) => {
                 old_width > width || old_height > height
             }
@@ -820,6 +1223,17 @@ impl RectManager {
 
         self.flag_refresh(rect_id)?;
 
+        match old_size {
+            Some(old_size) => {
+                if old_size != (width, height) {
+                    self.apply_anchors(rect_id, old_size)?;
+                }
+            }
+            None => ()
+        }
+
+        self.apply_layout(rect_id)?;
+
         Ok(())
     }
 
@@ -939,6 +1353,7 @@ impl RectManager {
         if has_parent {
             self.update_child_space(rect_id)?;
             self.flag_parent_refresh(rect_id)?;
+            self.update_anchor_margins(rect_id)?;
         }
 
         Ok(())
@@ -992,6 +1407,7 @@ impl RectManager {
 
         if ! was_enabled {
             self.update_child_space(rect_id)?;
+            self.flag_dim_refresh(rect_id)?;
         }
 
         Ok(())
@@ -1090,6 +1506,7 @@ impl RectManager {
     /// rectmanager.kill();
     /// ```
     pub fn detach(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        self.flag_dim_refresh(rect_id)?;
         self.flag_parent_refresh(rect_id);
         self.clear_child_space(rect_id)?;
 
@@ -1149,6 +1566,7 @@ impl RectManager {
         }
 
         self.set_position(rect_id, 0, 0)?;
+        self.flag_dim_refresh(rect_id)?;
 
         Ok(())
     }
@@ -1165,7 +1583,7 @@ impl RectManager {
     pub fn set_string(&mut self, rect_id: usize, start_x: isize, start_y: isize, string: &str) -> Result<(), WreckedError> {
         let mut dimensions = (0, 0);
 
-        match self.get_rect_size(rect_id) {
+        match self.get_content_size(rect_id) {
             Some(_dim) => {
                 dimensions = (_dim.0 as isize, _dim.1 as isize);
             }
@@ -1208,159 +1626,254 @@ impl RectManager {
         Ok(())
     }
 
-    /// Set the character at the given position of a rectangle.
+    /// Set the base direction of text in the given rect, used by set_bidi_string() and set_text().
+    /// Right-to-left text starts from the right edge of the rect.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::bidi::TextDirection;
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_character(ROOT, 0, 0, 'X');
-    /// assert_eq!(rectmanager.get_character(ROOT, 0, 0).ok().unwrap(), 'X');
+    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.set_text_direction(rect, TextDirection::RightToLeft);
+    /// assert_eq!(rectmanager.get_text_direction(rect), Some(TextDirection::RightToLeft));
     /// rectmanager.kill();
     /// ```
-    pub fn set_character(&mut self, rect_id: usize, x: isize, y: isize, character: char) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
+    pub fn set_text_direction(&mut self, rect_id: usize, direction: TextDirection) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                rect.set_character(x, y, character)
+                rect.text_direction = direction;
+                Ok(())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
-        }?;
-
-        if changed {
-            self.flag_pos_refresh(rect_id, x, y)
-        } else {
-            Ok(())
         }
     }
 
-    /// Delete a set character of a given rectangle at specified point
+    pub fn get_text_direction(&self, rect_id: usize) -> Option<TextDirection> {
+        self.get_rect(rect_id).map(|rect| rect.text_direction)
+    }
+
+    /// Set a line of text in logical order, as with set_string(), applying the bidirectional algorithm to
+    /// lay it out in visual order. In a right-to-left rect, `start_x` counts from the right edge.
+    /// Doesn't wrap, and will throw an error if the text doesn't fit on the line.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::bidi::TextDirection;
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_character(ROOT, 0, 0, 'X');
-    /// rectmanager.unset_character(ROOT, 0, 0);
-    /// assert_eq!(rectmanager.get_character(ROOT, 0, 0).ok().unwrap(), rectmanager.get_default_character(ROOT));
+    /// rectmanager.resize(ROOT, 10, 1);
+    /// rectmanager.set_text_direction(ROOT, TextDirection::RightToLeft);
+    /// rectmanager.set_bidi_string(ROOT, 0, 0, "שלום!");
+    /// assert_eq!(rectmanager.get_character(ROOT, 5, 0).ok(), Some('!'));
+    /// assert_eq!(rectmanager.get_character(ROOT, 9, 0).ok(), Some('ש'));
     /// rectmanager.kill();
     /// ```
-    pub fn unset_character(&mut self, rect_id: usize, x: isize, y: isize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                rect.unset_character(x, y)
+    pub fn set_bidi_string(&mut self, rect_id: usize, start_x: isize, start_y: isize, string: &str) -> Result<(), WreckedError> {
+        let (width, direction) = match (self.get_content_size(rect_id), self.get_text_direction(rect_id)) {
+            (Some(size), Some(direction)) => (size.0 as isize, direction),
+            _ => {
+                return Err(WreckedError::NotFound(rect_id));
             }
-            None => {
-                Err(WreckedError::NotFound(rect_id))
-            }
-        }?;
+        };
 
-        if changed {
-            self.flag_refresh(rect_id)
+        let characters: Vec<char> = string.chars().collect();
+        let rtl = bidi::is_rtl(direction, &characters);
+        let length = characters.len() as isize;
+        let x = if rtl {
+            width - start_x - length
         } else {
-            Ok(())
+            start_x
+        };
+        if x < 0 || x + length > width {
+            return Err(WreckedError::StringTooLong(rect_id, (start_x, start_y), string.to_string()));
         }
+
+        let visual: String = bidi::reorder_chars(&characters, rtl).into_iter().collect();
+        self.set_string(rect_id, x, start_y, &visual)
     }
 
-    /// Completely erase a rectangle & remove it from the RectManager's tree.
+    /// Set ANSI-escaped text, like colored command output, starting at the specified position of the given rectangle.
+    /// SGR sequences style the characters that follow them; characters without a style use the rectangle's effects.
+    /// `\n` moves to the start position's column on the next row, `\r` back to that column, and tabs to the next
+    /// multiple of 8 columns. Other escape sequences are dropped, and characters outside of the rectangle are left out.
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::{RectManager, ROOT, Color};
     /// let mut rectmanager = RectManager::new();
-    /// let mut rect = rectmanager.new_rect(ROOT).ok().unwrap();
-    ///
-    /// rectmanager.delete_rect(rect);
-    /// assert!(!rectmanager.has_rect(rect));
+    /// rectmanager.resize(ROOT, 20, 2);
+    /// rectmanager.set_ansi_string(ROOT, 0, 0, "\x1B[1;31merror\x1B[0m: oops\n\x1B[38;5;208mnote");
     ///
+    /// let style = rectmanager.get_character_style(ROOT, 0, 0).unwrap();
+    /// assert!(style.bold);
+    /// assert_eq!(style.foreground_color, Some(Color::RED));
+    /// assert_eq!(rectmanager.get_character_style(ROOT, 5, 0), None);
+    /// assert_eq!(rectmanager.get_character_style(ROOT, 0, 1).unwrap().foreground_color, Some(Color::Fixed(208)));
     /// rectmanager.kill();
     /// ```
-    pub fn delete_rect(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let mut to_delete = Vec::new();
-        let mut stack = vec![rect_id];
+    pub fn set_ansi_string(&mut self, rect_id: usize, start_x: isize, start_y: isize, string: &str) -> Result<(), WreckedError> {
+        let (width, height) = match self.get_content_size(rect_id) {
+            Some(size) => (size.0 as isize, size.1 as isize),
+            None => {
+                return Err(WreckedError::NotFound(rect_id));
+            }
+        };
 
-        while stack.len() > 0 {
-            match stack.pop() {
-                Some(working_id) => {
-                    match self.get_rect_mut(working_id) {
-                        Some(rect) => {
-                            stack.extend(rect.children.iter().copied());
-                            to_delete.push(working_id);
-                        }
-                        // don't throw an error here. it may be the case that the
-                        // parent still needs to be deleted even though the children are missing
-                        None => ()
+        let mut cells: Vec<(isize, isize, char, Style)> = vec![];
+        let mut style = Style::default();
+        let (mut x, mut y) = (start_x, start_y);
+        for token in AnsiParser::new().feed(string) {
+            match token {
+                AnsiToken::Character(character) => {
+                    cells.push((x, y, character, style));
+                    x += 1;
+                }
+                AnsiToken::Control('\n') => {
+                    x = start_x;
+                    y += 1;
+                }
+                AnsiToken::Control('\r') => {
+                    x = start_x;
+                }
+                AnsiToken::Control('\t') => {
+                    let tab_stop = start_x + ((x - start_x) / 8 + 1) * 8;
+                    while x < tab_stop {
+                        cells.push((x, y, ' ', style));
+                        x += 1;
                     }
                 }
-                None => {
-                    break;
+                AnsiToken::Control('\x08') => {
+                    x = cmp::max(start_x, x - 1);
+                }
+                AnsiToken::Csi { private: None, params, final_char: 'm' } => {
+                    ansi::apply_sgr(&mut style, &params);
                 }
+                _ => {}
             }
         }
 
-        self.clear_child_space(rect_id)?;
-        match self.get_parent_mut(rect_id) {
-            Some(parent) => {
-                parent.detach_child(rect_id);
+        for (x, y, character, style) in cells {
+            if x < 0 || y < 0 || x >= width || y >= height {
+                continue;
             }
-            None => {
-                Err(WreckedError::NotFound(rect_id))?;
+            self.set_character(rect_id, x, y, character)?;
+            if style == Style::default() {
+                self.unset_character_style(rect_id, x, y)?;
+            } else {
+                self.set_character_style(rect_id, x, y, style)?;
             }
         }
 
-        for id in to_delete.iter() {
-            self.rects.remove(&id);
-            self.recycle_id(*id);
-        }
-
         Ok(())
     }
 
-    /// Swap out one rectangle with another.
+    /// Replace the characters of a rectangle with text, word-wrapped and aligned to fit.
+    /// Lines are broken at `\n`, and tabs are expanded to spaces. See TextOptions for what happens
+    /// to text that doesn't fit. Right-to-left paragraphs (see set_text_direction()) start from the right edge.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::text::{TextOptions, TextAlignment, Overflow};
     /// let mut rectmanager = RectManager::new();
-    /// // Create a rectangle that is attached to ROOT.
-    /// let rect_a = rectmanager.new_rect(ROOT).ok().unwrap();
-    /// // Create an orphan rectangle to switch in.
-    /// let rect_b = rectmanager.new_orphan().ok().unwrap();
-    /// rectmanager.replace_with(rect_a, rect_b);
+    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(rect, 10, 1);
     ///
-    /// assert!(rectmanager.has_parent(rect_b));
-    /// assert!(!rectmanager.has_parent(rect_a));
+    /// let options = TextOptions {
+    ///     alignment: TextAlignment::Right,
+    ///     overflow: Overflow::Grow,
+    ///     ..TextOptions::default()
+    /// };
+    /// rectmanager.set_text(rect, "The quick brown fox", options);
+    /// assert_eq!(rectmanager.get_rect_size(rect), Some((10, 2)));
+    /// assert_eq!(rectmanager.get_character(rect, 9, 1).ok(), Some('x'));
+    ///
+    /// rectmanager.kill();
     /// ```
-    pub fn replace_with(&mut self, old_rect_id: usize, new_rect_id: usize) -> Result<(), WreckedError> {
-        let mut parent_id = ROOT;
-        let mut old_position = (0, 0);
-        match self.get_parent_mut(old_rect_id) {
-            Some(parent) => {
-                parent_id = parent.rect_id;
-                old_position = *parent.child_positions.get(&old_rect_id).unwrap();
-            }
+    pub fn set_text(&mut self, rect_id: usize, text: &str, options: TextOptions) -> Result<(), WreckedError> {
+        let (mut width, mut height) = match self.get_content_size(rect_id) {
+            Some(size) => size,
             None => {
-                Err(WreckedError::NotFound(old_rect_id))?;
+                return Err(WreckedError::NotFound(rect_id));
+            }
+        };
+
+        let lines = text::wrap_text(text, width, &options);
+        let longest = lines.iter().map(|line| line.text.len()).max().unwrap_or(0);
+        let overflows = lines.len() > height || (!options.wrap && longest > width);
+
+        let mut ellipsis_line = None;
+        if overflows {
+            match options.overflow {
+                Overflow::Error => {
+                    return Err(WreckedError::StringTooLong(rect_id, (0, 0), text.to_string()));
+                }
+                Overflow::Grow => {
+                    let size = (width.max(longest), height.max(lines.len()));
+                    // Grow the content area instead if it's been made larger than the rect
+                    if self.get_rect_size(rect_id) == Some((width, height)) {
+                        self.resize(rect_id, size.0, size.1)?;
+                    } else {
+                        self.set_content_size(rect_id, size.0, size.1)?;
+                    }
+                    width = size.0;
+                    height = size.1;
+                }
+                Overflow::Ellipsis => {
+                    if lines.len() > height && height > 0 {
+                        ellipsis_line = Some(height - 1);
+                    }
+                }
+                Overflow::Clip => {}
             }
         }
 
-        self.detach(old_rect_id)?;
-        self.attach(new_rect_id, parent_id)?;
-        self.set_position(new_rect_id, old_position.0, old_position.1)?;
+        // Each paragraph is laid out in the rect's text direction, or in its own with TextDirection::Auto
+        let direction = self.get_text_direction(rect_id).unwrap_or_default();
+        let mut line_directions = Vec::with_capacity(lines.len());
+        let mut start = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if line.last {
+                let paragraph: Vec<char> = lines[start ..= i].iter().flat_map(|line| line.text.iter().copied()).collect();
+                line_directions.extend(std::iter::repeat_n(bidi::is_rtl(direction, &paragraph), i + 1 - start));
+                start = i + 1;
+            }
+        }
+
+        self.clear_characters(rect_id)?;
+        for (y, line) in lines.iter().take(height).enumerate() {
+            let mut line = line.clone();
+            if options.overflow == Overflow::Ellipsis {
+                if Some(y) == ellipsis_line {
+                    // More lines follow, so the ellipsis goes after what's shown of this one
+                    line.text = text::ellipsize(&line.text, width);
+                    line.last = true;
+                } else if line.text.len() > width {
+                    line.text = text::ellipsize(&line.text, width);
+                }
+            }
+
+            let (x, characters) = text::align_line(&line, width, options.alignment, line_directions[y]);
+            for (offset, character) in characters.into_iter().take(width.saturating_sub(x)).enumerate() {
+                self.set_character(rect_id, (x + offset) as isize, y as isize, character)?;
+            }
+        }
 
         Ok(())
     }
 
-    /// Apply bold effect to text of given rect (does not apply recursively).
+    /// Set the character at the given position of a rectangle.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_bold_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Bold Text");
+    /// rectmanager.set_character(ROOT, 0, 0, 'X');
+    /// assert_eq!(rectmanager.get_character(ROOT, 0, 0).ok().unwrap(), 'X');
     /// rectmanager.kill();
     /// ```
-    pub fn set_bold_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn set_character(&mut self, rect_id: usize, x: isize, y: isize, character: char) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.set_bold_flag())
+                rect.set_character(x, y, character)
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
@@ -1368,26 +1881,26 @@ impl RectManager {
         }?;
 
         if changed {
-            self.flag_refresh(rect_id)
+            self.flag_pos_refresh(rect_id, x, y)
         } else {
             Ok(())
         }
     }
 
-    /// Disable bold text effect
+    /// Delete a set character of a given rectangle at specified point
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_bold_flag(ROOT);
-    /// rectmanager.unset_bold_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// rectmanager.set_character(ROOT, 0, 0, 'X');
+    /// rectmanager.unset_character(ROOT, 0, 0);
+    /// assert_eq!(rectmanager.get_character(ROOT, 0, 0).ok().unwrap(), rectmanager.get_default_character(ROOT));
     /// rectmanager.kill();
     /// ```
-    pub fn unset_bold_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn unset_character(&mut self, rect_id: usize, x: isize, y: isize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_bold_flag())
+                rect.unset_character(x, y)
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
@@ -1401,251 +1914,374 @@ impl RectManager {
         }
     }
 
-    /// Apply underline effect to text of given rect (does not apply recursively).
+    /// Apply text effects to a single character of a rectangle, in place of the rectangle's own effects.
+    /// The style stays in place if the character is changed.
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::{RectManager, ROOT, Style, Color};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_underline_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Underlined Text");
+    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(rect, 10, 1);
+    /// rectmanager.set_string(rect, 0, 0, "Warning!");
+    ///
+    /// let style = Style { foreground_color: Some(Color::RED), ..Style::default() };
+    /// rectmanager.set_character_style(rect, 7, 0, style);
+    /// assert_eq!(rectmanager.get_character_style(rect, 7, 0), Some(style));
+    ///
     /// rectmanager.kill();
     /// ```
-    pub fn set_underline_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn set_character_style(&mut self, rect_id: usize, x: isize, y: isize, style: Style) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.set_underline_flag())
+                rect.set_character_style(x, y, Some(style.into()))
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
+
         if changed {
-            self.flag_refresh(rect_id)
+            self.flag_pos_refresh(rect_id, x, y)
         } else {
             Ok(())
         }
     }
 
-    /// Disable underline text effect
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT};
-    /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_underline_flag(ROOT);
-    /// rectmanager.unset_underline_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
-    /// rectmanager.kill();
-    /// ```
-    pub fn unset_underline_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    /// Return a character to using the effects of its rectangle.
+    pub fn unset_character_style(&mut self, rect_id: usize, x: isize, y: isize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_underline_flag())
+                rect.set_character_style(x, y, None)
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
+
         if changed {
-            self.flag_refresh(rect_id)
+            self.flag_pos_refresh(rect_id, x, y)
         } else {
             Ok(())
         }
     }
 
-    /// Invert the background and foreground colors of the text of the given rect (does not apply recursively).
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT};
-    /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_invert_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Inverted Text");
-    /// rectmanager.kill();
-    /// ```
-    pub fn set_invert_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
+    /// Get the style applied to a single character, if there is one.
+    pub fn get_character_style(&self, rect_id: usize, x: isize, y: isize) -> Option<Style> {
+        match self.get_rect(rect_id) {
             Some(rect) => {
-                Ok(rect.set_invert_flag())
+                rect.style_space.get(&(x, y)).map(|effects| Style::from(*effects))
             }
             None => {
-                Err(WreckedError::NotFound(rect_id))
+                None
             }
-        }?;
-        if changed {
-            self.flag_refresh(rect_id)
-        } else {
-            Ok(())
         }
     }
 
-    /// Disable invert text effect
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT};
-    /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_invert_flag(ROOT);
-    /// rectmanager.unset_invert_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
-    /// rectmanager.kill();
-    /// ```
-    pub fn unset_invert_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
+    /// Remove the styles of all the characters of a rectangle.
+    pub fn clear_character_styles(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_invert_flag())
+                rect.style_space.clear();
             }
             None => {
-                Err(WreckedError::NotFound(rect_id))
+                Err(WreckedError::NotFound(rect_id))?;
             }
-        }?;
-        if changed {
-            self.flag_refresh(rect_id)
-        } else {
-            Ok(())
-        }
+        };
+        self.flag_refresh(rect_id)
     }
 
-    /// Apply italics effect to text of given rect (does not apply recursively).
+    /// Draw an image into a rectangle with true color half blocks, so each cell shows two pixels.
+    /// Cells the image doesn't cover are left as they are.
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::image::{RgbImage, FitMode};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_italics_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Italicized Text");
+    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(rect, 1, 1);
+    ///
+    /// let mut image = RgbImage::new(1, 2);
+    /// image.set_pixel(0, 0, (255, 128, 0));
+    /// rectmanager.draw_image(rect, &image, FitMode::Stretch);
+    ///
+    /// assert_eq!(rectmanager.get_character(rect, 0, 0).ok(), Some('▀'));
+    /// let style = rectmanager.get_character_style(rect, 0, 0).unwrap();
+    /// assert_eq!(style.foreground_color, Some(Color::Rgb(255, 128, 0)));
+    /// assert_eq!(style.background_color, Some(Color::Rgb(0, 0, 0)));
+    ///
     /// rectmanager.kill();
     /// ```
-    pub fn set_italics_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                Ok(rect.set_italics_flag())
-            }
+    pub fn draw_image(&mut self, rect_id: usize, image: &RgbImage, fit_mode: FitMode) -> Result<(), WreckedError> {
+        self.draw_image_with_palette(rect_id, image, fit_mode, Palette::TrueColor, false)
+    }
+
+    /// Draw an image into a rectangle using the colors of the given palette, for terminals without true color.
+    /// With `dither`, Floyd-Steinberg dithering is used to make up for the missing colors.
+    pub fn draw_image_with_palette(&mut self, rect_id: usize, image: &RgbImage, fit_mode: FitMode, palette: Palette, dither: bool) -> Result<(), WreckedError> {
+        let (width, height) = match self.get_rect_size(rect_id) {
+            Some(size) => size,
             None => {
-                Err(WreckedError::NotFound(rect_id))
+                return Err(WreckedError::NotFound(rect_id));
+            }
+        };
+
+        let cells = image::get_image_cells(image, fit_mode, palette, dither, width, height);
+        for (index, cell) in cells.into_iter().enumerate() {
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            if let Some((character, foreground_color, background_color)) = cell {
+                self.set_character(rect_id, x, y, character)?;
+                self.set_character_style(rect_id, x, y, Style {
+                    foreground_color,
+                    background_color,
+                    ..Style::default()
+                })?;
             }
-        }?;
-        if changed {
-            self.flag_refresh(rect_id)
-        } else {
-            Ok(())
         }
+
+        Ok(())
     }
 
-    /// Disable italics text effect
+    /// Draw large text with a FIGlet font, with its top left corner at (x, y).
+    /// Each line of `text` is drawn below the previous one. Parts outside the rectangle are cut off.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::figlet::FigFont;
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_italics_flag(ROOT);
-    /// rectmanager.unset_italics_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// rectmanager.resize(ROOT, 40, 10);
+    /// let clock = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(clock, 20, 5);
+    ///
+    /// rectmanager.set_banner(clock, 0, 0, "12:30", &FigFont::block());
+    /// assert_eq!(rectmanager.get_character(clock, 1, 0).ok(), Some('█'));
+    ///
     /// rectmanager.kill();
     /// ```
-    pub fn unset_italics_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                Ok(rect.unset_italics_flag())
-            }
+    pub fn set_banner(&mut self, rect_id: usize, x: isize, y: isize, text: &str, font: &FigFont) -> Result<(), WreckedError> {
+        let rows = font.render(text);
+        self.set_banner_rows(rect_id, y, &rows, |_| x)
+    }
+
+    /// Draw large text with a FIGlet font starting at row `y`, with each line aligned within the rectangle.
+    pub fn set_banner_aligned(&mut self, rect_id: usize, y: isize, text: &str, font: &FigFont, alignment: Alignment) -> Result<(), WreckedError> {
+        let width = match self.get_rect_size(rect_id) {
+            Some((width, _)) => width as isize,
             None => {
-                Err(WreckedError::NotFound(rect_id))
+                return Err(WreckedError::NotFound(rect_id));
             }
-        }?;
-        if changed {
-            self.flag_refresh(rect_id)
-        } else {
-            Ok(())
-        }
-    }
+        };
 
-    /// Apply strike effect to text of given rect (does not apply recursively).
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT};
-    /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_strike_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Text With Strikethrough");
-    /// rectmanager.kill();
-    /// ```
-    pub fn set_strike_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                Ok(rect.set_strike_flag())
+        let rows = font.render(text);
+        self.set_banner_rows(rect_id, y, &rows, |row_width| {
+            match alignment {
+                Alignment::Left => 0,
+                Alignment::Right => width - row_width as isize,
+                Alignment::Center => (width - row_width as isize) / 2
             }
+        })
+    }
+
+    // Draw the rows of a banner, clipped to the rectangle. Each line of the banner is placed at the x
+    // position given by `get_x` for its width.
+    fn set_banner_rows<F: Fn(usize) -> isize>(&mut self, rect_id: usize, y: isize, rows: &[String], get_x: F) -> Result<(), WreckedError> {
+        let (width, height) = match self.get_rect_size(rect_id) {
+            Some(size) => (size.0 as isize, size.1 as isize),
             None => {
-                Err(WreckedError::NotFound(rect_id))
+                return Err(WreckedError::NotFound(rect_id));
             }
-        }?;
+        };
 
-        if changed {
-            self.flag_refresh(rect_id)
-        } else {
-            Ok(())
+        for (row_y, row) in (y ..).zip(rows.iter()) {
+            if row_y < 0 || row_y >= height {
+                continue;
+            }
+            let row_x = get_x(row.chars().count());
+            for (x, character) in (row_x ..).zip(row.chars()) {
+                if x >= 0 && x < width {
+                    self.set_character(rect_id, x, row_y, character)?;
+                }
+            }
         }
+
+        Ok(())
     }
 
-    /// Disable strike text effect
+    /// Show bitmaps set with set_bitmap() using real pixels, with Sixel or kitty graphics escapes.
+    /// With None (the default), bitmaps aren't shown and their rects' cells are drawn as usual,
+    /// so draw_image() can be used as a fallback.
+    pub fn set_graphics_protocol(&mut self, protocol: Option<GraphicsProtocol>) {
+        self.graphics_protocol = protocol;
+    }
+
+    pub fn get_graphics_protocol(&self) -> Option<GraphicsProtocol> {
+        self.graphics_protocol
+    }
+
+    /// Set the size of a cell in pixels, which Sixel images are scaled with. Defaults to 10x20.
+    pub fn set_cell_pixel_size(&mut self, width: usize, height: usize) {
+        self.cell_pixel_size = (cmp::max(width, 1), cmp::max(height, 1));
+        self.drawn_graphics.retain(|_, graphic| graphic.protocol != GraphicsProtocol::Sixel);
+    }
+
+    /// Show a bitmap over a rectangle, stretched to fill it, once a graphics protocol is set.
+    /// The cells it covers are left alone when drawing, and it's only sent to the terminal again when it's
+    /// changed or the rectangle moves or resizes. It isn't shown while any part of the rectangle is hidden,
+    /// or while anything else, such as a dialog or one of the rectangle's children, is drawn over it.
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::{RectManager, ROOT, HeadlessSink};
+    /// use wrecked::image::{RgbImage, GraphicsProtocol};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_strike_flag(ROOT);
-    /// rectmanager.unset_strike_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// rectmanager.resize(ROOT, 20, 10);
+    /// let sink = HeadlessSink::new();
+    /// rectmanager.set_output(sink.clone());
+    /// rectmanager.set_graphics_protocol(Some(GraphicsProtocol::Kitty));
+    ///
+    /// let thumbnail = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(thumbnail, 4, 2);
+    /// rectmanager.set_bitmap(thumbnail, RgbImage::new(8, 8));
+    ///
+    /// rectmanager.render();
+    /// assert!(String::from_utf8(sink.take()).unwrap().contains("\x1B_Ga=T"));
+    /// // Nothing has changed, so the image isn't sent again
+    /// rectmanager.render();
+    /// assert!(sink.take().is_empty());
+    ///
     /// rectmanager.kill();
     /// ```
-    pub fn unset_strike_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                Ok(rect.unset_strike_flag())
-            }
-            None => {
-                Err(WreckedError::NotFound(rect_id))
-            }
-        }?;
-        if changed {
-            self.flag_refresh(rect_id)
+    pub fn set_bitmap(&mut self, rect_id: usize, image: RgbImage) -> Result<(), WreckedError> {
+        if !self.has_rect(rect_id) {
+            Err(WreckedError::NotFound(rect_id))
         } else {
+            self.bitmap_generation += 1;
+            self.bitmaps.insert(rect_id, (image, self.bitmap_generation));
             Ok(())
         }
     }
 
-    /// Apply blink effect to text of given rect (does not apply recursively).
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT};
-    /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_blink_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Some Blinking Text");
-    /// rectmanager.kill();
-    /// ```
-    pub fn set_blink_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
-        let changed = match self.get_rect_mut(rect_id) {
-            Some(rect) => {
-                Ok(rect.set_blink_flag())
-            }
-            None => {
-                Err(WreckedError::NotFound(rect_id))
-            }
-        }?;
-
-        if changed {
-            self.flag_refresh(rect_id)
+    /// Stop showing a rectangle's bitmap. The cells under it are drawn again.
+    pub fn unset_bitmap(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        if !self.has_rect(rect_id) {
+            Err(WreckedError::NotFound(rect_id))
         } else {
+            self.bitmaps.remove(&rect_id);
             Ok(())
         }
     }
 
-    /// Disable blink text effect
+    pub fn get_bitmap(&self, rect_id: usize) -> Option<&RgbImage> {
+        self.bitmaps.get(&rect_id).map(|(image, _)| image)
+    }
+
+    /// Completely erase a rectangle & remove it from the RectManager's tree.
     /// # Example
     /// ```
     /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// rectmanager.set_blink_flag(ROOT);
-    /// rectmanager.unset_blink_flag(ROOT);
-    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// let mut rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    ///
+    /// rectmanager.delete_rect(rect);
+    /// assert!(!rectmanager.has_rect(rect));
+    ///
     /// rectmanager.kill();
     /// ```
-    pub fn unset_blink_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn delete_rect(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let mut to_delete = Vec::new();
+        let mut stack = vec![rect_id];
+
+        while stack.len() > 0 {
+            match stack.pop() {
+                Some(working_id) => {
+                    match self.get_rect_mut(working_id) {
+                        Some(rect) => {
+                            stack.extend(rect.children.iter().copied());
+                            to_delete.push(working_id);
+                        }
+                        // don't throw an error here. it may be the case that the
+                        // parent still needs to be deleted even though the children are missing
+                        None => ()
+                    }
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        self.flag_dim_refresh(rect_id)?;
+        self.clear_child_space(rect_id)?;
+        match self.get_parent_mut(rect_id) {
+            Some(parent) => {
+                parent.detach_child(rect_id);
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        for id in to_delete.iter() {
+            self.rects.remove(&id);
+            self.recycle_id(*id);
+        }
+
+        // Don't let the cursor follow the id onto a new rect
+        if let Some((cursor_rect_id, _, _)) = self.cursor {
+            if to_delete.contains(&cursor_rect_id) {
+                self.cursor = None;
+            }
+        }
+        self.bitmaps.retain(|id, _| !to_delete.contains(id));
+
+        Ok(())
+    }
+
+    /// Swap out one rectangle with another.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// // Create a rectangle that is attached to ROOT.
+    /// let rect_a = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// // Create an orphan rectangle to switch in.
+    /// let rect_b = rectmanager.new_orphan().ok().unwrap();
+    /// rectmanager.replace_with(rect_a, rect_b);
+    ///
+    /// assert!(rectmanager.has_parent(rect_b));
+    /// assert!(!rectmanager.has_parent(rect_a));
+    /// ```
+    pub fn replace_with(&mut self, old_rect_id: usize, new_rect_id: usize) -> Result<(), WreckedError> {
+        let mut parent_id = ROOT;
+        let mut old_position = (0, 0);
+        match self.get_parent_mut(old_rect_id) {
+            Some(parent) => {
+                parent_id = parent.rect_id;
+                old_position = *parent.child_positions.get(&old_rect_id).unwrap();
+            }
+            None => {
+                Err(WreckedError::NotFound(old_rect_id))?;
+            }
+        }
+
+        self.detach(old_rect_id)?;
+        self.attach(new_rect_id, parent_id)?;
+        self.set_position(new_rect_id, old_position.0, old_position.1)?;
+
+        Ok(())
+    }
+
+    /// Apply bold effect to text of given rect (does not apply recursively).
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.set_bold_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Bold Text");
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_bold_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_blink_flag())
+                Ok(rect.set_bold_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
@@ -1659,20 +2295,20 @@ impl RectManager {
         }
     }
 
-    /// Set color of background of given rect (does not apply recursively)
+    /// Disable bold text effect
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// // Give Top a Green background
-    /// rectmanager.set_fg_color(ROOT, Color::GREEN);
-    ///
+    /// rectmanager.set_bold_flag(ROOT);
+    /// rectmanager.unset_bold_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
     /// rectmanager.kill();
     /// ```
-    pub fn set_bg_color(&mut self, rect_id: usize, color: Color) -> Result<(), WreckedError> {
+    pub fn unset_bold_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.set_bg_color(color))
+                Ok(rect.unset_bold_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
@@ -1686,29 +2322,24 @@ impl RectManager {
         }
     }
 
-    /// Return background color to default
+    /// Apply underline effect to text of given rect (does not apply recursively).
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// // Give Top a Magenta background
-    /// rectmanager.set_bg_color(ROOT, Color::MAGENTA);
-    ///
-    /// // Remove those colors...
-    /// rectmanager.unset_bg_color(ROOT);
-    ///
+    /// rectmanager.set_underline_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Underlined Text");
     /// rectmanager.kill();
     /// ```
-    pub fn unset_bg_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn set_underline_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_bg_color())
+                Ok(rect.set_underline_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
-
         if changed {
             self.flag_refresh(rect_id)
         } else {
@@ -1716,26 +2347,25 @@ impl RectManager {
         }
     }
 
-    /// Set color of foreground (text) of given rect (does not apply recursively)
+    /// Disable underline text effect
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// // Give Top a YELLOW foreground
-    /// rectmanager.set_fg_color(ROOT, Color::YELLOW);
-    ///
+    /// rectmanager.set_underline_flag(ROOT);
+    /// rectmanager.unset_underline_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
     /// rectmanager.kill();
     /// ```
-    pub fn set_fg_color(&mut self, rect_id: usize, color: Color) -> Result<(), WreckedError> {
+    pub fn unset_underline_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.set_fg_color(color))
+                Ok(rect.unset_underline_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
-
         if changed {
             self.flag_refresh(rect_id)
         } else {
@@ -1743,29 +2373,24 @@ impl RectManager {
         }
     }
 
-    /// Return foreground color to default
+    /// Invert the background and foreground colors of the text of the given rect (does not apply recursively).
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// // Give Top a White foreground
-    /// rectmanager.set_fg_color(ROOT, Color::WHITE);
-    ///
-    /// // Remove those colors...
-    /// rectmanager.unset_fg_color(ROOT);
-    ///
+    /// rectmanager.set_invert_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Inverted Text");
     /// rectmanager.kill();
     /// ```
-    pub fn unset_fg_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn set_invert_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_fg_color())
+                Ok(rect.set_invert_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
-
         if changed {
             self.flag_refresh(rect_id)
         } else {
@@ -1773,131 +2398,834 @@ impl RectManager {
         }
     }
 
-    /// Return both background and foreground colors to default
+    /// Disable invert text effect
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// // Give Top a Blue background and a White foreground
-    /// rectmanager.set_bg_color(ROOT, Color::BLUE);
-    /// rectmanager.set_fg_color(ROOT, Color::WHITE);
-    ///
-    /// // Remove those colors...
-    /// rectmanager.unset_color(ROOT);
-    ///
+    /// rectmanager.set_invert_flag(ROOT);
+    /// rectmanager.unset_invert_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
     /// rectmanager.kill();
     /// ```
-    pub fn unset_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+    pub fn unset_invert_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                Ok(rect.unset_color())
+                Ok(rect.unset_invert_flag())
             }
             None => {
                 Err(WreckedError::NotFound(rect_id))
             }
         }?;
-
         if changed {
             self.flag_refresh(rect_id)
         } else {
             Ok(())
         }
     }
-    /// Get the fallback character that would be displayed where no character is set.
-    /// Defaults to ' '.
-    pub fn get_default_character(&self, rect_id: usize) -> char {
-        match self.get_rect(rect_id) {
+
+    /// Apply italics effect to text of given rect (does not apply recursively).
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.set_italics_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Italicized Text");
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_italics_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                rect.get_default_character()
+                Ok(rect.set_italics_flag())
             }
             None => {
-                self.default_character
+                Err(WreckedError::NotFound(rect_id))
             }
+        }?;
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
         }
     }
 
-    /// Get id of parent rectangle
+    /// Disable italics text effect
     /// # Example
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    ///
-    /// let mut rect = rectmanager.new_rect(ROOT);
-    /// assert_eq!(rectmanager.get_parent_id(rect), Some(ROOT));
-    ///
-    /// rectmanager.detach(rect);
-    /// assert_eq!(rectmanager.get_parent_id(rect), None);
-    ///
+    /// rectmanager.set_italics_flag(ROOT);
+    /// rectmanager.unset_italics_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
     /// rectmanager.kill();
     /// ```
-    pub fn get_parent_id(&self, rect_id: usize) -> Option<usize> {
-        let mut output = None;
-
-        match self.get_rect(rect_id) {
+    pub fn unset_italics_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                output = rect.parent;
+                Ok(rect.unset_italics_flag())
             }
-            None => ()
-        };
-
-        output
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
     }
 
-    /// Check if given Rect is connected to a parent.
+    /// Apply strike effect to text of given rect (does not apply recursively).
     /// # Example
     /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
+    /// use wrecked::{RectManager, ROOT};
     /// let mut rectmanager = RectManager::new();
-    /// let mut rect = rectmanager.new_rect(ROOT).ok().unwrap();
-    /// assert!(rectmanager.has_parent(rect));
-    ///
-    /// rectmanager.detach(rect);
-    /// assert!(!rectmanager.has_parent(rect));
-    ///
+    /// rectmanager.set_strike_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Text With Strikethrough");
     /// rectmanager.kill();
     /// ```
-    pub fn has_parent(&self, rect_id: usize) -> bool {
-        match self.get_rect(rect_id) {
+    pub fn set_strike_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.set_strike_flag())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Disable strike text effect
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.set_strike_flag(ROOT);
+    /// rectmanager.unset_strike_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// rectmanager.kill();
+    /// ```
+    pub fn unset_strike_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.unset_strike_flag())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Apply blink effect to text of given rect (does not apply recursively).
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.set_blink_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Some Blinking Text");
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_blink_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.set_blink_flag())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Disable blink text effect
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// rectmanager.set_blink_flag(ROOT);
+    /// rectmanager.unset_blink_flag(ROOT);
+    /// rectmanager.set_string(ROOT, 0, 0, "Normal Text");
+    /// rectmanager.kill();
+    /// ```
+    pub fn unset_blink_flag(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.unset_blink_flag())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set color of background of given rect (does not apply recursively)
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// // Give Top a Green background
+    /// rectmanager.set_fg_color(ROOT, Color::GREEN);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_bg_color(&mut self, rect_id: usize, color: Color) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.set_bg_color(color))
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return background color to default
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// // Give Top a Magenta background
+    /// rectmanager.set_bg_color(ROOT, Color::MAGENTA);
+    ///
+    /// // Remove those colors...
+    /// rectmanager.unset_bg_color(ROOT);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn unset_bg_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.unset_bg_color())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set color of foreground (text) of given rect (does not apply recursively)
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// // Give Top a YELLOW foreground
+    /// rectmanager.set_fg_color(ROOT, Color::YELLOW);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_fg_color(&mut self, rect_id: usize, color: Color) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.set_fg_color(color))
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return foreground color to default
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// // Give Top a White foreground
+    /// rectmanager.set_fg_color(ROOT, Color::WHITE);
+    ///
+    /// // Remove those colors...
+    /// rectmanager.unset_fg_color(ROOT);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn unset_fg_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.unset_fg_color())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return both background and foreground colors to default
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// // Give Top a Blue background and a White foreground
+    /// rectmanager.set_bg_color(ROOT, Color::BLUE);
+    /// rectmanager.set_fg_color(ROOT, Color::WHITE);
+    ///
+    /// // Remove those colors...
+    /// rectmanager.unset_color(ROOT);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn unset_color(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.unset_color())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+    /// Get the fallback character that would be displayed where no character is set.
+    /// Defaults to ' '.
+    pub fn get_default_character(&self, rect_id: usize) -> char {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.get_default_character()
+            }
+            None => {
+                self.default_character
+            }
+        }
+    }
+
+    /// Get id of parent rectangle
+    /// # Example
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    ///
+    /// let mut rect = rectmanager.new_rect(ROOT);
+    /// assert_eq!(rectmanager.get_parent_id(rect), Some(ROOT));
+    ///
+    /// rectmanager.detach(rect);
+    /// assert_eq!(rectmanager.get_parent_id(rect), None);
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn get_parent_id(&self, rect_id: usize) -> Option<usize> {
+        let mut output = None;
+
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                output = rect.parent;
+            }
+            None => ()
+        };
+
+        output
+    }
+
+    /// Check if given Rect is connected to a parent.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// let mut rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// assert!(rectmanager.has_parent(rect));
+    ///
+    /// rectmanager.detach(rect);
+    /// assert!(!rectmanager.has_parent(rect));
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn has_parent(&self, rect_id: usize) -> bool {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.parent.is_some()
+            }
+            None => {
+                false
+            }
+        }
+    }
+
+    /// Check if the given id has an associated Rect within the RectManager.
+    pub fn has_rect(&self, rect_id: usize) -> bool {
+        self.rects.contains_key(&rect_id)
+    }
+
+
+    /// Check if the given Rect displays its background where no characters are set.
+    pub fn is_transparent(&self, rect_id: usize) -> bool {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.transparent
+            }
+            None => {
+                false
+            }
+        }
+    }
+
+
+    /// Set the transparency of given Rect. Transparent Rects will show the content of the Rects behind them where no characters or children are set. Opaque Rects will display the default characters in the corresponding foreground and background colors.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.set_transparency(rect, true);
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_transparency(&mut self, rect_id: usize, transparent: bool) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.transparent = transparent;
+                Ok(())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }
+    }
+
+    /// Dim everything beneath the given rect within its parent: the parent's own characters and the siblings under it.
+    /// Used to push the rest of the screen into the background behind a modal rect.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// let background = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// let modal = rectmanager.new_rect(ROOT).ok().unwrap();
+    ///
+    /// rectmanager.set_dim_below(modal, true);
+    /// assert!(rectmanager.is_dim_below(modal));
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_dim_below(&mut self, rect_id: usize, dim_below: bool) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                let changed = rect.dim_below != dim_below;
+                rect.dim_below = dim_below;
+                changed
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?
+            }
+        };
+
+        if changed {
+            if let Some(parent_id) = self.get_parent_id(rect_id) {
+                self.flag_refresh(parent_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_dim_below(&self, rect_id: usize) -> bool {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.dim_below
+            }
+            None => {
+                false
+            }
+        }
+    }
+
+
+    /// Get foreground color of given Rect
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// assert_eq!(rectmanager.get_fg_color(ROOT), None);
+    /// rectmanager.set_fg_color(ROOT, Color::BLUE);
+    /// assert_eq!(rectmanager.get_fg_color(ROOT), Some(Color::BLUE));
+    /// // turn echo back on and return input to normal.
+    /// rectmanager.kill();
+    /// ```
+    pub fn get_fg_color(&self, rect_id: usize) -> Option<Color> {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.get_fg_color()
+            }
+            None => {
+                None
+            }
+        }
+    }
+
+    /// Get background color of given rectangle
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT, Color};
+    /// let mut rectmanager = RectManager::new();
+    /// assert_eq!(rectmanager.get_bg_color(ROOT), None);
+    /// rectmanager.set_bg_color(ROOT, Color::BLUE);
+    /// assert_eq!(rectmanager.get_bg_color(ROOT), Some(Color::BLUE));
+    /// // turn echo back on and return input to normal.
+    /// rectmanager.kill();
+    /// ```
+    pub fn get_bg_color(&self, rect_id: usize) -> Option<Color> {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                rect.get_bg_color()
+            }
+            None => {
+                None
+            }
+        }
+    }
+
+
+    /// Give a rectangle a content area larger than the rectangle itself.
+    /// Characters and children can be placed anywhere in the content area, and the part that
+    /// is displayed can be moved with scroll_to() and scroll_by().
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// let mut rectmanager = RectManager::new();
+    /// let viewport = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.resize(viewport, 20, 5);
+    /// rectmanager.set_content_size(viewport, 20, 100);
+    ///
+    /// rectmanager.set_string(viewport, 0, 50, "Halfway down");
+    /// rectmanager.scroll_to(viewport, 0, 48);
+    /// assert_eq!(rectmanager.get_scroll_offset(viewport), Some((0, 48)));
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_content_size(&mut self, rect_id: usize, width: usize, height: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.content_size = Some((width, height));
+                rect.clamp_scroll();
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        self.flag_refresh(rect_id)
+    }
+
+    /// Make the content area of the given rectangle the same size as the rectangle again.
+    pub fn unset_content_size(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.content_size = None;
+                rect.clamp_scroll();
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        self.flag_refresh(rect_id)
+    }
+
+    /// Get the dimensions of the area characters and children can be placed in.
+    /// This is the size of the rectangle unless set_content_size() has been used.
+    pub fn get_content_size(&self, rect_id: usize) -> Option<(usize, usize)> {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                Some((rect.content_width(), rect.content_height()))
+            }
+            None => {
+                None
+            }
+        }
+    }
+
+    /// Get the position in the content area shown at the top-left corner of the given rectangle.
+    pub fn get_scroll_offset(&self, rect_id: usize) -> Option<(isize, isize)> {
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                Some(rect.scroll)
+            }
+            None => {
+                None
+            }
+        }
+    }
+
+    /// Show the part of the content area starting at the given position.
+    /// The offset is kept within the content area.
+    pub fn scroll_to(&mut self, rect_id: usize, x: isize, y: isize) -> Result<(), WreckedError> {
+        let changed = match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                Ok(rect.scroll_to(x, y))
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }?;
+
+        if changed {
+            self.flag_refresh(rect_id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Move the visible part of the content area by the given offsets.
+    pub fn scroll_by(&mut self, rect_id: usize, x_offset: isize, y_offset: isize) -> Result<(), WreckedError> {
+        match self.get_scroll_offset(rect_id) {
+            Some((x, y)) => {
+                self.scroll_to(rect_id, x + x_offset, y + y_offset)
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }
+    }
+
+    /// Draw scrollbars over the right column and bottom row of the given rectangle when its content area is larger than it.
+    pub fn set_scrollbar(&mut self, rect_id: usize, scrollbar: bool) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.scrollbar = scrollbar;
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        self.flag_refresh(rect_id)
+    }
+
+    /// Have the children of the given rect positioned and sized automatically.
+    /// Only children that have been given a Constraint are managed by the layout.
+    /// The layout is reapplied every time the rect is resized.
+    /// # Example
+    /// ```
+    /// use wrecked::{RectManager, ROOT};
+    /// use wrecked::layout::{Layout, Direction, Constraint};
+    /// let mut rectmanager = RectManager::new();
+    /// let header = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// let body = rectmanager.new_rect(ROOT).ok().unwrap();
+    /// rectmanager.set_constraint(header, Constraint::Fixed(1));
+    /// rectmanager.set_constraint(body, Constraint::Fill(1));
+    ///
+    /// rectmanager.set_layout(ROOT, Layout::new(Direction::Vertical));
+    ///
+    /// rectmanager.kill();
+    /// ```
+    pub fn set_layout(&mut self, rect_id: usize, layout: Layout) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.grid = None;
+                rect.layout = Some(layout);
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        self.apply_layout(rect_id)
+    }
+
+    /// Stop managing the children of the given rect. Children keep their current positions and sizes.
+    pub fn unset_layout(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.layout = None;
+                Ok(())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }
+    }
+
+    /// Set how much space the given rect should take up in its parent's layout.
+    pub fn set_constraint(&mut self, rect_id: usize, constraint: Constraint) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.constraint = Some(constraint);
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        match self.get_parent_id(rect_id) {
+            Some(parent_id) => {
+                self.apply_layout(parent_id)
+            }
+            None => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the given rect from its parent's layout, leaving it where it is.
+    pub fn unset_constraint(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.constraint = None;
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        match self.get_parent_id(rect_id) {
+            Some(parent_id) => {
+                self.apply_layout(parent_id)
+            }
+            None => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Have the children of the given rect placed in the cells of a grid.
+    /// Only children that have been given a GridCell are managed by the grid.
+    /// Replaces any Layout the rect had, and is reapplied every time the rect is resized.
+    pub fn set_grid(&mut self, rect_id: usize, grid: Grid) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.layout = None;
+                rect.grid = Some(grid);
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        self.apply_layout(rect_id)
+    }
+
+    /// Stop managing the children of the given rect with a grid. Children keep their current positions and sizes.
+    pub fn unset_grid(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.grid = None;
+                Ok(())
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))
+            }
+        }
+    }
+
+    /// Set the cell(s) the given rect occupies in its parent's grid.
+    pub fn set_grid_cell(&mut self, rect_id: usize, cell: GridCell) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.grid_cell = Some(cell);
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        match self.get_parent_id(rect_id) {
+            Some(parent_id) => {
+                self.apply_layout(parent_id)
+            }
+            None => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the given rect from its parent's grid, leaving it where it is.
+    pub fn unset_grid_cell(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                rect.parent.is_some()
+                rect.grid_cell = None;
+                Ok(())
             }
             None => {
-                false
+                Err(WreckedError::NotFound(rect_id))
             }
         }
     }
 
-    /// Check if the given id has an associated Rect within the RectManager.
-    pub fn has_rect(&self, rect_id: usize) -> bool {
-        self.rects.contains_key(&rect_id)
-    }
+    /// Set the space kept clear around the given rect when it is placed by its parent's layout or grid.
+    pub fn set_margin(&mut self, rect_id: usize, margin: Edges) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
+            Some(rect) => {
+                rect.margin = margin;
+            }
+            None => {
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
 
+        match self.get_parent_id(rect_id) {
+            Some(parent_id) => {
+                self.apply_layout(parent_id)
+            }
+            None => {
+                Ok(())
+            }
+        }
+    }
 
-    /// Check if the given Rect displays its background where no characters are set.
-    pub fn is_transparent(&self, rect_id: usize) -> bool {
-        match self.get_rect(rect_id) {
+    /// Set how the given rect is moved and resized when its parent is resized.
+    /// Anchors are ignored while the rect is being managed by its parent's layout or grid.
+    pub fn set_anchor(&mut self, rect_id: usize, anchor: Anchor) -> Result<(), WreckedError> {
+        match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                rect.transparent
+                rect.anchor = Some(anchor);
             }
             None => {
-                false
+                Err(WreckedError::NotFound(rect_id))?;
             }
         }
-    }
 
+        self.update_anchor_margins(rect_id)
+    }
 
-    /// Set the transparency of given Rect. Transparent Rects will show the content of the Rects behind them where no characters or children are set. Opaque Rects will display the default characters in the corresponding foreground and background colors.
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
-    /// let mut rectmanager = RectManager::new();
-    /// let rect = rectmanager.new_rect(ROOT).ok().unwrap();
-    /// rectmanager.set_transparency(rect, true);
-    /// rectmanager.kill();
-    /// ```
-    pub fn set_transparency(&mut self, rect_id: usize, transparent: bool) -> Result<(), WreckedError> {
+    /// Stop moving the given rect when its parent is resized.
+    pub fn unset_anchor(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         match self.get_rect_mut(rect_id) {
             Some(rect) => {
-                rect.transparent = transparent;
+                rect.anchor = None;
                 Ok(())
             }
             None => {
@@ -1906,51 +3234,195 @@ impl RectManager {
         }
     }
 
+    /// Recalculate the positions and sizes of the children managed by the given rect's layout or grid.
+    /// Does nothing if the rect has neither.
+    pub fn apply_layout(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let mut managed = Vec::new();
+        let mut children = Vec::new();
+        let mut layout = None;
+        let mut grid = None;
+        let mut dimensions = (0, 0);
 
-    /// Get foreground color of given Rect
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
-    /// let mut rectmanager = RectManager::new();
-    /// assert_eq!(rectmanager.get_fg_color(ROOT), None);
-    /// rectmanager.set_fg_color(ROOT, Color::BLUE);
-    /// assert_eq!(rectmanager.get_fg_color(ROOT), Some(Color::BLUE));
-    /// // turn echo back on and return input to normal.
-    /// rectmanager.kill();
-    /// ```
-    pub fn get_fg_color(&self, rect_id: usize) -> Option<Color> {
         match self.get_rect(rect_id) {
             Some(rect) => {
-                rect.get_fg_color()
+                layout = rect.layout.clone();
+                grid = rect.grid.clone();
+                dimensions = (rect.width, rect.height);
+                children = rect.children.clone();
             }
             None => {
-                None
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        if let Some(grid) = grid {
+            return self.apply_grid(rect_id, &grid, dimensions, &children);
+        }
+
+        let layout = match layout {
+            Some(layout) => layout,
+            None => {
+                return Ok(());
+            }
+        };
+
+        for child_id in children.iter() {
+            match self.get_rect(*child_id) {
+                Some(child) => {
+                    if let Some(constraint) = child.constraint {
+                        managed.push((*child_id, (constraint, child.margin)));
+                    }
+                }
+                None => {
+                    Err(WreckedError::ChildNotFound(rect_id, *child_id))?;
+                }
             }
         }
+
+        let areas = layout.split(
+            dimensions.0,
+            dimensions.1,
+            &managed.iter().map(|(_, spec)| *spec).collect::<Vec<(Constraint, Edges)>>()
+        );
+
+        for ((child_id, _), (x, y, width, height)) in managed.iter().zip(areas.iter()) {
+            self.resize(*child_id, *width, *height)?;
+            self.set_position(*child_id, *x, *y)?;
+        }
+
+        Ok(())
     }
 
-    /// Get background color of given rectangle
-    /// # Example
-    /// ```
-    /// use wrecked::{RectManager, ROOT, Color};
-    /// let mut rectmanager = RectManager::new();
-    /// assert_eq!(rectmanager.get_bg_color(ROOT), None);
-    /// rectmanager.set_bg_color(ROOT, Color::BLUE);
-    /// assert_eq!(rectmanager.get_bg_color(ROOT), Some(Color::BLUE));
-    /// // turn echo back on and return input to normal.
-    /// rectmanager.kill();
-    /// ```
-    pub fn get_bg_color(&self, rect_id: usize) -> Option<Color> {
+    // Move and resize the anchored children of a rect that has just been resized.
+    // Resizing the children here cascades the change down the tree.
+    fn apply_anchors(&mut self, rect_id: usize, old_size: (usize, usize)) -> Result<(), WreckedError> {
+        let mut anchored = Vec::new();
+
         match self.get_rect(rect_id) {
             Some(rect) => {
-                rect.get_bg_color()
+                let new_size = (rect.width, rect.height);
+                for child_id in rect.children.iter() {
+                    match self.get_rect(*child_id) {
+                        Some(child) => {
+                            let managed = (rect.layout.is_some() && child.constraint.is_some())
+                                || (rect.grid.is_some() && child.grid_cell.is_some());
+
+                            match child.anchor {
+                                Some(anchor) => {
+                                    if !managed {
+                                        let (x, y) = rect.get_child_position(*child_id).unwrap_or((0, 0));
+                                        let area = anchor.follow((x, y, child.width, child.height), child.anchor_margins, old_size, new_size);
+                                        anchored.push((*child_id, area, child.anchor_margins));
+                                    }
+                                }
+                                None => ()
+                            }
+                        }
+                        None => {
+                            Err(WreckedError::ChildNotFound(rect_id, *child_id))?;
+                        }
+                    }
+                }
             }
             None => {
-                None
+                Err(WreckedError::NotFound(rect_id))?;
+            }
+        }
+
+        for (child_id, (x, y, width, height), margins) in anchored.iter() {
+            self.resize(*child_id, *width, *height)?;
+            self.set_position(*child_id, *x, *y)?;
+
+            // Moving & resizing remeasured the margins, which are off if the size was cut off at 0
+            if let Some(child) = self.get_rect_mut(*child_id) {
+                child.anchor_margins = *margins;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Measure the distances from an anchored rect's right and bottom edges to its parent's.
+    fn update_anchor_margins(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let margins = match (self.get_rect(rect_id), self.get_parent(rect_id)) {
+            (Some(rect), Some(parent)) => {
+                if rect.anchor.is_none() {
+                    return Ok(());
+                }
+                let (x, y) = parent.get_child_position(rect_id).unwrap_or((0, 0));
+                (
+                    parent.width as isize - x - rect.width as isize,
+                    parent.height as isize - y - rect.height as isize
+                )
+            }
+            (Some(_), None) => {
+                return Ok(());
+            }
+            (None, _) => {
+                Err(WreckedError::NotFound(rect_id))?
+            }
+        };
+
+        if let Some(rect) = self.get_rect_mut(rect_id) {
+            rect.anchor_margins = margins;
+        }
+
+        Ok(())
+    }
+
+    fn apply_grid(&mut self, rect_id: usize, grid: &Grid, dimensions: (usize, usize), children: &[usize]) -> Result<(), WreckedError> {
+        let mut managed = Vec::new();
+        for child_id in children.iter() {
+            let cell = match self.get_rect(*child_id) {
+                Some(child) => {
+                    child.grid_cell.map(|cell| (cell, child.margin))
+                }
+                None => {
+                    Err(WreckedError::ChildNotFound(rect_id, *child_id))?
+                }
+            };
+
+            if let Some((cell, margin)) = cell {
+                managed.push((*child_id, (cell, margin, self.get_content_extent(*child_id))));
             }
         }
+
+        let areas = grid.place(
+            dimensions.0,
+            dimensions.1,
+            &managed.iter().map(|(_, spec)| *spec).collect::<Vec<(GridCell, Edges, (usize, usize))>>()
+        );
+
+        for ((child_id, _), (x, y, width, height)) in managed.iter().zip(areas.iter()) {
+            self.resize(*child_id, *width, *height)?;
+            self.set_position(*child_id, *x, *y)?;
+        }
+
+        Ok(())
     }
 
+    // Get the smallest size that would show all the characters and children of the given rect.
+    fn get_content_extent(&self, rect_id: usize) -> (usize, usize) {
+        let mut size = (0, 0);
+        match self.get_rect(rect_id) {
+            Some(rect) => {
+                for (x, y) in rect.character_space.keys() {
+                    size.0 = cmp::max(size.0, (x + 1) as usize);
+                    size.1 = cmp::max(size.1, (y + 1) as usize);
+                }
+
+                for child_id in rect.children.iter() {
+                    let child_size = self.get_rect_size(*child_id).unwrap_or((0, 0));
+                    let (x, y) = rect.get_child_position(*child_id).unwrap_or((0, 0));
+                    size.0 = cmp::max(size.0, cmp::max(0, x + child_size.0 as isize) as usize);
+                    size.1 = cmp::max(size.1, cmp::max(0, y + child_size.1 as isize) as usize);
+                }
+            }
+            None => ()
+        }
+
+        size
+    }
 
     fn flag_refresh(&mut self, rect_id: usize) -> Result<(), WreckedError> {
         match self.get_rect_mut(rect_id) {
@@ -1967,6 +3439,23 @@ impl RectManager {
         Ok(())
     }
 
+    // Dimming affects every position of the parent, not just the ones under the rect
+    fn flag_dim_refresh(&mut self, rect_id: usize) -> Result<(), WreckedError> {
+        let parent_id = match self.get_rect(rect_id) {
+            Some(rect) if rect.dim_below => rect.parent,
+            _ => None
+        };
+
+        match parent_id {
+            Some(parent_id) => {
+                self.flag_refresh(parent_id)
+            }
+            None => {
+                Ok(())
+            }
+        }
+    }
+
     fn get_rect(&self, rect_id: usize) -> Option<&Rect> {
         self.rects.get(&rect_id)
     }
@@ -2043,16 +3532,26 @@ impl RectManager {
         }
 
         let mut transparent_children = HashSet::new();
+        // Anything ranked below the top-most dimming child gets dimmed
+        let mut dim_rank = None;
         for child_id in child_ids.iter() {
             if self.is_transparent(*child_id) {
                 transparent_children.insert(*child_id);
             }
+            if self.is_dim_below(*child_id) && self.is_rect_enabled(*child_id) {
+                dim_rank = cmp::max(dim_rank, self.get_rect(rect_id).and_then(|rect| rect._child_ranks.get(child_id).copied()));
+            }
         }
 
         let mut child_positions: HashMap<usize, (isize, isize)> = HashMap::new();
+        let mut child_ranks: HashMap<usize, usize> = HashMap::new();
+        // positions are in the visible area of the rect, but characters and children are in its content area
+        let mut scroll = (0, 0);
         match self.get_rect_mut(rect_id) {
             Some(rect) => {
                 child_positions = rect.child_positions.clone();
+                child_ranks = rect._child_ranks.clone();
+                scroll = rect.scroll;
                 for (_x, _y) in positions.iter() {
                     x = *_x;
                     y = *_y;
@@ -2061,31 +3560,32 @@ impl RectManager {
                         continue;
                     }
 
-                    if !rect.child_space.contains_key(&(x, y)) || rect.child_space[&(x, y)].is_empty() {
+                    let content_pos = (x + scroll.0, y + scroll.1);
+                    if !rect.child_space.contains_key(&content_pos) || rect.child_space[&content_pos].is_empty() {
                         // Make sure at least default character is present
                         if !rect.transparent {
-                            tmp_fx = rect.effects;
+                            tmp_fx = *rect.style_space.get(&content_pos)
+                                .unwrap_or(&rect.effects);
+                            tmp_fx.dim |= dim_rank.is_some();
 
-                            tmp_chr = rect.character_space.entry((x, y))
-                                .or_insert(rect.default_character);
+                            tmp_chr = *rect.character_space.get(&content_pos)
+                                .unwrap_or(&rect.default_character);
 
                             rect._cached_display.entry((x,y))
-                                .and_modify(|e| {*e = (*tmp_chr, tmp_fx, 0)})
-                                .or_insert((*tmp_chr, tmp_fx, 0));
+                                .and_modify(|e| {*e = (tmp_chr, tmp_fx, 0)})
+                                .or_insert((tmp_chr, tmp_fx, 0));
                         }
                     } else {
-                        match rect.child_space.get(&(x, y)) {
+                        match rect.child_space.get(&content_pos) {
                             Some(child_ids) => {
+                                // Opaque children are kept in the stack, since box-drawing
+                                // characters need to see what's beneath them to form junctions.
                                 for (i, child_id) in child_ids.iter().rev().enumerate() {
                                     let rank = child_ids.len() - i;
                                     require_updates.insert(*child_id);
                                     pos_stack.entry((x, y))
                                         .and_modify(|e| e.push((*child_id, rank)))
                                         .or_insert(vec![(*child_id, rank)]);
-
-                                    if !transparent_children.contains(child_id) {
-                                        break;
-                                    }
                                 }
                             }
                             None => ()
@@ -2107,20 +3607,38 @@ impl RectManager {
         let mut transparent_coords = HashSet::new();
 
         for ((x, y), child_ids) in pos_stack.iter() {
+            // (value, rank, id of the child it came from)
+            let mut top_value: Option<((char, EffectsHandler, usize), usize, usize)> = None;
             for (child_id, rank) in child_ids.iter() {
                 match child_positions.get(child_id) {
                     Some(child_position) => {
                         match self.get_rect_mut(*child_id) {
                             Some(child) => {
-
-                                match child._cached_display.get(&(*x - child_position.0, *y - child_position.1)) {
+                                match child._cached_display.get(&(*x + scroll.0 - child_position.0, *y + scroll.1 - child_position.1)) {
                                     Some(new_value) => {
-                                        new_values.push((*new_value, *rank, *x, *y));
-                                        break;
+                                        match top_value.as_mut() {
+                                            Some((value, _, _)) => {
+                                                // Join the box-drawing character on top with the one beneath it
+                                                if let Some(junction) = box_drawing::merge_box_characters(value.0, new_value.0) {
+                                                    value.0 = junction;
+                                                }
+                                            }
+                                            None => {
+                                                top_value = Some((*new_value, *rank, *child_id));
+                                            }
+                                        }
+
+                                        if !box_drawing::is_box_character(new_value.0) {
+                                            break;
+                                        }
                                     }
                                     None => {
                                         if child.transparent {
-                                            transparent_coords.insert((*x, *y));
+                                            if top_value.is_none() {
+                                                transparent_coords.insert((*x, *y));
+                                            }
+                                        } else {
+                                            break;
                                         }
                                     }
                                 }
@@ -2133,6 +3651,22 @@ impl RectManager {
                     None => ()
                 }
             }
+
+            if let Some((mut value, rank, source_id)) = top_value {
+                if let Some(dim_rank) = dim_rank {
+                    if child_ranks.get(&source_id).map(|source_rank| *source_rank < dim_rank).unwrap_or(false) {
+                        value.1.dim = true;
+                    }
+                }
+
+                // The parent's own characters can form junctions too (eg, a panel touching its parent's border)
+                if let Some(parent_character) = self.get_rect(rect_id).and_then(|rect| rect.character_space.get(&(*x + scroll.0, *y + scroll.1))) {
+                    if let Some(junction) = box_drawing::merge_box_characters(value.0, *parent_character) {
+                        value.0 = junction;
+                    }
+                }
+                new_values.push((value, rank, *x, *y));
+            }
         }
 
         match self.get_rect_mut(rect_id) {
@@ -2153,14 +3687,28 @@ impl RectManager {
                     if rect.transparent {
                         rect._cached_display.remove(coord);
                     } else {
-                        tmp_fx = rect.effects;
+                        tmp_fx = *rect.style_space.get(&(coord.0 + scroll.0, coord.1 + scroll.1))
+                            .unwrap_or(&rect.effects);
+                        tmp_fx.dim |= dim_rank.is_some();
 
-                        tmp_chr = rect.character_space.entry(*coord)
-                            .or_insert(rect.default_character);
+                        tmp_chr = *rect.character_space.get(&(coord.0 + scroll.0, coord.1 + scroll.1))
+                            .unwrap_or(&rect.default_character);
 
                         rect._cached_display.entry(*coord)
-                            .and_modify(|e| {*e = (*tmp_chr, tmp_fx, 0)})
-                            .or_insert((tmp_chr.clone(), tmp_fx.clone(), 0));
+                            .and_modify(|e| {*e = (tmp_chr, tmp_fx, 0)})
+                            .or_insert((tmp_chr, tmp_fx, 0));
+                    }
+                }
+
+                if rect.scrollbar {
+                    for (x, y) in positions.iter() {
+                        match rect.get_scrollbar_character(*x, *y) {
+                            Some(character) => {
+                                tmp_fx = rect.effects;
+                                rect._cached_display.insert((*x, *y), (character, tmp_fx, 0));
+                            }
+                            None => ()
+                        }
                     }
                 }
             }
@@ -2362,6 +3910,12 @@ impl RectManager {
 
             match self.get_parent_mut(working_id) {
                 Some(parent) => {
+                    // Convert to the visible area of the parent
+                    offset = (
+                        offset.0 - parent.scroll.0,
+                        offset.1 - parent.scroll.1
+                    );
+
                     for x in 0 .. dimensions.0 {
                         for y in 0 .. dimensions.1 {
                             parent.flag_pos_refresh((offset.0 + x as isize, offset.1 + y as isize));
@@ -2387,6 +3941,9 @@ impl RectManager {
         loop {
             match self.get_rect_mut(working_id) {
                 Some(rect) => {
+                    // Convert from the rect's content area to its visible area
+                    x_out -= rect.scroll.0;
+                    y_out -= rect.scroll.1;
                     rect.flag_pos_refresh((x_out, y_out));
                 }
                 None => {
@@ -2404,7 +3961,7 @@ impl RectManager {
 
             match self.get_parent_mut(working_id) {
                 Some(parent) => {
-                    match parent._cached_display.remove(&(x_out, y_out)) {
+                    match parent._cached_display.remove(&(x_out - parent.scroll.0, y_out - parent.scroll.1)) {
                         Some(_) => {}
                         None => {}
                     }
@@ -2488,6 +4045,118 @@ impl RectManager {
         }
     }
 
+    // Get the absolute area a rect's bitmap would cover, if all of the rect is displayed
+    fn get_graphic_area(&self, rect_id: usize) -> Option<(isize, isize, usize, usize)> {
+        let mut working_id = rect_id;
+        while working_id != ROOT {
+            if !self.is_rect_enabled(working_id) {
+                return None;
+            }
+            working_id = self.get_parent_id(working_id)?;
+        }
+
+        let (width, height) = self.get_rect_size(rect_id)?;
+        let (x, y) = self.get_absolute_offset(rect_id)?;
+        let visible_box = self.get_visible_box(rect_id).ok()?;
+        if width == 0 || height == 0 || visible_box != (x, y, width as isize, height as isize) {
+            None
+        } else if self.is_covered(rect_id, (x, y, width, height)) {
+            // Whatever is on top would be hidden by the bitmap, so the cells are drawn instead
+            None
+        } else {
+            Some((x, y, width, height))
+        }
+    }
+
+    // Check if any cell of an absolute area of a rect is drawn from another rect, be it one of
+    // its own children or a rect stacked over it or over any of its ancestors.
+    fn is_covered(&self, rect_id: usize, area: (isize, isize, usize, usize)) -> bool {
+        let (x, y, width, height) = area;
+        let cells: Vec<(isize, isize)> = (y .. y + height as isize)
+            .flat_map(|cell_y| (x .. x + width as isize).map(move |cell_x| (cell_x, cell_y)))
+            .collect();
+
+        let mut working_id = rect_id;
+        let mut above_id = None;
+        loop {
+            let (rect, (offset_x, offset_y)) = match (self.get_rect(working_id), self.get_absolute_offset(working_id)) {
+                (Some(rect), Some(offset)) => (rect, offset),
+                _ => {
+                    return true;
+                }
+            };
+
+            for (cell_x, cell_y) in cells.iter() {
+                let position = (cell_x - offset_x + rect.scroll.0, cell_y - offset_y + rect.scroll.1);
+                // The last child in a position is the one on top
+                let top_id = rect.child_space.get(&position).and_then(|child_ids| child_ids.last());
+                if top_id != above_id.as_ref() {
+                    return true;
+                }
+            }
+
+            if working_id == ROOT {
+                return false;
+            }
+            above_id = Some(working_id);
+            working_id = match self.get_parent_id(working_id) {
+                Some(parent_id) => parent_id,
+                None => {
+                    return true;
+                }
+            };
+        }
+    }
+
+    // Bring the terminal's graphics up to date with the bitmaps, returning the escapes to do so.
+    // The cells under bitmaps that are removed or moved are flagged to be drawn again.
+    fn build_graphics_string(&mut self) -> String {
+        let mut current = HashMap::new();
+        if let Some(protocol) = self.graphics_protocol {
+            for (rect_id, (_, generation)) in self.bitmaps.iter() {
+                if let Some(area) = self.get_graphic_area(*rect_id) {
+                    current.insert(*rect_id, DrawnGraphic { area, generation: *generation, protocol });
+                }
+            }
+        }
+
+        let mut output = String::new();
+        let stale: Vec<usize> = self.drawn_graphics.iter()
+            .filter(|(rect_id, drawn)| current.get(*rect_id) != Some(*drawn))
+            .map(|(rect_id, _)| *rect_id)
+            .collect();
+        for rect_id in stale {
+            if let Some(drawn) = self.drawn_graphics.remove(&rect_id) {
+                self.top_cache.retain(|position, _| !drawn.contains(position));
+                if drawn.protocol == GraphicsProtocol::Kitty {
+                    output += &image::get_kitty_delete(rect_id + 1);
+                }
+            }
+        }
+
+        let mut added: Vec<(usize, DrawnGraphic)> = current.into_iter()
+            .filter(|(rect_id, _)| !self.drawn_graphics.contains_key(rect_id))
+            .collect();
+        added.sort_by_key(|(rect_id, _)| *rect_id);
+        for (rect_id, graphic) in added {
+            let image = &self.bitmaps[&rect_id].0;
+            let (x, y, width, height) = graphic.area;
+            output += &format!("\x1B[{};{}H", y + 1, x + 1);
+            output += &match graphic.protocol {
+                GraphicsProtocol::Sixel => {
+                    image::encode_sixel(image, width * self.cell_pixel_size.0, height * self.cell_pixel_size.1)
+                }
+                GraphicsProtocol::Kitty => {
+                    // Kitty image ids can't be 0
+                    image::encode_kitty(image, rect_id + 1, width, height)
+                }
+            };
+            self.drawn_graphics.insert(rect_id, graphic);
+        }
+
+        output
+    }
+
     fn recycle_id(&mut self, old_id: usize) {
         // NOTE: Assumes 0 is reserved and can't be removed
         self.recycle_ids.push(old_id);
@@ -2502,6 +4171,15 @@ impl RectManager {
 
 }
 
+// Get the position and length of a scrollbar's thumb
+fn get_scrollbar_thumb(visible: usize, content: usize, scroll: isize) -> (isize, isize) {
+    let length = cmp::max(1, (visible * visible) / cmp::max(1, content));
+    let track = visible.saturating_sub(length) as isize;
+    let scrollable = cmp::max(1, content as isize - visible as isize);
+
+    ((scroll * track) / scrollable, length as isize)
+}
+
 #[derive(Debug)]
 struct Rect {
     rect_id: usize,
@@ -2520,6 +4198,8 @@ struct Rect {
     _child_ranks: HashMap<usize, usize>,
 
     character_space: HashMap<(isize,isize), char>,
+    // Effects of individual characters, used in place of the rect's effects
+    style_space: HashMap<(isize,isize), EffectsHandler>,
 
     flag_full_refresh: bool,
     flags_pos_refresh: HashSet<(isize, isize)>,
@@ -2529,6 +4209,25 @@ struct Rect {
 
     effects: EffectsHandler,
 
+    layout: Option<Layout>,
+    constraint: Option<Constraint>,
+    grid: Option<Grid>,
+    grid_cell: Option<GridCell>,
+    margin: Edges,
+    anchor: Option<Anchor>,
+    // Distances from the right and bottom edges to the parent's, kept by stretching anchors
+    anchor_margins: (isize, isize),
+
+    // Size of the area characters & children can be placed in, if larger than the rect itself
+    content_size: Option<(usize, usize)>,
+    // Offset of the visible area within the content area
+    scroll: (isize, isize),
+    scrollbar: bool,
+    // Dim the parent's characters and the siblings beneath this rect
+    dim_below: bool,
+    // Base direction of text set with set_bidi_string() and set_text()
+    text_direction: TextDirection,
+
     _cached_display: HashMap<(isize, isize), (char, EffectsHandler, usize)>
 }
 
@@ -2545,6 +4244,7 @@ impl Rect {
             child_positions: HashMap::new(),
             _child_ranks: HashMap::new(),
             character_space: HashMap::new(),
+            style_space: HashMap::new(),
             flag_full_refresh: true,
             flags_pos_refresh: HashSet::new(),
             enabled: true,
@@ -2552,6 +4252,20 @@ impl Rect {
 
             effects: EffectsHandler::new(),
 
+            layout: None,
+            constraint: None,
+            grid: None,
+            grid_cell: None,
+            margin: Edges::default(),
+            anchor: None,
+            anchor_margins: (0, 0),
+
+            content_size: None,
+            scroll: (0, 0),
+            scrollbar: false,
+            dim_below: false,
+            text_direction: TextDirection::LeftToRight,
+
             _cached_display: HashMap::new(),
             default_character: ' ' // Space
         }
@@ -2659,7 +4373,7 @@ impl Rect {
         };
 
         for position in new_positions.iter() {
-            self.flag_pos_refresh(*position);
+            self.flag_pos_refresh((position.0 - self.scroll.0, position.1 - self.scroll.1));
 
             match self.child_space.get_mut(&position) {
                 Some(child_ids) => {
@@ -2676,7 +4390,7 @@ impl Rect {
     }
 
     fn get_character(&self, x: isize, y: isize) -> Result<char, WreckedError> {
-        if y < self.height as isize && y >= 0 && x < self.width as isize && x >= 0 {
+        if y < self.content_height() as isize && y >= 0 && x < self.content_width() as isize && x >= 0 {
             match self.character_space.get(&(x, y)) {
                 Some(character) => {
                     Ok(character.clone())
@@ -2691,7 +4405,7 @@ impl Rect {
     }
 
     fn set_character(&mut self, x: isize, y: isize, character: char) -> Result<bool, WreckedError> {
-        if y < self.height as isize && y >= 0 && x < self.width as isize && x >= 0 {
+        if y < self.content_height() as isize && y >= 0 && x < self.content_width() as isize && x >= 0 {
             let mut changed = true;
             match self.character_space.get(&(x,y)) {
                 Some(existing_char) => {
@@ -2714,6 +4428,25 @@ impl Rect {
 
     }
 
+    fn set_character_style(&mut self, x: isize, y: isize, effects: Option<EffectsHandler>) -> Result<bool, WreckedError> {
+        if y < self.content_height() as isize && y >= 0 && x < self.content_width() as isize && x >= 0 {
+            let changed = self.style_space.get(&(x, y)) != effects.as_ref();
+            if changed {
+                match effects {
+                    Some(effects) => {
+                        self.style_space.insert((x, y), effects);
+                    }
+                    None => {
+                        self.style_space.remove(&(x, y));
+                    }
+                }
+            }
+            Ok(changed)
+        } else {
+            Err(WreckedError::BadPosition(x, y))
+        }
+    }
+
     fn unset_character(&mut self, x: isize, y: isize) -> Result<bool, WreckedError> {
         self.set_character(x, y, self.default_character)
     }
@@ -2914,6 +4647,65 @@ impl Rect {
     fn resize(&mut self, width: usize, height: usize) {
         self.width = width;
         self.height = height;
+        self.clamp_scroll();
+    }
+
+    fn content_width(&self) -> usize {
+        match self.content_size {
+            Some((width, _)) => width,
+            None => self.width
+        }
+    }
+
+    fn content_height(&self) -> usize {
+        match self.content_size {
+            Some((_, height)) => height,
+            None => self.height
+        }
+    }
+
+    // Returns true if the scroll offset changed
+    fn scroll_to(&mut self, x: isize, y: isize) -> bool {
+        let old_scroll = self.scroll;
+        self.scroll = (x, y);
+        self.clamp_scroll();
+
+        old_scroll != self.scroll
+    }
+
+    // Keep the visible area inside the content area
+    fn clamp_scroll(&mut self) {
+        let max_x = cmp::max(0, self.content_width() as isize - self.width as isize);
+        let max_y = cmp::max(0, self.content_height() as isize - self.height as isize);
+        self.scroll = (
+            cmp::min(cmp::max(0, self.scroll.0), max_x),
+            cmp::min(cmp::max(0, self.scroll.1), max_y)
+        );
+    }
+
+    // Get the piece of scrollbar to be drawn over the given visible position, if any.
+    // The vertical bar runs down the last column, and the horizontal bar along the last row.
+    fn get_scrollbar_character(&self, x: isize, y: isize) -> Option<char> {
+        let show_vertical = self.content_height() > self.height;
+        let show_horizontal = self.content_width() > self.width;
+
+        if show_vertical && x == self.width as isize - 1 {
+            let (start, length) = get_scrollbar_thumb(self.height, self.content_height(), self.scroll.1);
+            if y >= start && y < start + length {
+                Some('█')
+            } else {
+                Some('░')
+            }
+        } else if show_horizontal && y == self.height as isize - 1 {
+            let (start, length) = get_scrollbar_thumb(self.width, self.content_width(), self.scroll.0);
+            if x >= start && x < start + length {
+                Some('█')
+            } else {
+                Some('░')
+            }
+        } else {
+            None
+        }
     }
 
     // Can't update child_space here, need child width and height
@@ -853,3 +853,1608 @@ fn test_out_of_bounds_rendering_set_position() {
     }
     assert!(found);
 }
+
+#[test]
+fn test_box_junctions() -> Result<(), WreckedError> {
+    let mut rectmanager = RectManager::new();
+    let container = rectmanager.new_rect(ROOT).ok().unwrap();
+    rectmanager.resize(container, 19, 3)?;
+
+    // Two panels that share their inner edge
+    let mut panels = vec![];
+    for i in 0 .. 2 {
+        let panel = rectmanager.new_rect(container).ok().unwrap();
+        rectmanager.resize(panel, 10, 3)?;
+        rectmanager.set_position(panel, i * 9, 0)?;
+        rectmanager.set_string(panel, 0, 0, "┌────────┐")?;
+        rectmanager.set_string(panel, 0, 1, "│        │")?;
+        rectmanager.set_string(panel, 0, 2, "└────────┘")?;
+        panels.push(panel);
+    }
+
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(0, 0)).unwrap().0, '┌');
+    assert_eq!(display.get(&(9, 0)).unwrap().0, '┬');
+    assert_eq!(display.get(&(9, 1)).unwrap().0, '│');
+    assert_eq!(display.get(&(9, 2)).unwrap().0, '┴');
+    assert_eq!(display.get(&(18, 2)).unwrap().0, '┘');
+
+    // Characters that aren't box-drawing characters still cover what's beneath them
+    rectmanager.set_character(panels[1], 0, 1, 'X')?;
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(9, 1)).unwrap().0, 'X');
+
+    assert_eq!(box_drawing::merge_box_characters('═', '│'), Some('╪'));
+    assert_eq!(box_drawing::merge_box_characters('┏', '┛'), Some('╋'));
+    assert_eq!(box_drawing::merge_box_characters('╔', '┗'), Some('╠'));
+    assert_eq!(box_drawing::merge_box_characters('X', '┗'), None);
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_solve_constraints() {
+    use layout::{solve_constraints, Constraint::*};
+
+    assert_eq!(solve_constraints(100, &[Fixed(10), Fill(1), Fill(3)]), vec![10, 23, 67]);
+    assert_eq!(solve_constraints(100, &[Percentage(25), Fill(1)]), vec![25, 75]);
+    assert_eq!(solve_constraints(100, &[Max(10), Fill(1)]), vec![10, 90]);
+    assert_eq!(solve_constraints(20, &[Min(15), Fill(1)]), vec![18, 2]);
+    assert_eq!(solve_constraints(10, &[Fixed(8), Fixed(8)]), vec![8, 2]);
+    assert_eq!(solve_constraints(10, &[Max(2), Max(3)]), vec![2, 3]);
+}
+
+#[test]
+fn test_layout() -> Result<(), WreckedError> {
+    use layout::{Layout, Direction, Constraint, Edges};
+
+    let mut rectmanager = RectManager::new();
+    let container = rectmanager.new_rect(ROOT).ok().unwrap();
+    let header = rectmanager.new_rect(container).ok().unwrap();
+    let body = rectmanager.new_rect(container).ok().unwrap();
+    let footer = rectmanager.new_rect(container).ok().unwrap();
+    let floating = rectmanager.new_rect(container).ok().unwrap();
+    rectmanager.resize(floating, 3, 3)?;
+    rectmanager.set_position(floating, 5, 5)?;
+
+    rectmanager.set_constraint(header, Constraint::Fixed(3))?;
+    rectmanager.set_constraint(body, Constraint::Fill(1))?;
+    rectmanager.set_constraint(footer, Constraint::Fixed(1))?;
+    rectmanager.set_margin(body, Edges::new(0, 2, 0, 2))?;
+
+    let mut layout = Layout::new(Direction::Vertical);
+    layout.padding = Edges::uniform(1);
+    layout.spacing = 1;
+    rectmanager.set_layout(container, layout)?;
+
+    rectmanager.resize(container, 20, 20)?;
+    assert_eq!(rectmanager.get_relative_offset(header), Some((1, 1)));
+    assert_eq!(rectmanager.get_rect_size(header), Some((18, 3)));
+    assert_eq!(rectmanager.get_relative_offset(body), Some((3, 5)));
+    assert_eq!(rectmanager.get_rect_size(body), Some((14, 12)));
+    assert_eq!(rectmanager.get_relative_offset(footer), Some((1, 18)));
+    assert_eq!(rectmanager.get_rect_size(footer), Some((18, 1)));
+
+    // Unconstrained children are left alone
+    assert_eq!(rectmanager.get_relative_offset(floating), Some((5, 5)));
+    assert_eq!(rectmanager.get_rect_size(floating), Some((3, 3)));
+
+    // Layouts follow resizes
+    rectmanager.resize(container, 10, 12)?;
+    assert_eq!(rectmanager.get_rect_size(body), Some((4, 4)));
+    assert_eq!(rectmanager.get_relative_offset(footer), Some((1, 10)));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_grid() -> Result<(), WreckedError> {
+    use layout::{Grid, GridCell, Track};
+
+    let mut rectmanager = RectManager::new();
+    let dashboard = rectmanager.new_rect(ROOT).ok().unwrap();
+
+    // A label column sized to its contents, followed by two equal columns
+    let label = rectmanager.new_rect(dashboard).ok().unwrap();
+    rectmanager.resize(label, 20, 1)?;
+    rectmanager.set_string(label, 0, 0, "Uptime")?;
+    rectmanager.set_grid_cell(label, GridCell::new(0, 0))?;
+
+    let wide = rectmanager.new_rect(dashboard).ok().unwrap();
+    rectmanager.set_grid_cell(wide, GridCell::spanning(0, 1, 1, 2))?;
+
+    let tall = rectmanager.new_rect(dashboard).ok().unwrap();
+    rectmanager.set_grid_cell(tall, GridCell::spanning(1, 2, 2, 1))?;
+
+    let mut grid = Grid::new(
+        vec![Track::Auto, Track::Fraction(1), Track::Fraction(1)],
+        vec![Track::Fixed(2), Track::Fraction(1), Track::Fraction(2)]
+    );
+    grid.column_gap = 1;
+    rectmanager.set_grid(dashboard, grid)?;
+    rectmanager.resize(dashboard, 27, 20)?;
+
+    assert_eq!(rectmanager.get_rect_size(label), Some((6, 2)));
+    assert_eq!(rectmanager.get_relative_offset(wide), Some((7, 0)));
+    assert_eq!(rectmanager.get_rect_size(wide), Some((20, 2)));
+    assert_eq!(rectmanager.get_relative_offset(tall), Some((17, 2)));
+    assert_eq!(rectmanager.get_rect_size(tall), Some((10, 18)));
+
+    // Recalculated when the parent changes size
+    rectmanager.resize(dashboard, 37, 11)?;
+    assert_eq!(rectmanager.get_rect_size(wide), Some((30, 2)));
+    assert_eq!(rectmanager.get_relative_offset(tall), Some((22, 2)));
+    assert_eq!(rectmanager.get_rect_size(tall), Some((15, 9)));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_anchors() -> Result<(), WreckedError> {
+    use layout::{Anchor, HorizontalAnchor, VerticalAnchor};
+
+    let mut rectmanager = RectManager::new();
+    let window = rectmanager.new_rect(ROOT).ok().unwrap();
+    rectmanager.resize(window, 40, 20)?;
+
+    let footer = rectmanager.new_rect(window).ok().unwrap();
+    rectmanager.resize(footer, 38, 1)?;
+    rectmanager.set_position(footer, 1, 18)?;
+    let mut anchor = Anchor::new(HorizontalAnchor::Left, VerticalAnchor::Bottom);
+    anchor.stretch_horizontal = true;
+    rectmanager.set_anchor(footer, anchor)?;
+
+    // Anchored to its own parent, so it should follow the footer as it stretches
+    let clock = rectmanager.new_rect(footer).ok().unwrap();
+    rectmanager.resize(clock, 5, 1)?;
+    rectmanager.set_position(clock, 33, 0)?;
+    rectmanager.set_anchor(clock, Anchor::new(HorizontalAnchor::Right, VerticalAnchor::Top))?;
+
+    let popup = rectmanager.new_rect(window).ok().unwrap();
+    rectmanager.resize(popup, 10, 4)?;
+    rectmanager.set_position(popup, 15, 8)?;
+    rectmanager.set_anchor(popup, Anchor::new(HorizontalAnchor::Center, VerticalAnchor::Center))?;
+
+    let unanchored = rectmanager.new_rect(window).ok().unwrap();
+    rectmanager.resize(unanchored, 2, 2)?;
+    rectmanager.set_position(unanchored, 3, 3)?;
+
+    rectmanager.resize(window, 60, 30)?;
+
+    assert_eq!(rectmanager.get_relative_offset(footer), Some((1, 28)));
+    assert_eq!(rectmanager.get_rect_size(footer), Some((58, 1)));
+    assert_eq!(rectmanager.get_relative_offset(clock), Some((53, 0)));
+    assert_eq!(rectmanager.get_relative_offset(popup), Some((25, 13)));
+    assert_eq!(rectmanager.get_relative_offset(unanchored), Some((3, 3)));
+
+    // and back again
+    rectmanager.resize(window, 40, 20)?;
+    assert_eq!(rectmanager.get_relative_offset(footer), Some((1, 18)));
+    assert_eq!(rectmanager.get_rect_size(footer), Some((38, 1)));
+    assert_eq!(rectmanager.get_relative_offset(clock), Some((33, 0)));
+    assert_eq!(rectmanager.get_relative_offset(popup), Some((15, 8)));
+
+    // Squeezing the footer down to nothing shouldn't lose its margins
+    rectmanager.resize(window, 1, 20)?;
+    assert_eq!(rectmanager.get_rect_size(footer), Some((0, 1)));
+    rectmanager.resize(window, 40, 20)?;
+    assert_eq!(rectmanager.get_relative_offset(footer), Some((1, 18)));
+    assert_eq!(rectmanager.get_rect_size(footer), Some((38, 1)));
+    assert_eq!(rectmanager.get_relative_offset(clock), Some((33, 0)));
+
+    // Moving an anchored rect by hand measures its margins again
+    rectmanager.set_position(footer, 5, 18)?;
+    rectmanager.resize(window, 50, 20)?;
+    assert_eq!(rectmanager.get_rect_size(footer), Some((48, 1)));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_scrolling() -> Result<(), WreckedError> {
+    let mut rectmanager = RectManager::new();
+    let container = rectmanager.new_rect(ROOT).ok().unwrap();
+    rectmanager.resize(container, 10, 10)?;
+
+    let viewport = rectmanager.new_rect(container).ok().unwrap();
+    rectmanager.resize(viewport, 5, 3)?;
+    rectmanager.set_position(viewport, 1, 1)?;
+    rectmanager.set_content_size(viewport, 5, 20)?;
+    assert_eq!(rectmanager.get_content_size(viewport), Some((5, 20)));
+
+    for y in 0 .. 20 {
+        rectmanager.set_string(viewport, 0, y, &format!("{}", y))?;
+    }
+    let child = rectmanager.new_rect(viewport).ok().unwrap();
+    rectmanager.set_position(child, 4, 10)?;
+    rectmanager.set_character(child, 0, 0, '*')?;
+
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(1, 1)).unwrap().0, '0');
+    assert_eq!(display.get(&(1, 3)).unwrap().0, '2');
+
+    rectmanager.scroll_to(viewport, 0, 9)?;
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(1, 1)).unwrap().0, '9');
+    assert_eq!(display.get(&(1, 2)).unwrap().0, '1');
+    assert_eq!(display.get(&(2, 2)).unwrap().0, '0');
+    assert_eq!(display.get(&(5, 2)).unwrap().0, '*');
+    // content that isn't in view doesn't leak out of the viewport
+    assert_eq!(display.get(&(1, 4)).unwrap().0, ' ');
+    assert_eq!(rectmanager.get_absolute_offset(child), Some((5, 2)));
+
+    // Changes to the content area are refreshed at their scrolled position
+    rectmanager.set_character(viewport, 3, 11, 'Z')?;
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(4, 3)).unwrap().0, 'Z');
+
+    // Offsets are kept inside the content area
+    rectmanager.scroll_by(viewport, 0, 100)?;
+    assert_eq!(rectmanager.get_scroll_offset(viewport), Some((0, 17)));
+    rectmanager.scroll_by(viewport, -3, -100)?;
+    assert_eq!(rectmanager.get_scroll_offset(viewport), Some((0, 0)));
+
+    rectmanager.set_scrollbar(viewport, true)?;
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(5, 1)).unwrap().0, '█');
+    assert_eq!(display.get(&(5, 2)).unwrap().0, '░');
+    assert_eq!(display.get(&(5, 3)).unwrap().0, '░');
+    rectmanager.scroll_to(viewport, 0, 17)?;
+    let display = rectmanager.get_cached_display(container)?;
+    assert_eq!(display.get(&(5, 1)).unwrap().0, '░');
+    assert_eq!(display.get(&(5, 3)).unwrap().0, '█');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_list_view() -> Result<(), WreckedError> {
+    use widgets::ListView;
+    use input::{parse_input, InputEvent, Key, Modifiers};
+
+    let mut rectmanager = RectManager::new();
+    let rows: Vec<String> = (0 .. 1000).map(|i| format!("Row {}", i)).collect();
+    let mut list = ListView::new(&mut rectmanager, ROOT, rows)?;
+    let rect_id = list.get_rect_id();
+    rectmanager.resize(rect_id, 8, 4)?;
+    rectmanager.set_position(rect_id, 2, 3)?;
+    list.refresh(&mut rectmanager)?;
+
+    assert_eq!(rectmanager.get_character(rect_id, 4, 0)?, '0');
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 0).unwrap().invert, true);
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 1), None);
+
+    for _ in 0 .. 5 {
+        list.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    }
+    assert_eq!(list.get_cursor(), Some(5));
+    assert_eq!(list.get_top(), 2);
+    assert_eq!(rectmanager.get_character(rect_id, 4, 3)?, '5');
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 3).unwrap().invert, true);
+
+    list.handle_event(&mut rectmanager, &InputEvent::key(Key::End))?;
+    assert_eq!(list.get_cursor(), Some(999));
+    assert_eq!(rectmanager.get_character(rect_id, 6, 3)?, '9');
+
+    // Multi-select with shift and space
+    list.set_multi_select(true);
+    list.handle_event(&mut rectmanager, &InputEvent::key(Key::Home))?;
+    list.handle_event(&mut rectmanager, &InputEvent::Key(Key::Down, Modifiers { shift: true, ..Modifiers::none() }))?;
+    list.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    list.handle_event(&mut rectmanager, &InputEvent::key(Key::Char(' ')))?;
+    assert_eq!(list.get_selection(), vec![0, 1, 2]);
+
+    // Click on the last visible line (absolute coordinates; terminals count from 1)
+    for event in parse_input(b"\x1B[<0;3;7M") {
+        assert!(list.handle_event(&mut rectmanager, &event)?);
+    }
+    assert_eq!(list.get_cursor(), Some(3));
+    // Clicks outside of the list are ignored
+    for event in parse_input(b"\x1B[<0;1;1M") {
+        assert!(!list.handle_event(&mut rectmanager, &event)?);
+    }
+
+    // Shrinking the data source
+    list.get_source_mut().truncate(2);
+    list.refresh(&mut rectmanager)?;
+    assert_eq!(list.get_cursor(), Some(1));
+    assert_eq!(list.get_selection(), vec![0, 1]);
+    assert_eq!(rectmanager.get_character(rect_id, 0, 2)?, ' ');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_table() -> Result<(), WreckedError> {
+    use widgets::{Table, Column, ColumnWidth, SortDirection, Alignment};
+    use input::{parse_input, InputEvent, Key};
+
+    let mut rectmanager = RectManager::new();
+    let mut table = Table::new(&mut rectmanager, ROOT, vec![
+        Column::new("Name", ColumnWidth::Min(4)),
+        Column::new("PID", ColumnWidth::Auto),
+        Column::aligned("CPU", ColumnWidth::Fixed(5), Alignment::Right)
+    ])?;
+    let rect_id = table.get_rect_id();
+    rectmanager.resize(rect_id, 20, 5)?;
+    table.set_rows(&mut rectmanager, vec![
+        vec!["systemd-journald".to_string(), "1".to_string(), "0.5".to_string()],
+        vec!["cargo".to_string(), "31337".to_string(), "98.2".to_string()],
+        vec!["bash".to_string(), "200".to_string(), "10".to_string()]
+    ])?;
+
+    // 20 cells, less 2 separators: Auto fits "31337", Fixed takes 5, Min gets the rest
+    assert_eq!(table.get_column_widths(&rectmanager), vec![8, 5, 5]);
+
+    let line = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 20).map(|x| rectmanager.get_character(rect_id, x, y).ok().unwrap()).collect()
+    };
+    assert_eq!(line(&rectmanager, 0), "Name    │PID  │  CPU");
+    assert_eq!(line(&rectmanager, 1), "────────┼─────┼─────");
+    assert_eq!(line(&rectmanager, 2), "systemd…│1    │  0.5");
+
+    // Numeric sort, with the indicator in the header
+    table.sort(&mut rectmanager, 2, SortDirection::Descending)?;
+    assert_eq!(line(&rectmanager, 0), "Name    │PID  │CPU ▼");
+    assert_eq!(line(&rectmanager, 2), "cargo   │31337│ 98.2");
+    assert_eq!(line(&rectmanager, 3), "bash    │200  │   10");
+
+    // Selection, with scrolling once the selection leaves the 3 visible rows
+    table.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    assert_eq!(table.get_selected(), Some(0));
+    assert_eq!(rectmanager.get_character_style(rect_id, 3, 2).unwrap().invert, true);
+    table.handle_event(&mut rectmanager, &InputEvent::key(Key::End))?;
+    assert_eq!(table.get_selected(), Some(2));
+
+    // Clicking the Name header sorts by it; the selected row is kept
+    for event in parse_input(b"\x1B[<0;2;1M") {
+        table.handle_event(&mut rectmanager, &event)?;
+    }
+    assert_eq!(table.get_sort(), Some((0, SortDirection::Ascending)));
+    assert_eq!(table.get_rows()[2][0], "systemd-journald");
+    assert_eq!(table.get_selected(), Some(2));
+
+    // Columns mixing numbers and text sort the numbers first
+    table.set_rows(&mut rectmanager, vec![
+        vec!["a".to_string(), "10".to_string()],
+        vec!["b".to_string(), "-".to_string()],
+        vec!["c".to_string(), "NaN".to_string()],
+        vec!["d".to_string(), "9".to_string()],
+        vec!["e".to_string(), "1x".to_string()]
+    ])?;
+    table.sort(&mut rectmanager, 1, SortDirection::Ascending)?;
+    let order: Vec<String> = table.get_rows().iter().map(|row| row[0].clone()).collect();
+    assert_eq!(order, vec!["d", "a", "b", "e", "c"]);
+
+    // Words that happen to parse as floats are still text
+    table.set_rows(&mut rectmanager, vec![
+        vec!["a".to_string(), "Warn".to_string()],
+        vec!["b".to_string(), "inf".to_string()],
+        vec!["c".to_string(), "Error".to_string()],
+        vec!["d".to_string(), "NaN".to_string()],
+        vec!["e".to_string(), "Info".to_string()]
+    ])?;
+    table.sort(&mut rectmanager, 1, SortDirection::Ascending)?;
+    let order: Vec<String> = table.get_rows().iter().map(|row| row[0].clone()).collect();
+    assert_eq!(order, vec!["c", "e", "d", "a", "b"]);
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_text_input() -> Result<(), WreckedError> {
+    use widgets::TextInput;
+    use input::{parse_input, InputEvent, Key};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 25, 25)?;
+    let mut text_input = TextInput::new(&mut rectmanager, ROOT)?;
+    let rect_id = text_input.get_rect_id();
+    rectmanager.resize(rect_id, 8, 1)?;
+    rectmanager.set_position(rect_id, 1, 2)?;
+
+    text_input.set_placeholder(&mut rectmanager, "Search")?;
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, 'S');
+    assert_eq!(rectmanager.get_cursor_position(), None);
+
+    text_input.focus(&mut rectmanager)?;
+    for event in parse_input("hello wörld".as_bytes()) {
+        text_input.handle_event(&mut rectmanager, &event)?;
+    }
+    assert_eq!(text_input.get_text(), "hello wörld");
+
+    // The text scrolls to keep the cursor (after the last character) in view
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, 'o');
+    assert_eq!(rectmanager.get_character(rect_id, 6, 0)?, 'd');
+    assert_eq!(rectmanager.get_cursor_position(), Some((8, 2)));
+
+    // Ctrl-Left jumps to the start of the word, Home to the start of the text
+    text_input.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Left))?;
+    assert_eq!(text_input.get_cursor(), 6);
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Backspace))?;
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('_')))?;
+    assert_eq!(text_input.get_text(), "hello_wörld");
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Home))?;
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Delete))?;
+    assert_eq!(text_input.get_text(), "ello_wörld");
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, 'e');
+    assert_eq!(rectmanager.get_cursor_position(), Some((1, 2)));
+
+    // Masking
+    text_input.set_mask(&mut rectmanager, Some('*'))?;
+    assert_eq!(rectmanager.get_character(rect_id, 3, 0)?, '*');
+    text_input.set_mask(&mut rectmanager, None)?;
+
+    // History
+    text_input.set_history_size(2);
+    text_input.submit(&mut rectmanager)?;
+    text_input.set_text(&mut rectmanager, "two")?;
+    text_input.submit(&mut rectmanager)?;
+    text_input.set_text(&mut rectmanager, "three")?;
+    text_input.submit(&mut rectmanager)?;
+    assert_eq!(text_input.get_history(), vec!["two".to_string(), "three".to_string()]);
+
+    text_input.set_text(&mut rectmanager, "draft")?;
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Up))?;
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Up))?;
+    assert_eq!(text_input.get_text(), "two");
+    assert!(!text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Up))?);
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    text_input.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    assert_eq!(text_input.get_text(), "draft");
+
+    // Blurring leaves the cursor alone if another input has already been focused
+    let mut other = TextInput::new(&mut rectmanager, ROOT)?;
+    rectmanager.resize(other.get_rect_id(), 8, 1)?;
+    rectmanager.set_position(other.get_rect_id(), 1, 4)?;
+    other.focus(&mut rectmanager)?;
+    text_input.blur(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_cursor_position(), Some((1, 4)));
+    other.blur(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_cursor_position(), None);
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_text_area() -> Result<(), WreckedError> {
+    use widgets::{TextArea, Clipboard};
+    use input::{parse_input, InputEvent, Key, Modifiers};
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    struct SharedClipboard(Rc<RefCell<String>>);
+    impl Clipboard for SharedClipboard {
+        fn copy(&mut self, text: &str) {
+            *self.0.borrow_mut() = text.to_string();
+        }
+        fn paste(&mut self) -> Option<String> {
+            Some(self.0.borrow().clone())
+        }
+    }
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 25, 25)?;
+    let mut editor = TextArea::new(&mut rectmanager, ROOT)?;
+    rectmanager.resize(editor.get_rect_id(), 10, 3)?;
+    let gutter_id = editor.get_gutter_rect_id();
+    let text_id = editor.get_text_rect_id();
+    let clipboard = Rc::new(RefCell::new(String::new()));
+    editor.set_clipboard(Box::new(SharedClipboard(clipboard.clone())));
+
+    editor.set_text(&mut rectmanager, "alpha\nbeta\ngamma")?;
+    editor.focus(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_character(gutter_id, 0, 2)?, '3');
+    assert_eq!(rectmanager.get_character(text_id, 0, 1)?, 'b');
+    assert_eq!(rectmanager.get_cursor_position(), Some((7, 2)));
+
+    // Enter splits lines, and the view follows the cursor
+    editor.set_cursor(&mut rectmanager, 1, 2)?;
+    editor.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?;
+    assert_eq!(editor.get_text(), "alpha\nbe\nta\ngamma");
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::End))?;
+    assert_eq!(rectmanager.get_character(text_id, 0, 2)?, 'g');
+    assert_eq!(rectmanager.get_character(gutter_id, 0, 2)?, '4');
+
+    // Select across lines with shift, then cut
+    let shift = Modifiers { shift: true, ..Modifiers::none() };
+    editor.set_cursor(&mut rectmanager, 1, 1)?;
+    editor.handle_event(&mut rectmanager, &InputEvent::Key(Key::Down, shift))?;
+    editor.handle_event(&mut rectmanager, &InputEvent::Key(Key::Right, shift))?;
+    assert_eq!(editor.get_selection(), Some(((1, 1), (2, 2))));
+    assert_eq!(rectmanager.get_character_style(text_id, 1, 1).unwrap().invert, true);
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('x')))?;
+    assert_eq!(*clipboard.borrow(), "e\nta");
+    assert_eq!(editor.get_text(), "alpha\nb\ngamma");
+    assert_eq!(rectmanager.get_character_style(text_id, 1, 1), None);
+
+    // Paste, then undo the paste and the cut, then redo the cut
+    editor.handle_event(&mut rectmanager, &InputEvent::key(Key::End))?;
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('v')))?;
+    assert_eq!(editor.get_text(), "alpha\nbe\nta\ngamma");
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('z')))?;
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('z')))?;
+    assert_eq!(editor.get_text(), "alpha\nbe\nta\ngamma");
+    editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('y')))?;
+    assert_eq!(editor.get_text(), "alpha\nb\ngamma");
+
+    // Typing is undone a run at a time
+    editor.set_text(&mut rectmanager, "")?;
+    for event in parse_input(b"one two") {
+        editor.handle_event(&mut rectmanager, &event)?;
+    }
+    editor.handle_event(&mut rectmanager, &InputEvent::key(Key::Backspace))?;
+    editor.undo(&mut rectmanager)?;
+    assert_eq!(editor.get_text(), "one two");
+    editor.undo(&mut rectmanager)?;
+    assert_eq!(editor.get_text(), "");
+
+    // Clicking past the end of a line puts the cursor at its end, with nothing selected
+    editor.set_text(&mut rectmanager, "ab\ncdef")?;
+    for event in parse_input(b"\x1B[<0;9;1MX") {
+        editor.handle_event(&mut rectmanager, &event)?;
+    }
+    assert_eq!(editor.get_text(), "abX\ncdef");
+    for event in parse_input(b"\x1B[<0;9;1M") {
+        editor.handle_event(&mut rectmanager, &event)?;
+    }
+    editor.handle_event(&mut rectmanager, &InputEvent::key(Key::Backspace))?;
+    assert_eq!(editor.get_text(), "ab\ncdef");
+
+    // Soft wrap
+    editor.set_text(&mut rectmanager, "0123456789abc\nx")?;
+    editor.set_wrap(&mut rectmanager, true)?;
+    // The gutter takes 2 columns, leaving 8 for the text
+    assert_eq!(rectmanager.get_character(text_id, 0, 1)?, '8');
+    assert_eq!(rectmanager.get_character(gutter_id, 0, 1)?, ' ');
+    assert_eq!(rectmanager.get_character(gutter_id, 0, 2)?, '2');
+
+    // Without wrapping, the text scrolls sideways to follow the cursor
+    editor.set_wrap(&mut rectmanager, false)?;
+    editor.set_cursor(&mut rectmanager, 0, 13)?;
+    assert_eq!(rectmanager.get_character(text_id, 0, 0)?, '6');
+    assert_eq!(rectmanager.get_cursor_position(), Some((9, 0)));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_highlighters() -> Result<(), WreckedError> {
+    use widgets::{Highlighter, Span, JsonHighlighter, IniHighlighter, DiffHighlighter, TextArea};
+    use widgets::highlight::get_character_styles;
+
+    let json = JsonHighlighter::default();
+    let spans = json.highlight_line(r#"  "name": "w\"x", "n": -1.5e3, "ok": null"#);
+    assert_eq!(spans, vec![
+        Span::new(2, 8, json.key),
+        Span::new(10, 16, json.string),
+        Span::new(18, 21, json.key),
+        Span::new(23, 29, json.number),
+        Span::new(31, 35, json.key),
+        Span::new(37, 41, json.literal)
+    ]);
+
+    let ini = IniHighlighter::default();
+    assert_eq!(ini.highlight_line("[server.tls]"), vec![Span::new(0, 12, ini.section)]);
+    assert_eq!(ini.highlight_line("  ; off"), vec![Span::new(2, 7, ini.comment)]);
+    assert_eq!(ini.highlight_line("port = 8080 # http"), vec![
+        Span::new(0, 4, ini.key),
+        Span::new(7, 11, ini.number),
+        Span::new(12, 18, ini.comment)
+    ]);
+    assert_eq!(ini.highlight_line("name='a#b'"), vec![
+        Span::new(0, 4, ini.key),
+        Span::new(5, 10, ini.string)
+    ]);
+
+    let diff = DiffHighlighter::default();
+    assert_eq!(diff.highlight_line("--- a/lib.rs"), vec![Span::new(0, 12, diff.header)]);
+    assert_eq!(diff.highlight_line("@@ -1 +1 @@"), vec![Span::new(0, 11, diff.hunk)]);
+    assert_eq!(diff.highlight_line("-old"), vec![Span::new(0, 4, diff.removed)]);
+    assert_eq!(diff.highlight_line(" same"), vec![]);
+
+    let styles = get_character_styles(&[Span::new(1, 3, diff.added)], 4);
+    assert_eq!(styles, vec![None, Some(diff.added), Some(diff.added), None]);
+
+    // Highlighting in an editor
+    let mut rectmanager = RectManager::new();
+    let mut editor = TextArea::new(&mut rectmanager, ROOT)?;
+    rectmanager.resize(editor.get_rect_id(), 20, 3)?;
+    editor.set_line_numbers(&mut rectmanager, false)?;
+    editor.set_highlighter(&mut rectmanager, Some(Box::new(IniHighlighter::default())))?;
+    editor.set_text(&mut rectmanager, "[a]\nk = true")?;
+    let text_id = editor.get_text_rect_id();
+    assert_eq!(rectmanager.get_character_style(text_id, 0, 1), Some(ini.key));
+    assert_eq!(rectmanager.get_character_style(text_id, 1, 1), None);
+    assert_eq!(rectmanager.get_character_style(text_id, 4, 1), Some(ini.literal));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_tabs() -> Result<(), WreckedError> {
+    use widgets::Tabs;
+    use input::{parse_input, InputEvent, Key, Modifiers};
+
+    let mut rectmanager = RectManager::new();
+    let mut tabs = Tabs::new(&mut rectmanager, ROOT)?;
+    let rect_id = tabs.get_rect_id();
+    let bar_id = tabs.get_bar_rect_id();
+    rectmanager.resize(rect_id, 20, 6)?;
+    rectmanager.set_position(rect_id, 0, 1)?;
+
+    let first = tabs.add_page(&mut rectmanager, "One")?;
+    let second = tabs.add_page(&mut rectmanager, "Two")?;
+    let orphan = rectmanager.new_orphan()?;
+    tabs.add_page_rect(&mut rectmanager, "Three", orphan)?;
+
+    let bar: String = (0 .. 20).map(|x| rectmanager.get_character(bar_id, x, 0).ok().unwrap()).collect();
+    assert_eq!(bar, " One │ Two │ Three  ");
+    assert_eq!(rectmanager.get_character_style(bar_id, 1, 0).unwrap().invert, true);
+    assert_eq!(rectmanager.get_character_style(bar_id, 7, 0).unwrap().invert, false);
+
+    // Pages fill the content area below the bar, and follow it when resized
+    assert_eq!(rectmanager.get_rect_size(first), Some((20, 5)));
+    assert_eq!(rectmanager.get_absolute_offset(second), Some((0, 2)));
+    rectmanager.resize(rect_id, 30, 8)?;
+    assert_eq!(rectmanager.get_rect_size(orphan), Some((30, 7)));
+
+    let ctrl = Modifiers { ctrl: true, ..Modifiers::none() };
+    tabs.handle_event(&mut rectmanager, &InputEvent::Key(Key::PageUp, ctrl))?;
+    assert_eq!(tabs.get_active(), Some(2));
+    assert!(rectmanager.is_rect_enabled(orphan));
+    assert!(!rectmanager.is_rect_enabled(first));
+    tabs.handle_event(&mut rectmanager, &InputEvent::Key(Key::PageDown, ctrl))?;
+    assert_eq!(tabs.get_active(), Some(0));
+    assert!(!tabs.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('4')))?);
+
+    // Click on "Two" (terminals count from 1)
+    for event in parse_input(b"\x1B[<0;9;2M") {
+        assert!(tabs.handle_event(&mut rectmanager, &event)?);
+    }
+    assert_eq!(tabs.get_active(), Some(1));
+    assert!(rectmanager.is_rect_enabled(second));
+
+    // Removing the active page shows the one that takes its place
+    tabs.remove_page(&mut rectmanager, 1)?;
+    assert_eq!(tabs.get_page_count(), 2);
+    assert_eq!(tabs.get_active(), Some(1));
+    assert!(rectmanager.is_rect_enabled(orphan));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_dialogs() -> Result<(), WreckedError> {
+    use widgets::{DialogStack, DialogEvent, DialogResponse, TextInput};
+    use input::{parse_input, InputEvent, Key};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 40, 12)?;
+    rectmanager.set_character(ROOT, 39, 0, 'R')?;
+    let background = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(background, 40, 1)?;
+    rectmanager.set_position(background, 0, 11)?;
+    rectmanager.set_character(background, 0, 0, 'B')?;
+
+    let mut text_input = TextInput::new(&mut rectmanager, ROOT)?;
+    rectmanager.resize(text_input.get_rect_id(), 10, 1)?;
+    text_input.focus(&mut rectmanager)?;
+    let saved_cursor = rectmanager.get_cursor();
+    assert!(saved_cursor.is_some());
+
+    let mut dialogs = DialogStack::new();
+    let alert = dialogs.alert(&mut rectmanager, "Note", "Saved")?;
+    let confirm = dialogs.confirm(&mut rectmanager, "Delete", "Remove 3 files?")?;
+    assert_eq!(dialogs.len(), 2);
+    assert_eq!(dialogs.get_top(), Some(confirm));
+    assert_eq!(rectmanager.get_cursor(), None);
+
+    // Everything beneath the top dialog is dimmed, including the other dialog
+    let (confirm_x, confirm_y) = rectmanager.get_absolute_offset(confirm).unwrap();
+    let display = rectmanager.get_cached_display(ROOT)?;
+    assert_eq!(display.get(&(39, 0)).unwrap().0, 'R');
+    assert!(display.get(&(39, 0)).unwrap().1.dim);
+    assert_eq!(display.get(&(0, 11)).unwrap().0, 'B');
+    assert!(display.get(&(0, 11)).unwrap().1.dim);
+    assert_eq!(display.get(&(confirm_x, confirm_y)).unwrap().0, '┌');
+    assert!(!display.get(&(confirm_x, confirm_y)).unwrap().1.dim);
+
+    // Input is captured; the buttons toggle and the choice is returned
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Right))?, DialogEvent::Captured);
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?, DialogEvent::Closed(confirm, DialogResponse::Confirmed(false)));
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('x')))?, DialogEvent::Captured);
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Escape))?, DialogEvent::Closed(alert, DialogResponse::Dismissed));
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Escape))?, DialogEvent::Ignored);
+
+    // Nothing is dimmed once the dialogs are gone, and the cursor is back
+    let display = rectmanager.get_cached_display(ROOT)?;
+    assert!(!display.get(&(0, 0)).unwrap().1.dim);
+    assert!(!display.get(&(0, 11)).unwrap().1.dim);
+    assert_eq!(rectmanager.get_cursor(), saved_cursor);
+
+    // Prompts edit text and return it
+    let prompt = dialogs.prompt(&mut rectmanager, "Rename", "New name:", "a.txt")?;
+    assert!(rectmanager.get_cursor_position().is_some());
+    for event in parse_input(b"\x7F\x7F\x7Fmd") {
+        dialogs.handle_event(&mut rectmanager, &event)?;
+    }
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?, DialogEvent::Closed(prompt, DialogResponse::Prompted(Some("a.md".to_string()))));
+
+    // Custom dialogs have their input passed back
+    let custom = dialogs.push(&mut rectmanager, 10, 4)?;
+    assert_eq!(rectmanager.get_relative_offset(custom), Some((15, 4)));
+    assert_eq!(dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?, DialogEvent::Forward(custom));
+    rectmanager.resize(ROOT, 60, 20)?;
+    assert_eq!(rectmanager.get_relative_offset(custom), Some((25, 8)));
+    assert_eq!(dialogs.pop(&mut rectmanager)?, Some(custom));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_dim_ansi() {
+    let mut rectmanager = RectManager::new();
+    let mut bold_dim = EffectsHandler::new();
+    bold_dim.bold = true;
+    bold_dim.dim = true;
+    let mut bold = EffectsHandler::new();
+    bold.bold = true;
+
+    // Turning dim off also turns off bold, so bold has to be turned back on
+    let output = rectmanager.build_ansi_string(vec![((0, 0), ('a', bold_dim)), ((1, 0), ('b', bold))]);
+    assert_eq!(output, "\x1B[1;1H\x1B[1;2ma\x1B[22;1mb");
+    rectmanager.kill().ok();
+}
+
+#[test]
+fn test_menus() -> Result<(), WreckedError> {
+    use widgets::{MenuBar, ContextMenu, MenuItem, MenuEvent};
+    use input::{parse_input, InputEvent, Key, Modifiers};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 40, 12)?;
+    let ctrl = Modifiers { ctrl: true, ..Modifiers::none() };
+    let alt = Modifiers { alt: true, ..Modifiers::none() };
+
+    let mut menubar = MenuBar::new(&mut rectmanager, ROOT)?;
+    menubar.add_menu(&mut rectmanager, "&File", vec![
+        MenuItem::with_accelerator("&New", "new", Key::Char('n'), ctrl),
+        MenuItem::separator(),
+        MenuItem::submenu("&Recent", vec![
+            MenuItem::new("a.txt", "recent:a"),
+            MenuItem::new("b.txt", "recent:b")
+        ]),
+        MenuItem { enabled: false, ..MenuItem::new("&Quit", "quit") }
+    ])?;
+    menubar.add_menu(&mut rectmanager, "&Edit", vec![MenuItem::new("&Undo", "undo")])?;
+    assert_eq!(rectmanager.get_character(menubar.get_rect_id(), 7, 0)?, 'E');
+    assert!(rectmanager.get_character_style(menubar.get_rect_id(), 7, 0).unwrap().underline);
+
+    assert_eq!(menubar.handle_event(&mut rectmanager, &InputEvent::Key(Key::Char('n'), ctrl))?, MenuEvent::Activated("new".to_string()));
+    assert_eq!(menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('n')))?, MenuEvent::Ignored);
+
+    // The dropdown opens below its title, on top of everything else
+    menubar.handle_event(&mut rectmanager, &InputEvent::Key(Key::Char('f'), alt))?;
+    assert_eq!(menubar.get_open_menu(), Some(0));
+    let display = rectmanager.get_cached_display(ROOT)?;
+    assert_eq!(display.get(&(0, 1)).unwrap().0, '┌');
+    assert_eq!(display.get(&(0, 3)).unwrap().0, '├');
+    assert_eq!(display.get(&(2, 2)).unwrap().0, 'N');
+    assert!(display.get(&(2, 2)).unwrap().1.invert);
+    assert_eq!(display.get(&(10, 2)).unwrap().0, 'C');
+    assert_eq!(display.get(&(17, 4)).unwrap().0, '▸');
+
+    // Down skips the separator; Right opens the submenu beside its item
+    menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Right))?;
+    let display = rectmanager.get_cached_display(ROOT)?;
+    assert_eq!(display.get(&(20, 3)).unwrap().0, '┌');
+    assert_eq!(display.get(&(22, 4)).unwrap().0, 'a');
+    menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Down))?;
+    assert_eq!(menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?, MenuEvent::Activated("recent:b".to_string()));
+    assert!(!menubar.is_open());
+    assert_eq!(rectmanager.get_cached_display(ROOT)?.get(&(0, 1)).unwrap().0, ' ');
+
+    // Disabled items can't be chosen; Right moves to the next menu and clicking elsewhere closes it
+    menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::F(10)))?;
+    assert_eq!(menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('q')))?, MenuEvent::Captured);
+    menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Right))?;
+    assert_eq!(menubar.get_open_menu(), Some(1));
+    for event in parse_input(b"\x1B[<0;31;11M") {
+        assert_eq!(menubar.handle_event(&mut rectmanager, &event)?, MenuEvent::Closed);
+    }
+    assert!(!menubar.is_open());
+
+    // Clicking a title opens its menu
+    for event in parse_input(b"\x1B[<0;9;1M") {
+        assert_eq!(menubar.handle_event(&mut rectmanager, &event)?, MenuEvent::Captured);
+    }
+    assert_eq!(menubar.get_open_menu(), Some(1));
+    assert_eq!(menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Escape))?, MenuEvent::Closed);
+
+    // A context menu near the bottom right corner flips to fit
+    let mut context_menu = ContextMenu::new();
+    let rect_id = context_menu.open(&mut rectmanager, 38, 10, vec![
+        MenuItem::new("Copy", "copy"),
+        MenuItem::new("Paste", "paste")
+    ])?;
+    assert_eq!(rectmanager.get_rect_size(rect_id), Some((9, 4)));
+    assert_eq!(rectmanager.get_absolute_offset(rect_id), Some((30, 7)));
+    assert_eq!(context_menu.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('x')))?, MenuEvent::Captured);
+    for event in parse_input(b"\x1B[<0;33;10M") {
+        assert_eq!(context_menu.handle_event(&mut rectmanager, &event)?, MenuEvent::Activated("paste".to_string()));
+    }
+    assert!(!context_menu.is_open());
+    assert!(!rectmanager.has_rect(rect_id));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_tree_view() -> Result<(), WreckedError> {
+    use std::cell::Cell;
+    use widgets::{TreeView, TreeDataSource};
+    use input::{parse_input, InputEvent, Key};
+
+    // Node 0 has 100000 children; 1 and 2 each have one child (10 and 20)
+    struct Source {
+        loads: Cell<usize>,
+        labels: Cell<usize>
+    }
+    impl TreeDataSource for Source {
+        fn get_roots(&self) -> Vec<usize> {
+            vec![1, 2, 0]
+        }
+        fn get_label(&self, node: usize) -> String {
+            self.labels.set(self.labels.get() + 1);
+            format!("n{}", node)
+        }
+        fn has_children(&self, node: usize) -> bool {
+            node < 3
+        }
+        fn get_children(&self, node: usize) -> Vec<usize> {
+            self.loads.set(self.loads.get() + 1);
+            if node == 0 {
+                (100 .. 100100).collect()
+            } else {
+                vec![node * 10]
+            }
+        }
+    }
+
+    let mut rectmanager = RectManager::new();
+    let source = Source { loads: Cell::new(0), labels: Cell::new(0) };
+    let mut tree = TreeView::new(&mut rectmanager, ROOT, source)?;
+    let rect_id = tree.get_rect_id();
+    rectmanager.resize(rect_id, 12, 5)?;
+    rectmanager.set_position(rect_id, 0, 2)?;
+    tree.refresh(&mut rectmanager)?;
+    assert_eq!(tree.get_row_count(), 3);
+    assert_eq!(tree.get_source().loads.get(), 0);
+
+    // Right expands, then moves to the first child
+    tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Right))?;
+    assert_eq!(tree.get_source().loads.get(), 1);
+    tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Right))?;
+    assert_eq!(tree.get_cursor(), Some(10));
+    assert_eq!(tree.get_depth(1), Some(1));
+
+    // Expand the big node and check the guides
+    tree.expand(&mut rectmanager, 0)?;
+    let line = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 12).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect()
+    };
+    assert_eq!(line(&rectmanager, 0), "▾ n1        ");
+    assert_eq!(line(&rectmanager, 1), "└── n10     ");
+    assert_eq!(line(&rectmanager, 2), "▸ n2        ");
+    assert_eq!(line(&rectmanager, 3), "▾ n0        ");
+    assert_eq!(line(&rectmanager, 4), "├── n100    ");
+    assert!(rectmanager.get_character_style(rect_id, 0, 4).unwrap().foreground_color.is_some());
+    assert!(rectmanager.get_character_style(rect_id, 3, 1).unwrap().invert);
+
+    // Only visible rows are drawn
+    tree.get_source().labels.set(0);
+    tree.handle_event(&mut rectmanager, &InputEvent::key(Key::End))?;
+    assert_eq!(tree.get_cursor(), Some(100099));
+    assert_eq!(tree.get_top(), 99999);
+    assert_eq!(line(&rectmanager, 4), "└── n100099 ");
+    assert!(tree.get_source().labels.get() <= 10);
+
+    // Left moves to the parent, then collapses it
+    tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Left))?;
+    assert_eq!(tree.get_cursor(), Some(0));
+    tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Left))?;
+    assert_eq!(tree.get_row_count(), 4);
+    assert_eq!(tree.get_cursor_row(), 3);
+    assert_eq!(tree.get_top(), 0);
+
+    // Clicking the marker of n1 collapses it
+    for event in parse_input(b"\x1B[<0;1;3M") {
+        assert!(tree.handle_event(&mut rectmanager, &event)?);
+    }
+    assert_eq!(tree.get_cursor(), Some(1));
+    assert!(!tree.is_expanded(1));
+    assert_eq!(tree.get_row_count(), 3);
+
+    // Children are loaded once, until reloaded
+    tree.expand(&mut rectmanager, 1)?;
+    assert_eq!(tree.get_source().loads.get(), 2);
+    tree.reload(&mut rectmanager)?;
+    assert_eq!(tree.get_source().loads.get(), 3);
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_progress() -> Result<(), WreckedError> {
+    use std::time::{Duration, Instant};
+    use widgets::{Progress, ProgressBar, Gauge, Spinner};
+
+    // Half done in 10 seconds; 10 more to go
+    let mut progress = Progress::new(1000);
+    progress.set_start_time(Instant::now() - Duration::from_secs(10));
+    progress.set_done(500);
+    progress.show_percentage = false;
+    progress.show_eta = true;
+    progress.show_rate = true;
+    progress.unit = "B".to_string();
+    assert_eq!(progress.format(26), "████▌     50.0B/s ETA 0:10");
+    assert_eq!(progress.get_parts(26).1, "████▌    ");
+
+    let mut output = Vec::new();
+    Progress::indeterminate().write_inline(&mut output, 10).ok().unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\r⠋\x1B[K");
+
+    // An indeterminate Progress is the inline stand-in for a Spinner, label and all
+    let mut progress = Progress::indeterminate();
+    progress.label = "Linking".to_string();
+    let mut output = Vec::new();
+    progress.write_inline(&mut output, 12).ok().unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\r⠋ Linking\x1B[K");
+
+    let mut rectmanager = RectManager::new();
+    let mut progress_bar = ProgressBar::new(&mut rectmanager, ROOT, 64)?;
+    let rect_id = progress_bar.get_rect_id();
+    rectmanager.resize(rect_id, 13, 1)?;
+    progress_bar.set_bar_style(&mut rectmanager, Style { foreground_color: Some(Color::GREEN), ..Style::default() })?;
+    progress_bar.set_done(&mut rectmanager, 3)?;
+    let line: String = (0 .. 13).map(|x| rectmanager.get_character(rect_id, x, 0).unwrap_or(' ')).collect();
+    assert_eq!(line, "▍          4%");
+    assert!(rectmanager.get_character_style(rect_id, 0, 0).unwrap().foreground_color.is_some());
+    assert!(rectmanager.get_character_style(rect_id, 9, 0).is_none());
+
+    let mut gauge = Gauge::new(&mut rectmanager, ROOT)?;
+    rectmanager.resize(gauge.get_rect_id(), 1, 3)?;
+    gauge.set_value(&mut rectmanager, 0.5)?;
+    let column: String = (0 .. 3).map(|y| rectmanager.get_character(gauge.get_rect_id(), 0, y).unwrap_or(' ')).collect();
+    assert_eq!(column, " ▄█");
+    gauge.set_value(&mut rectmanager, 2.0)?;
+    assert_eq!(gauge.get_value(), 1.0);
+
+    let mut spinner = Spinner::new(&mut rectmanager, ROOT, "")?;
+    spinner.set_frames(&mut rectmanager, &['-', '|'], Duration::from_millis(20))?;
+    assert_eq!(spinner.get_frame(), '-');
+    std::thread::sleep(Duration::from_millis(25));
+    assert!(spinner.update(&mut rectmanager)?);
+    assert_eq!(rectmanager.get_character(spinner.get_rect_id(), 0, 0)?, '|');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_canvas() -> Result<(), WreckedError> {
+    use widgets::{Canvas, CanvasMode};
+
+    let mut rectmanager = RectManager::new();
+    let mut canvas = Canvas::new(&mut rectmanager, ROOT, 3, 2, CanvasMode::Braille)?;
+    let rect_id = canvas.get_rect_id();
+
+    // Each dot of the first cell
+    canvas.set_pixel(0, 0);
+    canvas.flush(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, '⠁');
+    canvas.set_pixel(1, 3);
+    canvas.set_pixel(0, 3);
+    canvas.flush(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, '⣁');
+    canvas.unset_pixel(0, 0);
+    assert!(!canvas.get_pixel(0, 0));
+    canvas.set_pixel(-1, 100);
+
+    // Cells take the color of the last pixel drawn in them
+    canvas.clear();
+    canvas.set_color(Some(Color::RED));
+    canvas.line(0, 0, 5, 0);
+    canvas.set_color(Some(Color::BLUE));
+    canvas.set_pixel(5, 1);
+    canvas.flush(&mut rectmanager)?;
+    assert_eq!(rectmanager.get_character(rect_id, 1, 0)?, '⠉');
+    assert_eq!(rectmanager.get_character_style(rect_id, 1, 0).unwrap().foreground_color, Some(Color::RED));
+    assert_eq!(rectmanager.get_character_style(rect_id, 2, 0).unwrap().foreground_color, Some(Color::BLUE));
+
+    // A filled square covers every pixel inside it
+    canvas.clear();
+    canvas.set_color(None);
+    canvas.rect(0, 0, 6, 8);
+    canvas.fill(2, 2);
+    canvas.flush(&mut rectmanager)?;
+    for x in 0 .. 3 {
+        for y in 0 .. 2 {
+            assert_eq!(rectmanager.get_character(rect_id, x, y)?, '⣿');
+        }
+    }
+
+    // Lines are cut down to the canvas, however far away their ends are
+    canvas.clear();
+    canvas.line(0, 7, 0, -1_000_000_000_000);
+    canvas.line(-1_000_000_000_000, 7, 1_000_000_000_000, 7);
+    canvas.line(10, -10, 20, -20);
+    canvas.line(isize::MIN, isize::MAX, isize::MAX, isize::MIN);
+    assert!((0 .. 8).all(|y| canvas.get_pixel(0, y)));
+    assert!((0 .. 6).all(|x| canvas.get_pixel(x, 7)));
+    assert!(!canvas.get_pixel(1, 0) && !canvas.get_pixel(5, 6));
+
+    // A circle is symmetrical and leaves its center empty
+    canvas.clear();
+    canvas.circle(3, 4, 2);
+    assert!(canvas.get_pixel(5, 4) && canvas.get_pixel(1, 4) && canvas.get_pixel(3, 2) && canvas.get_pixel(3, 6));
+    assert!(!canvas.get_pixel(3, 4));
+
+    canvas.set_mode(CanvasMode::HalfBlock);
+    assert_eq!(canvas.get_pixel_size(), (3, 4));
+    canvas.line(0, 1, 2, 1);
+    canvas.set_pixel(2, 0);
+    canvas.flush(&mut rectmanager)?;
+    let row: String = (0 .. 3).map(|x| rectmanager.get_character(rect_id, x, 0).unwrap_or(' ')).collect();
+    assert_eq!(row, "▄▄█");
+    assert_eq!(rectmanager.get_character(rect_id, 0, 1)?, ' ');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_charts() -> Result<(), WreckedError> {
+    use widgets::{LineChart, BarChart, Histogram, Sparkline};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 40, 20)?;
+
+    // Axes are labelled, and fixed bounds let new points be drawn without a full redraw
+    let mut chart = LineChart::new(&mut rectmanager, ROOT)?;
+    let rect_id = chart.get_rect_id();
+    rectmanager.resize(rect_id, 20, 6)?;
+    let series = chart.add_series("requests", None);
+    chart.set_x_bounds(&mut rectmanager, Some((0.0, 33.0)))?;
+    chart.set_y_bounds(&mut rectmanager, Some((0.0, 15.0)))?;
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, '1');
+    assert_eq!(rectmanager.get_character(rect_id, 1, 3)?, '0');
+    assert_eq!(rectmanager.get_character(rect_id, 2, 0)?, '┤');
+    assert_eq!(rectmanager.get_character(rect_id, 2, 4)?, '└');
+
+    let plot_id = chart.get_plot_rect_id();
+    chart.push_point(&mut rectmanager, series, 0.0, 0.0)?;
+    assert_eq!(rectmanager.get_character(plot_id, 0, 3)?, '⡀');
+    chart.push_point(&mut rectmanager, series, 1.0, 0.0)?;
+    assert_eq!(rectmanager.get_character(plot_id, 0, 3)?, '⣀');
+    chart.push_point(&mut rectmanager, series, 33.0, 15.0)?;
+    assert_eq!(rectmanager.get_character(plot_id, 16, 0)?, '⠉');
+    assert_eq!(rectmanager.get_character(plot_id, 0, 3)?, '⣀');
+    assert_eq!(chart.get_y_bounds(), Some((0.0, 15.0)));
+
+    // Samples far out of bounds are cut off at the edge, and ones that aren't finite are skipped over
+    chart.push_point(&mut rectmanager, series, 33.0, 1.0e12)?;
+    chart.push_point(&mut rectmanager, series, 16.0, f64::NAN)?;
+    chart.push_point(&mut rectmanager, series, 33.0, 0.0)?;
+    for _ in 0 .. 2 {
+        assert!((0 .. 4).all(|y| rectmanager.get_character(plot_id, 16, y).unwrap() != ' '));
+        assert_eq!(rectmanager.get_character(plot_id, 10, 0)?, ' ');
+        chart.refresh(&mut rectmanager)?;
+    }
+
+    // Vertical bars grow up from their labels
+    let mut bars = BarChart::new(&mut rectmanager, ROOT)?;
+    let rect_id = bars.get_rect_id();
+    rectmanager.resize(rect_id, 7, 4)?;
+    bars.set_bars(&mut rectmanager, &[("a", 8.0), ("b", 4.0)])?;
+    assert_eq!(rectmanager.get_character(rect_id, 1, 0)?, '8');
+    assert_eq!(rectmanager.get_character(rect_id, 5, 0)?, '4');
+    assert_eq!(rectmanager.get_character(rect_id, 0, 1)?, '█');
+    assert_eq!(rectmanager.get_character(rect_id, 4, 1)?, ' ');
+    assert_eq!(rectmanager.get_character(rect_id, 4, 2)?, '█');
+    assert_eq!(rectmanager.get_character(rect_id, 1, 3)?, 'a');
+
+    // Sparklines only keep as many values as fit
+    let mut sparkline = Sparkline::new(&mut rectmanager, ROOT)?;
+    let rect_id = sparkline.get_rect_id();
+    rectmanager.resize(rect_id, 3, 2)?;
+    sparkline.set_values(&mut rectmanager, &[0.0, 4.0, 8.0, 2.0])?;
+    assert_eq!(sparkline.get_values(), vec![4.0, 8.0, 2.0]);
+    let rows: Vec<String> = (0 .. 2).map(|y| {
+        (0 .. 3).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect()
+    }).collect();
+    assert_eq!(rows, vec![" █ ", "██▄"]);
+
+    // Histograms fit their range to the values, with the top of the range in the last bin
+    let mut histogram = Histogram::new(&mut rectmanager, ROOT)?;
+    let rect_id = histogram.get_rect_id();
+    rectmanager.resize(rect_id, 10, 4)?;
+    histogram.set_bins(&mut rectmanager, 2)?;
+    for value in [0.0, 1.0, 2.0, 3.0] {
+        histogram.push(&mut rectmanager, value)?;
+    }
+    assert_eq!(histogram.get_counts(), vec![2, 2]);
+    assert_eq!(rectmanager.get_character(rect_id, 0, 3)?, '0');
+    assert_eq!(rectmanager.get_character(rect_id, 9, 3)?, '3');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_draw_image() -> Result<(), WreckedError> {
+    use image::{RgbImage, FitMode, Palette};
+
+    // 16-bit binary samples are scaled down to 8 bits
+    let mut data = b"P6\n# comment\n2 1\n65535\n".to_vec();
+    data.extend([0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0x80, 0x00, 0xFF, 0xFF]);
+    let image = RgbImage::from_pnm(&data)?;
+    assert_eq!(image.get_pixel(0, 0), Some((255, 0, 0)));
+    assert_eq!(image.get_pixel(1, 0), Some((0, 128, 255)));
+    assert!(matches!(RgbImage::from_pnm(b"P6\n2 2\n255\n\x00"), Err(WreckedError::BadImage(_))));
+    assert!(matches!(RgbImage::from_pnm(b"GIF89a"), Err(WreckedError::BadImage(_))));
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 4, 3)?;
+    rectmanager.set_string(rect_id, 0, 0, "xxxx")?;
+
+    // A wide image is centered vertically, leaving the cells above and below it alone
+    let image = RgbImage::from_pixels(2, 1, vec![(255, 0, 0), (0, 0, 255)]).unwrap();
+    rectmanager.draw_image_with_palette(rect_id, &image, FitMode::Contain, Palette::Ansi16, false)?;
+    assert_eq!(rectmanager.get_character(rect_id, 0, 0)?, 'x');
+    assert_eq!(rectmanager.get_character(rect_id, 0, 1)?, '▀');
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 1).unwrap().foreground_color, Some(Color::BRIGHTRED));
+    assert_eq!(rectmanager.get_character_style(rect_id, 3, 1).unwrap().background_color, Some(Color::BLUE));
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 2), None);
+
+    let image = RgbImage::from_pixels(1, 1, vec![(255, 128, 0)]).unwrap();
+    rectmanager.draw_image_with_palette(rect_id, &image, FitMode::Stretch, Palette::Ansi256, false)?;
+    assert_eq!(rectmanager.get_character_style(rect_id, 2, 2).unwrap().foreground_color, Some(Color::Fixed(208)));
+
+    // A flat grey between two palette colors is only approximated when dithered
+    let grey = RgbImage::from_pixels(4, 6, vec![(64, 64, 64); 24]).unwrap();
+    let mut colors = std::collections::BTreeSet::new();
+    rectmanager.draw_image_with_palette(rect_id, &grey, FitMode::Stretch, Palette::Ansi16, false)?;
+    for x in 0 .. 4 {
+        colors.insert(rectmanager.get_character_style(rect_id, x, 1).unwrap().foreground_color);
+    }
+    assert_eq!(colors.len(), 1);
+    rectmanager.draw_image_with_palette(rect_id, &grey, FitMode::Stretch, Palette::Ansi16, true)?;
+    for x in 0 .. 4 {
+        colors.insert(rectmanager.get_character_style(rect_id, x, 1).unwrap().foreground_color);
+    }
+    assert!(colors.contains(&Some(Color::BLACK)) && colors.contains(&Some(Color::BRIGHTBLACK)));
+
+    let mut effects = EffectsHandler::new();
+    effects.foreground_color = Some(Color::Rgb(1, 2, 3));
+    effects.background_color = Some(Color::Fixed(208));
+    let output = rectmanager.build_ansi_string(vec![((0, 0), ('a', effects))]);
+    assert_eq!(output, "\x1B[1;1H\x1B[38;2;1;2;3;48;5;208ma");
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_graphics() -> Result<(), WreckedError> {
+    use image::{RgbImage, GraphicsProtocol};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 10, 5)?;
+    let sink = HeadlessSink::new();
+    rectmanager.set_output(sink.clone());
+    rectmanager.render()?;
+    sink.take();
+
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 2, 1)?;
+    rectmanager.set_position(rect_id, 3, 2)?;
+    rectmanager.set_string(rect_id, 0, 0, "ab")?;
+    let image = RgbImage::from_pixels(2, 1, vec![(255, 0, 0), (0, 0, 255)]).unwrap();
+    rectmanager.set_bitmap(rect_id, image)?;
+
+    // Without a protocol, the cells are drawn as usual
+    rectmanager.render()?;
+    assert!(String::from_utf8(sink.take()).unwrap().contains("ab"));
+
+    // Each column of 6 pixels is a single sixel, in one of two registers
+    rectmanager.set_graphics_protocol(Some(GraphicsProtocol::Sixel));
+    rectmanager.set_cell_pixel_size(1, 6);
+    rectmanager.render()?;
+    let sixel = "\x1BPq\"1;1;2;6#0;2;100;0;0#1;2;0;0;100#0~$#1?~\x1B\\";
+    assert_eq!(String::from_utf8(sink.take()).unwrap(), format!("\x1B[?25l\x1B[3;4H{}\x1B[1;1H", sixel));
+
+    // Cells under the bitmap are opaque to the diff, and nothing is re-sent unless the bitmap moves
+    rectmanager.set_string(rect_id, 0, 0, "cd")?;
+    rectmanager.render()?;
+    assert!(sink.take().is_empty());
+    rectmanager.set_position(rect_id, 5, 2)?;
+    rectmanager.render()?;
+    let output = String::from_utf8(sink.take()).unwrap();
+    assert!(output.contains(&format!("\x1B[3;6H{}", sixel)));
+    assert!(!output.contains("cd"));
+
+    let id = rect_id + 1;
+    rectmanager.set_graphics_protocol(Some(GraphicsProtocol::Kitty));
+    rectmanager.render()?;
+    let output = String::from_utf8(sink.take()).unwrap();
+    assert!(output.contains(&format!("\x1B[3;6H\x1B_Ga=T,f=24,s=2,v=1,c=2,r=1,i={},C=1,q=2,m=0;/wAAAAD/\x1B\\", id)));
+
+    // Large images are sent in chunks
+    rectmanager.set_bitmap(rect_id, RgbImage::new(40, 40))?;
+    rectmanager.render()?;
+    let output = String::from_utf8(sink.take()).unwrap();
+    assert!(output.starts_with(&format!("\x1B[?25l\x1B_Ga=d,d=I,i={},q=2\x1B\\", id)));
+    assert_eq!(output.matches("\x1B_G").count(), 3);
+    assert!(output.contains(",m=1;") && output.contains("\x1B_Gm=0;"));
+
+    // Removing the bitmap deletes the image and draws the cells under it
+    rectmanager.unset_bitmap(rect_id)?;
+    rectmanager.render()?;
+    let output = String::from_utf8(sink.take()).unwrap();
+    assert!(output.contains("cd"));
+    assert!(output.contains(&format!("\x1B_Ga=d,d=I,i={},q=2\x1B\\", id)));
+
+    // Anything drawn over a bitmap, like a dialog, replaces it with the cells until it's gone
+    let background = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(background, 10, 5)?;
+    rectmanager.set_bitmap(background, RgbImage::new(10, 5))?;
+    rectmanager.render()?;
+    assert!(String::from_utf8(sink.take()).unwrap().contains(&format!("i={},", background + 1)));
+
+    let popup = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(popup, 4, 1)?;
+    rectmanager.set_position(popup, 2, 2)?;
+    rectmanager.set_string(popup, 0, 0, "OKAY")?;
+    rectmanager.render()?;
+    let output = String::from_utf8(sink.take()).unwrap();
+    assert!(output.contains("OKAY"));
+    assert!(output.contains(&format!("\x1B_Ga=d,d=I,i={},q=2\x1B\\", background + 1)));
+
+    rectmanager.delete_rect(popup)?;
+    rectmanager.render()?;
+    assert!(String::from_utf8(sink.take()).unwrap().contains(&format!("\x1B_Ga=T,f=24,s=10,v=5,c=10,r=5,i={},", background + 1)));
+
+    // Setting up and restoring the console goes to the terminal, not the output
+    rectmanager.enable_mouse()?;
+    rectmanager.disable_mouse()?;
+    assert!(sink.take().is_empty());
+    rectmanager.kill()?;
+    assert!(!String::from_utf8(sink.take()).unwrap().contains("\x1B[2A"));
+
+    Ok(())
+}
+
+#[test]
+fn test_figlet() -> Result<(), WreckedError> {
+    use figlet::{FigFont, FigLayout};
+    use widgets::Alignment;
+
+    // Smushes equal characters (1) and by hierarchy (4), with a code-tagged character at the end
+    let build_font = |full_layout: usize| {
+        let mut flf = format!("flf2a$ 2 2 4 0 1 0 {} 1\nA test font\n", full_layout);
+        for code in (32 .. 127).chain([196, 214, 220, 228, 246, 252, 223]) {
+            let character = char::from_u32(code).unwrap();
+            let end = if character == '@' { '#' } else { '@' };
+            if character == ' ' {
+                flf += "$$@\n$$@@\n";
+            } else {
+                flf += &format!("{0}{0}{1}\n|/{1}{1}\n", character, end);
+            }
+        }
+        flf += "0x263A smiley\n:)@\n(:@@\n";
+        FigFont::from_bytes(flf.as_bytes())
+    };
+
+    let mut font = build_font(128 | 1 | 4)?;
+    assert_eq!(font.get_layout(), FigLayout::Smushing);
+    assert_eq!(font.render("aa"), vec!["aaa", "|//"]);
+    assert_eq!(font.render("ab"), vec!["aabb", "|/|/"]);
+    assert_eq!(font.render(" "), vec!["  ", "  "]);
+    assert_eq!(font.render("☺"), vec![":)", "(:"]);
+    font.set_layout(FigLayout::Kerning);
+    assert_eq!(font.render("aa"), vec!["aaaa", "|/|/"]);
+
+    // Without rules, the later character wins
+    let universal = build_font(128)?;
+    assert_eq!(universal.render("ab"), vec!["abb", "||/"]);
+
+    assert_eq!(FigFont::small().render("a\nb").len(), 6);
+    assert!(matches!(FigFont::from_bytes(b"hello"), Err(WreckedError::BadFont(_))));
+    assert!(matches!(FigFont::from_bytes(b"flf2a$ 999999999999 1 10 0 0"), Err(WreckedError::BadFont(_))));
+    assert!(FigFont::from_bytes(b"flf2a$ 1 1 10 0 999999999999").is_ok());
+    assert!(matches!(FigFont::from_file("/nonexistent.flf"), Err(WreckedError::BadFont(_))));
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 10, 3)?;
+    let small = FigFont::small();
+
+    rectmanager.set_banner_aligned(rect_id, 0, "1", &small, Alignment::Right)?;
+    assert_eq!(rectmanager.get_character(rect_id, 6, 0)?, '▄');
+    assert_eq!(rectmanager.get_character(rect_id, 7, 0)?, '█');
+    rectmanager.clear_characters(rect_id)?;
+    rectmanager.set_banner_aligned(rect_id, 0, "1", &small, Alignment::Center)?;
+    assert_eq!(rectmanager.get_character(rect_id, 3, 0)?, '▄');
+
+    // Anything outside the rect is cut off
+    rectmanager.set_banner(rect_id, 8, 1, "11", &small)?;
+    assert_eq!(rectmanager.get_character(rect_id, 9, 1)?, '█');
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_set_text() -> Result<(), WreckedError> {
+    use text::{TextOptions, TextAlignment, Overflow};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 10, 3)?;
+    let get_row = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 10).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect::<String>().trim_end().to_string()
+    };
+    let with = |alignment: TextAlignment, overflow: Overflow| TextOptions { alignment, overflow, ..TextOptions::default() };
+
+    // Words are wrapped at whitespace, and words too long for a line are split
+    rectmanager.set_text(rect_id, "The quick brown fox jumps", TextOptions::default())?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1), get_row(&rectmanager, 2)), ("The quick".to_string(), "brown fox".to_string(), "jumps".to_string()));
+    rectmanager.set_text(rect_id, "abcdefghijkl\n\tx", TextOptions::default())?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1), get_row(&rectmanager, 2)), ("abcdefghij".to_string(), "kl".to_string(), "    x".to_string()));
+
+    rectmanager.set_text(rect_id, "aa bb cc dd", with(TextAlignment::Justify, Overflow::Error))?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1)), ("aa  bb  cc".to_string(), "dd".to_string()));
+    rectmanager.set_text(rect_id, "ab\nabc", with(TextAlignment::Center, Overflow::Error))?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1)), ("    ab".to_string(), "   abc".to_string()));
+    rectmanager.set_text(rect_id, "ab", with(TextAlignment::Right, Overflow::Error))?;
+    assert_eq!(get_row(&rectmanager, 0), "        ab");
+
+    // Overflow policies
+    let text = "one two three four five six seven";
+    rectmanager.set_text(rect_id, text, TextOptions::default())?;
+    assert_eq!(get_row(&rectmanager, 2), "five six…");
+    rectmanager.set_text(rect_id, text, with(TextAlignment::Left, Overflow::Clip))?;
+    assert_eq!(get_row(&rectmanager, 2), "five six");
+    rectmanager.set_text(rect_id, "abcdefghij klmnopqrstu vw", TextOptions::default())?;
+    assert_eq!(get_row(&rectmanager, 2), "u vw");
+    assert_eq!(
+        rectmanager.set_text(rect_id, text, with(TextAlignment::Left, Overflow::Error)),
+        Err(WreckedError::StringTooLong(rect_id, (0, 0), text.to_string()))
+    );
+    assert_eq!(get_row(&rectmanager, 2), "u vw");
+
+    let no_wrap = TextOptions { wrap: false, ..TextOptions::default() };
+    rectmanager.set_text(rect_id, "abcdefghijklmnop", no_wrap)?;
+    assert_eq!(get_row(&rectmanager, 0), "abcdefghi…");
+
+    rectmanager.set_text(rect_id, text, with(TextAlignment::Left, Overflow::Grow))?;
+    assert_eq!(rectmanager.get_rect_size(rect_id), Some((10, 4)));
+    assert_eq!(get_row(&rectmanager, 3), "seven");
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_bidi() -> Result<(), WreckedError> {
+    use bidi::{reorder, TextDirection};
+    use text::{TextOptions, TextAlignment};
+
+    // Numbers keep their separators and terminators, and Arabic numbers stay apart
+    assert_eq!(reorder("אבג 1,234.5% def", TextDirection::RightToLeft), "def 1,234.5% גבא");
+    assert_eq!(reorder("سلام 1-2", TextDirection::Auto), "2-1 مالس");
+
+    // Brackets follow what they enclose, or the text around them, and are mirrored in right-to-left runs
+    assert_eq!(reorder("abc (אבג)", TextDirection::LeftToRight), "abc (גבא)");
+    assert_eq!(reorder("אבג (דהו) abc", TextDirection::LeftToRight), "(והד) גבא abc");
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 10, 3)?;
+    let get_row = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 10).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect::<String>().trim_end().to_string()
+    };
+
+    rectmanager.set_bidi_string(rect_id, 1, 0, "abc אבג")?;
+    assert_eq!(get_row(&rectmanager, 0), " abc גבא");
+    rectmanager.set_text_direction(rect_id, TextDirection::RightToLeft)?;
+    rectmanager.set_bidi_string(rect_id, 1, 1, "abc אבג")?;
+    assert_eq!(get_row(&rectmanager, 1), "  גבא abc");
+    assert_eq!(
+        rectmanager.set_bidi_string(rect_id, 4, 2, "abc אבג"),
+        Err(WreckedError::StringTooLong(rect_id, (4, 2), "abc אבג".to_string()))
+    );
+
+    // Right-to-left paragraphs start at the right edge
+    rectmanager.set_text(rect_id, "אא בב גג דד", TextOptions { alignment: TextAlignment::Justify, ..TextOptions::default() })?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1)), ("גג  בב  אא".to_string(), "        דד".to_string()));
+
+    rectmanager.set_text_direction(rect_id, TextDirection::Auto)?;
+    rectmanager.set_text(rect_id, "abc\nשלום", TextOptions::default())?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 1)), ("abc".to_string(), "      םולש".to_string()));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_set_ansi_string() -> Result<(), WreckedError> {
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let rect_id = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(rect_id, 12, 4)?;
+    let get_row = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 12).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect::<String>().trim_end().to_string()
+    };
+    let get_fg = |rectmanager: &RectManager, x: isize, y: isize| rectmanager.get_character_style(rect_id, x, y).and_then(|style| style.foreground_color);
+
+    // Colors in each form, and attributes turned on and off
+    let text = "\x1B[32ma\x1B[91mb\x1B[38;5;42mc\x1B[38;2;1;2;3md\x1B[38:2::4:5:6me\x1B[39;44mf\x1B[1;4;7mg\x1B[22;24mh\x1B[mi";
+    rectmanager.set_ansi_string(rect_id, 0, 0, text)?;
+    assert_eq!(get_row(&rectmanager, 0), "abcdefghi");
+    assert_eq!(
+        (0 .. 5).map(|x| get_fg(&rectmanager, x, 0)).collect::<Vec<Option<Color>>>(),
+        vec![Some(Color::GREEN), Some(Color::BRIGHTRED), Some(Color::Fixed(42)), Some(Color::Rgb(1, 2, 3)), Some(Color::Rgb(4, 5, 6))]
+    );
+    assert_eq!(rectmanager.get_character_style(rect_id, 5, 0), Some(Style { background_color: Some(Color::BLUE), ..Style::default() }));
+    assert_eq!(
+        rectmanager.get_character_style(rect_id, 6, 0),
+        Some(Style { bold: true, underline: true, invert: true, background_color: Some(Color::BLUE), ..Style::default() })
+    );
+    assert_eq!(rectmanager.get_character_style(rect_id, 7, 0), Some(Style { invert: true, background_color: Some(Color::BLUE), ..Style::default() }));
+    assert_eq!(rectmanager.get_character_style(rect_id, 8, 0), None);
+
+    // Line breaks go back to the start column, tabs are filled in, and anything else is dropped
+    rectmanager.clear_characters(rect_id)?;
+    rectmanager.set_ansi_string(rect_id, 1, 1, "\x1B]0;title\x07ab\x1B[2Jc\rX\n\x1B(Bd\te\n0123456789abcdef")?;
+    assert_eq!((get_row(&rectmanager, 1), get_row(&rectmanager, 2), get_row(&rectmanager, 3)), (" Xbc".to_string(), " d       e".to_string(), " 0123456789a".to_string()));
+
+    // Reading back what's rendered gives the same styles
+    let mut effects = EffectsHandler::new();
+    effects.italics = true;
+    effects.foreground_color = Some(Color::Rgb(10, 20, 30));
+    effects.background_color = Some(Color::Fixed(200));
+    let output = rectmanager.build_ansi_string(vec![((0, 0), ('x', effects)), ((1, 0), ('y', EffectsHandler::new()))]);
+    rectmanager.set_ansi_string(rect_id, 0, 0, &output)?;
+    assert_eq!(get_row(&rectmanager, 0), "xy");
+    assert_eq!(
+        rectmanager.get_character_style(rect_id, 0, 0),
+        Some(Style { italics: true, foreground_color: Some(Color::Rgb(10, 20, 30)), background_color: Some(Color::Fixed(200)), ..Style::default() })
+    );
+    assert_eq!(rectmanager.get_character_style(rect_id, 1, 0), None);
+
+    rectmanager.kill()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_terminal() -> Result<(), WreckedError> {
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+    use widgets::Terminal;
+    use input::{InputEvent, Key};
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 20, 10)?;
+    let mut terminal = Terminal::new(&mut rectmanager, ROOT)?;
+    terminal.resize(&mut rectmanager, 10, 4)?;
+    let rect_id = terminal.get_rect_id();
+    let get_row = |rectmanager: &RectManager, y: isize| -> String {
+        (0 .. 12).map(|x| rectmanager.get_character(rect_id, x, y).unwrap_or(' ')).collect::<String>().trim_end().to_string()
+    };
+
+    // Cursor movement, erasing and styles
+    terminal.process_output(&mut rectmanager, b"abcdef\x1B[1;3H\x1B[KX\x1B[3;2H\x1B[1;34mY")?;
+    assert_eq!((get_row(&rectmanager, 0), get_row(&rectmanager, 2)), ("abX".to_string(), " Y".to_string()));
+    assert_eq!(rectmanager.get_character_style(rect_id, 1, 2), Some(Style { bold: true, foreground_color: Some(Color::BLUE), ..Style::default() }));
+
+    // Lines wrap at the edge, and scroll within the scroll region
+    terminal.process_output(&mut rectmanager, b"\x1B[2J\x1B[H\x1B[0m0123456789ab\x1B[2;3r\x1B[3;1H\nc")?;
+    assert_eq!(
+        (0 .. 4).map(|y| get_row(&rectmanager, y)).collect::<Vec<String>>(),
+        vec!["0123456789", "", "c", ""]
+    );
+
+    // The alternate screen keeps the main one to return to
+    terminal.process_output(&mut rectmanager, b"\x1B[r\x1B[?1049h\x1B[HALT")?;
+    assert!(terminal.is_alternate_screen());
+    assert_eq!(get_row(&rectmanager, 0), "ALT");
+    terminal.process_output(&mut rectmanager, b"\x1B[?1049l")?;
+    assert_eq!(get_row(&rectmanager, 0), "0123456789");
+
+    // A command's output is read from its pseudo-terminal, which is resized with the rect
+    let mut command = Command::new("sh");
+    command.args(["-c", "printf '\\033[2J\\033[H\\033[32mready\\033[m'; read line; stty size"]);
+    terminal.spawn(&mut rectmanager, command)?;
+    let wait_for = |terminal: &mut Terminal, rectmanager: &mut RectManager, y: isize, expected: &str| -> Result<(), WreckedError> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while get_row(rectmanager, y) != expected && Instant::now() < deadline {
+            if !terminal.update(rectmanager)? {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        assert_eq!(get_row(rectmanager, y), expected);
+        Ok(())
+    };
+    wait_for(&mut terminal, &mut rectmanager, 0, "ready")?;
+    assert_eq!(rectmanager.get_character_style(rect_id, 0, 0).and_then(|style| style.foreground_color), Some(Color::GREEN));
+
+    terminal.resize(&mut rectmanager, 12, 5)?;
+    assert!(terminal.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter))?);
+    wait_for(&mut terminal, &mut rectmanager, 1, "5 12")?;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while terminal.is_running() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(terminal.get_exit_code(), Some(0));
+
+    rectmanager.kill()
+}
+
+#[test]
+fn test_virtual_screen() -> Result<(), WreckedError> {
+    use vt::VirtualScreen;
+
+    let mut rectmanager = RectManager::new();
+    rectmanager.resize(ROOT, 12, 5)?;
+    let sink = HeadlessSink::new();
+    rectmanager.set_output(sink.clone());
+    let mut screen = VirtualScreen::new(12, 5);
+
+    // Applying each frame's output to the screen should give what's composited from the rects
+    let check_frame = |rectmanager: &mut RectManager, screen: &mut VirtualScreen| -> Result<(), WreckedError> {
+        rectmanager.render()?;
+        screen.feed(&sink.take());
+        for ((x, y), (character, effects)) in rectmanager.get_cached_draw_map(ROOT) {
+            assert_eq!(screen.get_character(x as usize, y as usize), Some(character), "at {:?}", (x, y));
+            assert_eq!(screen.get_style(x as usize, y as usize), Some(Style::from(effects)), "at {:?}", (x, y));
+        }
+        Ok(())
+    };
+
+    rectmanager.set_string(ROOT, 0, 0, "background")?;
+    let panel = rectmanager.new_rect(ROOT)?;
+    rectmanager.resize(panel, 6, 3)?;
+    rectmanager.set_position(panel, 2, 1)?;
+    rectmanager.set_bg_color(panel, Color::Fixed(17))?;
+    rectmanager.set_string(panel, 0, 0, "strike")?;
+    for x in 0 .. 6 {
+        rectmanager.set_character_style(panel, x, 0, Style { strike: true, foreground_color: Some(Color::Rgb(200, 0, 0)), ..Style::default() })?;
+    }
+    // The bottom row is drawn last, which mustn't scroll the screen
+    rectmanager.set_string(ROOT, 0, 4, "bottom")?;
+    check_frame(&mut rectmanager, &mut screen)?;
+    assert!(screen.get_style(2, 1).unwrap().strike);
+
+    rectmanager.set_position(panel, 5, 2)?;
+    rectmanager.set_underline_flag(panel)?;
+    rectmanager.unset_character_style(panel, 0, 0)?;
+    check_frame(&mut rectmanager, &mut screen)?;
+
+    rectmanager.delete_rect(panel)?;
+    rectmanager.set_character(ROOT, 11, 4, '!')?;
+    check_frame(&mut rectmanager, &mut screen)?;
+
+    rectmanager.set_cursor(ROOT, 3, 2)?;
+    rectmanager.enable_mouse()?;
+    rectmanager.render()?;
+    screen.feed(&sink.take());
+    assert_eq!(screen.get_cursor(), (3, 2));
+    assert!(screen.is_cursor_visible());
+    // Mouse reporting is console setup, so it goes to the terminal rather than the output
+    assert!(!screen.is_private_mode_set(1000) && !screen.is_private_mode_set(1006));
+
+    rectmanager.kill()
+}
@@ -0,0 +1,203 @@
+//! Laying out paragraphs of text in rects, used by RectManager::set_text().
+use crate::bidi;
+
+/// How each line of text is placed across the width of a rect.
+/// In right-to-left paragraphs, Left and Right are the start and end of the line, so they're swapped.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Spread the words of each wrapped line across the full width. The last line of a paragraph is left aligned.
+    Justify
+}
+
+/// What happens when text doesn't fit in a rect.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Overflow {
+    /// Cut the text short, ending with '…'
+    #[default]
+    Ellipsis,
+    /// Cut the text short
+    Clip,
+    /// Make the rect big enough for the text
+    Grow,
+    /// Leave the rect alone and return WreckedError::StringTooLong
+    Error
+}
+
+/// Options for RectManager::set_text().
+/// # Example
+/// ```
+/// use wrecked::text::{TextOptions, TextAlignment, Overflow};
+///
+/// let options = TextOptions {
+///     alignment: TextAlignment::Justify,
+///     overflow: Overflow::Grow,
+///     ..TextOptions::default()
+/// };
+/// assert_eq!(options.tab_width, 4);
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TextOptions {
+    pub alignment: TextAlignment,
+    pub overflow: Overflow,
+    /// Break lines at whitespace to fit the rect. Long lines are left to overflow when false.
+    pub wrap: bool,
+    /// Tabs are expanded with spaces up to the next multiple of this
+    pub tab_width: usize
+}
+
+impl Default for TextOptions {
+    fn default() -> TextOptions {
+        TextOptions {
+            alignment: TextAlignment::Left,
+            overflow: Overflow::Ellipsis,
+            wrap: true,
+            tab_width: 4
+        }
+    }
+}
+
+/// A line of laid out text.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) struct TextLine {
+    pub(crate) text: Vec<char>,
+    /// True if the line is the end of a paragraph, rather than wrapped
+    pub(crate) last: bool
+}
+
+// Replace tabs with spaces up to the next tab stop
+fn expand_tabs(paragraph: &str, tab_width: usize) -> Vec<char> {
+    let mut output = Vec::new();
+    for character in paragraph.chars() {
+        if character == '\t' {
+            let tab_width = tab_width.max(1);
+            let spaces = tab_width - output.len() % tab_width;
+            output.extend(std::iter::repeat_n(' ', spaces));
+        } else {
+            output.push(character);
+        }
+    }
+    output
+}
+
+/// Break text into lines no wider than `width` (if wrapping), at `\n` and between words.
+/// Words longer than the width are broken up.
+pub(crate) fn wrap_text(text: &str, width: usize, options: &TextOptions) -> Vec<TextLine> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let paragraph = expand_tabs(paragraph.trim_end_matches('\r'), options.tab_width);
+        if !options.wrap {
+            lines.push(TextLine { text: paragraph, last: true });
+            continue;
+        }
+
+        // Alternating runs of spaces and words, starting with a (possibly empty) run of spaces
+        let mut runs: Vec<(Vec<char>, Vec<char>)> = Vec::new();
+        let mut index = 0;
+        while index < paragraph.len() {
+            let spaces_end = paragraph[index ..].iter().position(|c| *c != ' ').map_or(paragraph.len(), |i| index + i);
+            let word_end = paragraph[spaces_end ..].iter().position(|c| *c == ' ').map_or(paragraph.len(), |i| spaces_end + i);
+            runs.push((paragraph[index .. spaces_end].to_vec(), paragraph[spaces_end .. word_end].to_vec()));
+            index = word_end;
+        }
+
+        let mut line: Vec<char> = Vec::new();
+        for (spaces, word) in runs {
+            // Spaces at a break are dropped, except at the start of the paragraph
+            if !line.is_empty() && line.len() + spaces.len() + word.len() > width {
+                lines.push(TextLine { text: std::mem::take(&mut line), last: false });
+            } else {
+                line.extend(spaces);
+            }
+
+            let mut word = &word[..];
+            while line.len() + word.len() > width {
+                // A word that doesn't fit on a line of its own is split
+                let room = width.saturating_sub(line.len());
+                if room > 0 && line.iter().all(|c| *c == ' ') {
+                    line.extend(&word[.. room]);
+                    word = &word[room ..];
+                }
+                lines.push(TextLine { text: std::mem::take(&mut line), last: false });
+            }
+            line.extend(word);
+        }
+        lines.push(TextLine { text: line, last: true });
+    }
+
+    lines
+}
+
+/// Place a line within `width` columns, returning the column it starts at and its characters in visual order.
+pub(crate) fn align_line(line: &TextLine, width: usize, alignment: TextAlignment, rtl: bool) -> (usize, Vec<char>) {
+    let (x, text) = align_logical(line, width, alignment);
+    let text = bidi::reorder_chars(&text, rtl);
+    if rtl {
+        // Mirror the line's position, so it starts from the right edge
+        (width.saturating_sub(text.len()).saturating_sub(x), text)
+    } else {
+        (x, text)
+    }
+}
+
+fn align_logical(line: &TextLine, width: usize, alignment: TextAlignment) -> (usize, Vec<char>) {
+    let mut text = line.text.clone();
+    while text.last() == Some(&' ') {
+        text.pop();
+    }
+    let free = width.saturating_sub(text.len());
+
+    match alignment {
+        TextAlignment::Left => (0, text),
+        TextAlignment::Center => (free / 2, text),
+        TextAlignment::Right => (free, text),
+        TextAlignment::Justify => {
+            if line.last {
+                return (0, text);
+            }
+
+            // Keep any indentation, and spread the words over the rest
+            let indent = text.iter().take_while(|c| **c == ' ').count();
+            let words: Vec<&[char]> = text[indent ..].split(|c| *c == ' ').filter(|word| !word.is_empty()).collect();
+            if words.len() < 2 {
+                return (0, text);
+            }
+
+            let gaps = words.len() - 1;
+            let spaces = width.saturating_sub(indent + words.iter().map(|word| word.len()).sum::<usize>());
+            let mut output = vec![' '; indent];
+            for (i, word) in words.iter().enumerate() {
+                if i > 0 {
+                    // The leftmost gaps take the remainder
+                    let gap = spaces / gaps + if i <= spaces % gaps { 1 } else { 0 };
+                    output.extend(std::iter::repeat_n(' ', gap.max(1)));
+                }
+                output.extend(word.iter());
+            }
+            (0, output)
+        }
+    }
+}
+
+/// End a line with '…', cutting it short if that doesn't fit in `width` characters.
+pub(crate) fn ellipsize(text: &[char], width: usize) -> Vec<char> {
+    let mut output = text.to_vec();
+    while output.last() == Some(&' ') {
+        output.pop();
+    }
+    if output.len() >= width {
+        output.truncate(width.saturating_sub(1));
+        while output.last() == Some(&' ') {
+            output.pop();
+        }
+    }
+    if width > 0 {
+        output.push('…');
+    }
+    output
+}
@@ -0,0 +1,568 @@
+//! A VT100/xterm state machine, keeping a grid of styled cells up to date with the output sent to a terminal.
+use std::collections::HashSet;
+use std::{mem, str};
+use crate::Style;
+use crate::ansi::{self, AnsiParser, AnsiToken};
+
+const TAB_WIDTH: usize = 8;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) struct Cell {
+    pub(crate) character: char,
+    pub(crate) style: Style
+}
+
+impl Cell {
+    // An erased cell keeps the background color being drawn with
+    fn blank(style: Style) -> Cell {
+        Cell {
+            character: ' ',
+            style: Style {
+                background_color: style.background_color,
+                ..Style::default()
+            }
+        }
+    }
+}
+
+pub(crate) struct Emulator {
+    width: usize,
+    height: usize,
+    parser: AnsiParser,
+    grid: Vec<Vec<Cell>>,
+    // The main screen, while the alternate screen is shown
+    main_grid: Option<Vec<Vec<Cell>>>,
+
+    cursor: (usize, usize),
+    // Set after writing to the last column; the next character goes on a new line
+    wrap_pending: bool,
+    saved_cursor: Option<((usize, usize), Style)>,
+    style: Style,
+    // First and last rows (inclusive) that scroll
+    scroll_region: (usize, usize),
+
+    autowrap: bool,
+    cursor_visible: bool,
+    application_cursor: bool,
+    // Every private mode (ESC [ ? n h) that's set, including ones with no effect on the grid
+    private_modes: HashSet<u32>,
+
+    // Replies to queries, to be sent back to whatever's writing
+    responses: Vec<u8>
+}
+
+impl Emulator {
+    pub(crate) fn new(width: usize, height: usize) -> Emulator {
+        let width = width.max(1);
+        let height = height.max(1);
+        Emulator {
+            width,
+            height,
+            parser: AnsiParser::new(),
+            grid: vec![vec![Cell::blank(Style::default()); width]; height],
+            main_grid: None,
+            cursor: (0, 0),
+            wrap_pending: false,
+            saved_cursor: None,
+            style: Style::default(),
+            scroll_region: (0, height - 1),
+            autowrap: true,
+            cursor_visible: true,
+            application_cursor: false,
+            private_modes: HashSet::new(),
+            responses: Vec::new()
+        }
+    }
+
+    pub(crate) fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub(crate) fn get_cell(&self, x: usize, y: usize) -> Option<Cell> {
+        self.grid.get(y).and_then(|row| row.get(x)).copied()
+    }
+
+    pub(crate) fn get_cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub(crate) fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub(crate) fn is_alternate_screen(&self) -> bool {
+        self.main_grid.is_some()
+    }
+
+    /// Whether arrow keys should be sent as ESC O A rather than ESC [ A
+    pub(crate) fn is_application_cursor(&self) -> bool {
+        self.application_cursor
+    }
+
+    pub(crate) fn is_private_mode_set(&self, mode: u32) -> bool {
+        self.private_modes.contains(&mode)
+    }
+
+    pub(crate) fn take_responses(&mut self) -> Vec<u8> {
+        mem::take(&mut self.responses)
+    }
+
+    /// Change the size of the screen. Rows are taken off the top if the cursor would otherwise be cut off.
+    pub(crate) fn resize(&mut self, width: usize, height: usize) {
+        let width = width.max(1);
+        let height = height.max(1);
+        let lost_rows = (self.cursor.1 + 1).saturating_sub(height);
+
+        let blank = Cell::blank(Style::default());
+        for grid in std::iter::once(&mut self.grid).chain(self.main_grid.as_mut()) {
+            grid.drain(.. lost_rows.min(grid.len()));
+            grid.resize(height, vec![blank; width]);
+            for row in grid.iter_mut() {
+                row.resize(width, blank);
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.cursor = ((self.cursor.0).min(width - 1), self.cursor.1 - lost_rows);
+        self.wrap_pending = false;
+        self.scroll_region = (0, height - 1);
+    }
+
+    pub(crate) fn feed(&mut self, text: &str) {
+        for token in self.parser.feed(text) {
+            match token {
+                AnsiToken::Character(character) => {
+                    self.put_character(character);
+                }
+                AnsiToken::Control(character) => {
+                    self.control(character);
+                }
+                AnsiToken::Escape(character) => {
+                    self.escape(character);
+                }
+                AnsiToken::Csi { private, params, final_char } => {
+                    self.csi(private, &params, final_char);
+                }
+            }
+        }
+    }
+
+    fn put_character(&mut self, character: char) {
+        if self.wrap_pending {
+            self.cursor.0 = 0;
+            self.line_feed();
+        }
+        self.wrap_pending = false;
+
+        let (x, y) = self.cursor;
+        self.grid[y][x] = Cell { character, style: self.style };
+        if x + 1 < self.width {
+            self.cursor.0 += 1;
+        } else if self.autowrap {
+            self.wrap_pending = true;
+        }
+    }
+
+    fn control(&mut self, character: char) {
+        match character {
+            '\r' => {
+                self.cursor.0 = 0;
+                self.wrap_pending = false;
+            }
+            '\n' | '\x0B' | '\x0C' => {
+                self.line_feed();
+            }
+            '\x08' => {
+                self.cursor.0 = self.cursor.0.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            '\t' => {
+                self.cursor.0 = ((self.cursor.0 / TAB_WIDTH + 1) * TAB_WIDTH).min(self.width - 1);
+            }
+            _ => {}
+        }
+    }
+
+    fn escape(&mut self, character: char) {
+        match character {
+            '7' => {
+                self.save_cursor();
+            }
+            '8' => {
+                self.restore_cursor();
+            }
+            'D' => {
+                self.line_feed();
+            }
+            'E' => {
+                self.cursor.0 = 0;
+                self.line_feed();
+            }
+            'M' => {
+                self.reverse_index();
+            }
+            'c' => {
+                *self = Emulator::new(self.width, self.height);
+            }
+            _ => {}
+        }
+    }
+
+    fn csi(&mut self, private: Option<char>, params: &[Vec<u32>], final_char: char) {
+        // The first part of a parameter, with 0 (or nothing) read as the default
+        let get = |i: usize, default: u32| -> usize {
+            match params.get(i).map(|param| param[0]) {
+                Some(0) | None => default as usize,
+                Some(value) => value as usize
+            }
+        };
+
+        if private == Some('?') {
+            if final_char == 'h' || final_char == 'l' {
+                for param in params {
+                    self.set_private_mode(param[0], final_char == 'h');
+                }
+            }
+            return;
+        }
+        if private.is_some() {
+            return;
+        }
+
+        let (x, y) = self.cursor;
+        if final_char != 'm' {
+            self.wrap_pending = false;
+        }
+        match final_char {
+            'A' => {
+                let top = if y >= self.scroll_region.0 { self.scroll_region.0 } else { 0 };
+                self.cursor.1 = y.saturating_sub(get(0, 1)).max(top);
+            }
+            'B' | 'e' => {
+                let bottom = if y <= self.scroll_region.1 { self.scroll_region.1 } else { self.height - 1 };
+                self.cursor.1 = (y + get(0, 1)).min(bottom);
+            }
+            'C' | 'a' => {
+                self.cursor.0 = (x + get(0, 1)).min(self.width - 1);
+            }
+            'D' => {
+                self.cursor.0 = x.saturating_sub(get(0, 1));
+            }
+            'E' => {
+                self.cursor = (0, (y + get(0, 1)).min(self.height - 1));
+            }
+            'F' => {
+                self.cursor = (0, y.saturating_sub(get(0, 1)));
+            }
+            'G' | '`' => {
+                self.cursor.0 = (get(0, 1) - 1).min(self.width - 1);
+            }
+            'd' => {
+                self.cursor.1 = (get(0, 1) - 1).min(self.height - 1);
+            }
+            'H' | 'f' => {
+                self.cursor = ((get(1, 1) - 1).min(self.width - 1), (get(0, 1) - 1).min(self.height - 1));
+            }
+            'J' => {
+                match get(0, 0) {
+                    0 => {
+                        self.erase(x, y, self.width, y + 1);
+                        self.erase(0, y + 1, self.width, self.height);
+                    }
+                    1 => {
+                        self.erase(0, 0, self.width, y);
+                        self.erase(0, y, x + 1, y + 1);
+                    }
+                    2 => {
+                        self.erase(0, 0, self.width, self.height);
+                    }
+                    // 3 clears the scrollback, which isn't kept
+                    _ => {}
+                }
+            }
+            'K' => {
+                match get(0, 0) {
+                    0 => self.erase(x, y, self.width, y + 1),
+                    1 => self.erase(0, y, x + 1, y + 1),
+                    _ => self.erase(0, y, self.width, y + 1)
+                }
+            }
+            'X' => {
+                self.erase(x, y, (x + get(0, 1)).min(self.width), y + 1);
+            }
+            '@' => {
+                let blank = Cell::blank(self.style);
+                let row = &mut self.grid[y];
+                for _ in 0 .. get(0, 1).min(self.width - x) {
+                    row.insert(x, blank);
+                    row.pop();
+                }
+            }
+            'P' => {
+                let blank = Cell::blank(self.style);
+                let row = &mut self.grid[y];
+                for _ in 0 .. get(0, 1).min(self.width - x) {
+                    row.remove(x);
+                    row.push(blank);
+                }
+            }
+            'L' if (self.scroll_region.0 ..= self.scroll_region.1).contains(&y) => {
+                self.scroll_down(y, get(0, 1));
+                self.cursor.0 = 0;
+            }
+            'M' if (self.scroll_region.0 ..= self.scroll_region.1).contains(&y) => {
+                self.scroll_up(y, get(0, 1));
+                self.cursor.0 = 0;
+            }
+            'S' => {
+                self.scroll_up(self.scroll_region.0, get(0, 1));
+            }
+            'T' => {
+                self.scroll_down(self.scroll_region.0, get(0, 1));
+            }
+            'r' => {
+                let top = get(0, 1) - 1;
+                let bottom = get(1, self.height as u32).min(self.height) - 1;
+                if top < bottom {
+                    self.scroll_region = (top, bottom);
+                    self.cursor = (0, 0);
+                }
+            }
+            'm' => {
+                ansi::apply_sgr(&mut self.style, params);
+            }
+            's' => {
+                self.save_cursor();
+            }
+            'u' => {
+                self.restore_cursor();
+            }
+            'n' => {
+                match get(0, 0) {
+                    5 => self.responses.extend(b"\x1B[0n"),
+                    6 => self.responses.extend(format!("\x1B[{};{}R", y + 1, x + 1).bytes()),
+                    _ => {}
+                }
+            }
+            'c' => {
+                // A VT102
+                self.responses.extend(b"\x1B[?6c");
+            }
+            _ => {}
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u32, set: bool) {
+        if set {
+            self.private_modes.insert(mode);
+        } else {
+            self.private_modes.remove(&mode);
+        }
+
+        match mode {
+            1 => {
+                self.application_cursor = set;
+            }
+            7 => {
+                self.autowrap = set;
+                self.wrap_pending = false;
+            }
+            25 => {
+                self.cursor_visible = set;
+            }
+            47 | 1047 | 1049 => {
+                if mode == 1049 && set {
+                    self.save_cursor();
+                }
+                if set && self.main_grid.is_none() {
+                    let blank = vec![vec![Cell::blank(Style::default()); self.width]; self.height];
+                    self.main_grid = Some(mem::replace(&mut self.grid, blank));
+                } else if !set {
+                    if let Some(grid) = self.main_grid.take() {
+                        self.grid = grid;
+                    }
+                }
+                if mode == 1049 && !set {
+                    self.restore_cursor();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved_cursor = Some((self.cursor, self.style));
+    }
+
+    fn restore_cursor(&mut self) {
+        if let Some((cursor, style)) = self.saved_cursor {
+            self.cursor = ((cursor.0).min(self.width - 1), (cursor.1).min(self.height - 1));
+            self.style = style;
+        }
+        self.wrap_pending = false;
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.1 == self.scroll_region.1 {
+            self.scroll_up(self.scroll_region.0, 1);
+        } else if self.cursor.1 + 1 < self.height {
+            self.cursor.1 += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        if self.cursor.1 == self.scroll_region.0 {
+            self.scroll_down(self.scroll_region.0, 1);
+        } else {
+            self.cursor.1 = self.cursor.1.saturating_sub(1);
+        }
+    }
+
+    // Move the rows from `top` to the bottom of the scroll region up, adding blank rows at the bottom
+    fn scroll_up(&mut self, top: usize, count: usize) {
+        let bottom = self.scroll_region.1;
+        let blank = vec![Cell::blank(self.style); self.width];
+        for _ in 0 .. count.min(bottom + 1 - top) {
+            self.grid.remove(top);
+            self.grid.insert(bottom, blank.clone());
+        }
+    }
+
+    // Move the rows from `top` to the bottom of the scroll region down, adding blank rows at `top`
+    fn scroll_down(&mut self, top: usize, count: usize) {
+        let bottom = self.scroll_region.1;
+        let blank = vec![Cell::blank(self.style); self.width];
+        for _ in 0 .. count.min(bottom + 1 - top) {
+            self.grid.remove(bottom);
+            self.grid.insert(top, blank.clone());
+        }
+    }
+
+    // Blank the cells from (x0, y0) up to, but not including, (x1, y1)
+    fn erase(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        let blank = Cell::blank(self.style);
+        for row in self.grid[y0.min(self.height) .. y1.min(self.height)].iter_mut() {
+            for cell in row[x0.min(self.width) .. x1.min(self.width)].iter_mut() {
+                *cell = blank;
+            }
+        }
+    }
+}
+
+/// Decode a chunk of utf8 output, keeping the start of a character split across chunks in `pending`.
+/// Invalid bytes are replaced with U+FFFD.
+pub(crate) fn decode_utf8(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    let mut bytes: Vec<u8> = mem::take(pending).into_iter().chain(bytes.iter().copied()).collect();
+    let mut text = String::new();
+    loop {
+        match str::from_utf8(&bytes) {
+            Ok(valid) => {
+                text += valid;
+                return text;
+            }
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                text += str::from_utf8(valid).unwrap_or("");
+                match error.error_len() {
+                    Some(length) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        bytes = rest[length ..].to_vec();
+                    }
+                    None => {
+                        // Wait for the rest of the character
+                        *pending = rest.to_vec();
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A model of a terminal's screen, built up from the output sent to it.
+/// Feeding it what a RectManager writes to a HeadlessSink shows what a terminal would display.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT, HeadlessSink, Color};
+/// use wrecked::vt::VirtualScreen;
+/// let mut rectmanager = RectManager::new();
+/// rectmanager.resize(ROOT, 10, 2);
+/// let sink = HeadlessSink::new();
+/// rectmanager.set_output(sink.clone());
+///
+/// rectmanager.set_string(ROOT, 0, 1, "Hi");
+/// rectmanager.set_fg_color(ROOT, Color::RED);
+/// rectmanager.render();
+///
+/// let mut screen = VirtualScreen::new(10, 2);
+/// screen.feed(&sink.take());
+/// assert_eq!(screen.get_row(1), Some("Hi        ".to_string()));
+/// assert_eq!(screen.get_style(0, 1).unwrap().foreground_color, Some(Color::RED));
+/// assert!(!screen.is_cursor_visible());
+///
+/// rectmanager.kill();
+/// ```
+pub struct VirtualScreen {
+    emulator: Emulator,
+    pending: Vec<u8>
+}
+
+impl VirtualScreen {
+    pub fn new(width: usize, height: usize) -> VirtualScreen {
+        VirtualScreen {
+            emulator: Emulator::new(width, height),
+            pending: Vec::new()
+        }
+    }
+
+    /// Apply output to the screen. Escape sequences and characters may be split across calls.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let text = decode_utf8(&mut self.pending, bytes);
+        self.emulator.feed(&text);
+        self.emulator.take_responses();
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        self.emulator.get_size()
+    }
+
+    /// Change the size of the screen, as a terminal window would be resized.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.emulator.resize(width, height);
+    }
+
+    pub fn get_character(&self, x: usize, y: usize) -> Option<char> {
+        self.emulator.get_cell(x, y).map(|cell| cell.character)
+    }
+
+    pub fn get_style(&self, x: usize, y: usize) -> Option<Style> {
+        self.emulator.get_cell(x, y).map(|cell| cell.style)
+    }
+
+    /// Get the characters of a row, including trailing spaces.
+    pub fn get_row(&self, y: usize) -> Option<String> {
+        if y < self.emulator.get_size().1 {
+            Some((0 .. self.emulator.get_size().0).filter_map(|x| self.get_character(x, y)).collect())
+        } else {
+            None
+        }
+    }
+
+    pub fn get_cursor(&self) -> (usize, usize) {
+        self.emulator.get_cursor()
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        self.emulator.is_cursor_visible()
+    }
+
+    pub fn is_alternate_screen(&self) -> bool {
+        self.emulator.is_alternate_screen()
+    }
+
+    /// Check a private mode (ESC [ ? n h), eg 1000 for mouse reporting.
+    pub fn is_private_mode_set(&self, mode: u32) -> bool {
+        self.emulator.is_private_mode_set(mode)
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::BTreeSet;
+use crate::{RectManager, WreckedError, Style, Color};
+
+/// How pixels are packed into the characters of a Canvas.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum CanvasMode {
+    /// 2x4 pixels per cell, drawn with braille characters
+    #[default]
+    Braille,
+    /// 1x2 pixels per cell, drawn with half blocks. Coarser, but works with more fonts.
+    HalfBlock
+}
+
+impl CanvasMode {
+    fn get_cell_size(&self) -> (usize, usize) {
+        match self {
+            CanvasMode::Braille => (2, 4),
+            CanvasMode::HalfBlock => (1, 2)
+        }
+    }
+
+    // Bit of a pixel within its cell
+    fn get_bit(&self, x: usize, y: usize) -> u8 {
+        match self {
+            CanvasMode::Braille => {
+                match (x, y) {
+                    (0, 3) => 0x40,
+                    (1, 3) => 0x80,
+                    (0, y) => 1 << y,
+                    (_, y) => 1 << (y + 3)
+                }
+            }
+            CanvasMode::HalfBlock => {
+                1 << y
+            }
+        }
+    }
+
+    fn get_character(&self, bits: u8) -> char {
+        match self {
+            CanvasMode::Braille => {
+                char::from_u32(0x2800 + bits as u32).unwrap_or(' ')
+            }
+            CanvasMode::HalfBlock => {
+                match bits {
+                    1 => '▀',
+                    2 => '▄',
+                    3 => '█',
+                    _ => ' '
+                }
+            }
+        }
+    }
+}
+
+/// A surface of pixels smaller than a character, drawn into a rect.
+/// Shapes are drawn with the current color; each cell takes the color of the last pixel drawn in it.
+/// Nothing is shown until flush() is called, which only redraws the cells that changed.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT, Color};
+/// use wrecked::widgets::{Canvas, CanvasMode};
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut canvas = Canvas::new(&mut rectmanager, ROOT, 20, 5, CanvasMode::Braille).ok().unwrap();
+/// assert_eq!(canvas.get_pixel_size(), (40, 20));
+///
+/// canvas.set_color(Some(Color::GREEN));
+/// canvas.line(0, 19, 39, 0);
+/// canvas.circle(20, 10, 8);
+/// canvas.flush(&mut rectmanager);
+///
+/// assert_eq!(rectmanager.get_character(canvas.get_rect_id(), 0, 4).ok(), Some('⣀'));
+///
+/// rectmanager.kill();
+/// ```
+pub struct Canvas {
+    rect_id: usize,
+    mode: CanvasMode,
+    // Size in cells
+    width: usize,
+    height: usize,
+    // Bits of the pixels that are set in each cell, and the cell's color
+    cells: Vec<(u8, Option<Color>)>,
+    // Cells changed since the last flush
+    dirty: BTreeSet<usize>,
+    color: Option<Color>
+}
+
+impl Canvas {
+    /// Create a Canvas of `width` x `height` cells, with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, width: usize, height: usize, mode: CanvasMode) -> Result<Canvas, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let mut canvas = Canvas {
+            rect_id,
+            mode,
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            dirty: BTreeSet::new(),
+            color: None
+        };
+        canvas.resize(rectmanager, width, height)?;
+        Ok(canvas)
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Resize the canvas and its rect, in cells. The canvas is cleared.
+    pub fn resize(&mut self, rectmanager: &mut RectManager, width: usize, height: usize) -> Result<(), WreckedError> {
+        rectmanager.resize(self.rect_id, width, height)?;
+        self.width = width;
+        self.height = height;
+        self.cells = vec![(0, None); width * height];
+        self.dirty = (0 .. width * height).collect();
+        Ok(())
+    }
+
+    pub fn get_mode(&self) -> CanvasMode {
+        self.mode
+    }
+
+    /// Change how pixels are packed into characters. The canvas is cleared.
+    pub fn set_mode(&mut self, mode: CanvasMode) {
+        self.mode = mode;
+        self.clear();
+    }
+
+    /// Get the size of the canvas in pixels.
+    pub fn get_pixel_size(&self) -> (usize, usize) {
+        let (cell_width, cell_height) = self.mode.get_cell_size();
+        (self.width * cell_width, self.height * cell_height)
+    }
+
+    /// Set the color of pixels drawn from now on. None uses the rect's color.
+    pub fn set_color(&mut self, color: Option<Color>) {
+        self.color = color;
+    }
+
+    pub fn clear(&mut self) {
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            if cell.0 != 0 {
+                *cell = (0, None);
+                self.dirty.insert(index);
+            }
+        }
+    }
+
+    /// Check if a pixel is set. Pixels outside the canvas are never set.
+    pub fn get_pixel(&self, x: isize, y: isize) -> bool {
+        match self.locate(x, y) {
+            Some((index, bit)) => self.cells[index].0 & bit != 0,
+            None => false
+        }
+    }
+
+    /// Set a single pixel. Pixels outside the canvas are ignored.
+    pub fn set_pixel(&mut self, x: isize, y: isize) {
+        if let Some((index, bit)) = self.locate(x, y) {
+            self.cells[index] = (self.cells[index].0 | bit, self.color);
+            self.dirty.insert(index);
+        }
+    }
+
+    pub fn unset_pixel(&mut self, x: isize, y: isize) {
+        if let Some((index, bit)) = self.locate(x, y) {
+            self.cells[index].0 &= !bit;
+            self.dirty.insert(index);
+        }
+    }
+
+    /// Draw a line between two pixels (inclusive), using Bresenham's algorithm.
+    /// Only the part of the line on the canvas is walked, so the ends can be any distance off of it.
+    pub fn line(&mut self, from_x: isize, from_y: isize, to_x: isize, to_y: isize) {
+        let ((from_x, from_y), (to_x, to_y)) = match self.clip_line((from_x, from_y), (to_x, to_y)) {
+            Some(ends) => ends,
+            None => {
+                return;
+            }
+        };
+
+        let delta_x = (to_x - from_x).abs();
+        let delta_y = -(to_y - from_y).abs();
+        let step_x = if from_x < to_x { 1 } else { -1 };
+        let step_y = if from_y < to_y { 1 } else { -1 };
+
+        let (mut x, mut y) = (from_x, from_y);
+        let mut error = delta_x + delta_y;
+        loop {
+            self.set_pixel(x, y);
+            if x == to_x && y == to_y {
+                break;
+            }
+            let doubled = error * 2;
+            if doubled >= delta_y {
+                error += delta_y;
+                x += step_x;
+            }
+            if doubled <= delta_x {
+                error += delta_x;
+                y += step_y;
+            }
+        }
+    }
+
+    // Cut a line down to the part on the canvas, using the Liang-Barsky algorithm. None if none of it is.
+    fn clip_line(&self, from: (isize, isize), to: (isize, isize)) -> Option<((isize, isize), (isize, isize))> {
+        let (width, height) = self.get_pixel_size();
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (right, bottom) = ((width - 1) as f64, (height - 1) as f64);
+
+        // The line is from + t * delta, for t from 0 to 1
+        let (x, y) = (from.0 as f64, from.1 as f64);
+        let (delta_x, delta_y) = (to.0 as f64 - x, to.1 as f64 - y);
+        let (mut start, mut end) = (0.0_f64, 1.0_f64);
+        // For each side, how fast the line heads out past it and how far inside of it the line starts
+        for (outward, inside) in [(-delta_x, x), (delta_x, right - x), (-delta_y, y), (delta_y, bottom - y)].iter() {
+            if *outward == 0.0 {
+                if *inside < 0.0 {
+                    return None;
+                }
+            } else {
+                let t = inside / outward;
+                if *outward < 0.0 {
+                    start = start.max(t);
+                } else {
+                    end = end.min(t);
+                }
+            }
+        }
+        if start > end {
+            return None;
+        }
+
+        // Rounding can't be trusted to land on the canvas when the ends are far enough away
+        let get_point = |t: f64| (
+            (x + t * delta_x).round().clamp(0.0, right) as isize,
+            (y + t * delta_y).round().clamp(0.0, bottom) as isize
+        );
+        Some((get_point(start), get_point(end)))
+    }
+
+    /// Draw the outline of a rectangle with its top left corner at (x, y).
+    pub fn rect(&mut self, x: isize, y: isize, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (right, bottom) = (x + width as isize - 1, y + height as isize - 1);
+        self.line(x, y, right, y);
+        self.line(x, bottom, right, bottom);
+        self.line(x, y, x, bottom);
+        self.line(right, y, right, bottom);
+    }
+
+    /// Draw the outline of a circle, using the midpoint algorithm.
+    pub fn circle(&mut self, center_x: isize, center_y: isize, radius: usize) {
+        let radius = radius as isize;
+        let (mut x, mut y) = (radius, 0);
+        let mut error = 1 - radius;
+
+        while x >= y {
+            for (offset_x, offset_y) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                self.set_pixel(center_x + offset_x, center_y + offset_y);
+            }
+
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Flood fill the area of unset pixels around (x, y), bounded by set pixels and the edges of the canvas.
+    pub fn fill(&mut self, x: isize, y: isize) {
+        if self.locate(x, y).is_none() || self.get_pixel(x, y) {
+            return;
+        }
+
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            if self.locate(x, y).is_none() || self.get_pixel(x, y) {
+                continue;
+            }
+            self.set_pixel(x, y);
+            stack.push((x + 1, y));
+            stack.push((x - 1, y));
+            stack.push((x, y + 1));
+            stack.push((x, y - 1));
+        }
+    }
+
+    /// Draw the cells that changed since the last flush into the canvas's rect.
+    pub fn flush(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let dirty = std::mem::take(&mut self.dirty);
+        for index in dirty.into_iter() {
+            let (bits, color) = &self.cells[index];
+            let x = (index % self.width) as isize;
+            let y = (index / self.width) as isize;
+
+            if *bits == 0 {
+                rectmanager.unset_character(self.rect_id, x, y)?;
+                rectmanager.unset_character_style(self.rect_id, x, y)?;
+                continue;
+            }
+
+            rectmanager.set_character(self.rect_id, x, y, self.mode.get_character(*bits))?;
+            match color {
+                Some(color) => {
+                    let style = Style {
+                        foreground_color: Some(*color),
+                        ..Style::default()
+                    };
+                    rectmanager.set_character_style(self.rect_id, x, y, style)?;
+                }
+                None => {
+                    rectmanager.unset_character_style(self.rect_id, x, y)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Get the index of the cell a pixel is in, and its bit in that cell
+    fn locate(&self, x: isize, y: isize) -> Option<(usize, u8)> {
+        let (pixel_width, pixel_height) = self.get_pixel_size();
+        if x < 0 || y < 0 || x as usize >= pixel_width || y as usize >= pixel_height {
+            return None;
+        }
+
+        let (cell_width, cell_height) = self.mode.get_cell_size();
+        let (x, y) = (x as usize, y as usize);
+        let index = (y / cell_height) * self.width + (x / cell_width);
+        Some((index, self.mode.get_bit(x % cell_width, y % cell_height)))
+    }
+}
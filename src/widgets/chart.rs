@@ -0,0 +1,755 @@
+use std::cmp;
+use std::collections::VecDeque;
+use crate::{RectManager, WreckedError, Style, Color};
+use crate::layout::Direction;
+use super::{Canvas, CanvasMode, fit_text, Alignment};
+use super::progress::{get_bar_string, get_column_characters};
+
+type Bounds = (f64, f64);
+
+// Format a value for an axis or a bar compactly, eg 1234567 -> "1.2M"
+fn format_value(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude >= 1e9 {
+        format!("{:.1}G", value / 1e9)
+    } else if magnitude >= 1e6 {
+        format!("{:.1}M", value / 1e6)
+    } else if magnitude >= 1e4 {
+        format!("{:.1}k", value / 1e3)
+    } else if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else if magnitude >= 100.0 {
+        format!("{:.0}", value)
+    } else if magnitude >= 1.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+// The smallest and largest of the values, widened if they're all the same so that they can still be scaled
+fn get_bounds<I: Iterator<Item = f64>>(values: I) -> Option<Bounds> {
+    let (min, max) = values
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+
+    if min > max {
+        None
+    } else if min == max {
+        Some((min - 1.0, max + 1.0))
+    } else {
+        Some((min, max))
+    }
+}
+
+// Where `value` is between the bounds, from 0 to 1
+fn get_fraction(value: f64, bounds: Bounds) -> f64 {
+    (value - bounds.0) / (bounds.1 - bounds.0)
+}
+
+// Draw a row of text into a rect, clipped to its width
+fn draw_text(rectmanager: &mut RectManager, rect_id: usize, x: isize, y: isize, text: &str) -> Result<(), WreckedError> {
+    let width = rectmanager.get_rect_width(rect_id) as isize;
+    for (offset, character) in text.chars().enumerate() {
+        let position = x + offset as isize;
+        if position >= 0 && position < width {
+            rectmanager.set_character(rect_id, position, y, character)?;
+        }
+    }
+    Ok(())
+}
+
+struct ChartSeries {
+    name: String,
+    color: Option<Color>,
+    points: VecDeque<(f64, f64)>
+}
+
+/// Plots series of (x, y) points as lines on a braille canvas, with labelled axes.
+/// Bounds that aren't set are fitted to the data.
+///
+/// Pushing a point only draws the new segment, unless the bounds of the chart change. Set both bounds when
+/// streaming samples to avoid redrawing the whole chart each time.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT, Color};
+/// use wrecked::widgets::LineChart;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut chart = LineChart::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(chart.get_rect_id(), 60, 15);
+/// let latency = chart.add_series("latency", Some(Color::GREEN));
+/// chart.set_max_points(Some(100));
+///
+/// for i in 0 .. 200 {
+///     let x = i as f64;
+///     chart.push_point(&mut rectmanager, latency, x, (x / 10.0).sin() * 50.0 + 100.0);
+/// }
+/// assert_eq!(chart.get_x_bounds(), Some((100.0, 199.0)));
+///
+/// rectmanager.kill();
+/// ```
+pub struct LineChart {
+    rect_id: usize,
+    canvas: Canvas,
+    series: Vec<ChartSeries>,
+    x_bounds: Option<Bounds>,
+    y_bounds: Option<Bounds>,
+    max_points: Option<usize>,
+    axis_style: Style,
+    // Bounds the points were last drawn with
+    drawn_bounds: Option<(Bounds, Bounds)>
+}
+
+impl LineChart {
+    /// Create an empty LineChart with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<LineChart, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let canvas = Canvas::new(rectmanager, rect_id, 0, 0, CanvasMode::Braille)?;
+
+        Ok(LineChart {
+            rect_id,
+            canvas,
+            series: Vec::new(),
+            x_bounds: None,
+            y_bounds: None,
+            max_points: None,
+            axis_style: Style {
+                foreground_color: Some(Color::BRIGHTBLACK),
+                ..Style::default()
+            },
+            drawn_bounds: None
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Get the id of the rect the lines are drawn in.
+    pub fn get_plot_rect_id(&self) -> usize {
+        self.canvas.get_rect_id()
+    }
+
+    /// Add an empty series, returning its index.
+    pub fn add_series(&mut self, name: &str, color: Option<Color>) -> usize {
+        self.series.push(ChartSeries {
+            name: name.to_string(),
+            color,
+            points: VecDeque::new()
+        });
+        self.series.len() - 1
+    }
+
+    pub fn get_series_name(&self, series: usize) -> Option<&str> {
+        self.series.get(series).map(|series| series.name.as_str())
+    }
+
+    /// Replace all the points of a series.
+    pub fn set_points(&mut self, rectmanager: &mut RectManager, series: usize, points: &[(f64, f64)]) -> Result<(), WreckedError> {
+        if let Some(series) = self.series.get_mut(series) {
+            series.points = points.iter().copied().collect();
+        }
+        self.trim_points();
+        self.refresh(rectmanager)
+    }
+
+    /// Add a point to the end of a series, dropping the oldest point if there are more than the maximum.
+    /// Points that aren't finite (NaN or infinite) are kept, but left out of the line.
+    pub fn push_point(&mut self, rectmanager: &mut RectManager, series: usize, x: f64, y: f64) -> Result<(), WreckedError> {
+        let (previous, color) = match self.series.get_mut(series) {
+            Some(series) => {
+                series.points.push_back((x, y));
+                // The line skips over points that aren't drawn
+                let previous = series.points.iter().rev().skip(1).find(|(x, y)| x.is_finite() && y.is_finite());
+                (previous.copied(), series.color)
+            }
+            None => {
+                return Ok(());
+            }
+        };
+
+        let trimmed = self.trim_points();
+        let bounds = self.get_bounds();
+        match previous {
+            Some(previous) if !trimmed && self.drawn_bounds == Some(bounds) => {
+                if let (Some((from_x, from_y)), Some((to_x, to_y))) = (self.get_pixel(previous, bounds), self.get_pixel((x, y), bounds)) {
+                    self.canvas.set_color(color);
+                    self.canvas.line(from_x, from_y, to_x, to_y);
+                }
+                self.canvas.flush(rectmanager)
+            }
+            _ => {
+                self.refresh(rectmanager)
+            }
+        }
+    }
+
+    /// Keep at most this many points in each series, dropping the oldest.
+    pub fn set_max_points(&mut self, max_points: Option<usize>) {
+        self.max_points = max_points;
+        self.trim_points();
+    }
+
+    /// Fix the range of the x axis, or fit it to the data (None).
+    pub fn set_x_bounds(&mut self, rectmanager: &mut RectManager, bounds: Option<(f64, f64)>) -> Result<(), WreckedError> {
+        self.x_bounds = bounds;
+        self.refresh(rectmanager)
+    }
+
+    /// Fix the range of the y axis, or fit it to the data (None).
+    pub fn set_y_bounds(&mut self, rectmanager: &mut RectManager, bounds: Option<(f64, f64)>) -> Result<(), WreckedError> {
+        self.y_bounds = bounds;
+        self.refresh(rectmanager)
+    }
+
+    /// Get the range of the x axis, as of the last time the chart was drawn.
+    pub fn get_x_bounds(&self) -> Option<(f64, f64)> {
+        self.drawn_bounds.map(|(x_bounds, _)| x_bounds)
+    }
+
+    /// Get the range of the y axis, as of the last time the chart was drawn.
+    pub fn get_y_bounds(&self) -> Option<(f64, f64)> {
+        self.drawn_bounds.map(|(_, y_bounds)| y_bounds)
+    }
+
+    /// Set the style of the axes and their labels.
+    pub fn set_axis_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.axis_style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the axes and all the series. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        let (x_bounds, y_bounds) = self.get_bounds();
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.clear_character_styles(self.rect_id)?;
+
+        // Rows of the y axis's ticks, from the top, with their labels
+        let plot_height = height.saturating_sub(2);
+        let tick_count = (plot_height / 3 + 1).clamp(2, 5);
+        let y_ticks: Vec<(usize, String)> = (0 .. tick_count).map(|i| {
+            let row = if plot_height > 1 { (i * (plot_height - 1) + (tick_count - 1) / 2) / (tick_count - 1) } else { 0 };
+            let fraction = if plot_height > 1 { row as f64 / (plot_height - 1) as f64 } else { 0.0 };
+            (row, format_value(y_bounds.1 - fraction * (y_bounds.1 - y_bounds.0)))
+        }).collect();
+
+        let label_width = y_ticks.iter().map(|(_, label)| label.chars().count()).max().unwrap_or(0);
+        let plot_x = label_width + 1;
+        let plot_width = width.saturating_sub(plot_x);
+        if plot_width == 0 || plot_height == 0 {
+            self.canvas.resize(rectmanager, 0, 0)?;
+            self.drawn_bounds = None;
+            return Ok(());
+        }
+
+        // Axes
+        let axis_x = label_width as isize;
+        let axis_y = plot_height as isize;
+        for y in 0 .. axis_y {
+            rectmanager.set_character(self.rect_id, axis_x, y, '│')?;
+        }
+        for x in plot_x .. width {
+            rectmanager.set_character(self.rect_id, x as isize, axis_y, '─')?;
+        }
+        rectmanager.set_character(self.rect_id, axis_x, axis_y, '└')?;
+
+        for (row, label) in y_ticks.iter() {
+            let label = fit_text(label, label_width, Alignment::Right);
+            draw_text(rectmanager, self.rect_id, 0, *row as isize, &label)?;
+            rectmanager.set_character(self.rect_id, axis_x, *row as isize, '┤')?;
+        }
+
+        let tick_count = (plot_width / 12 + 1).clamp(2, 6);
+        let mut label_end = 0;
+        for i in 0 .. tick_count {
+            let column = if plot_width > 1 { i * (plot_width - 1) / (tick_count - 1) } else { 0 };
+            let fraction = if plot_width > 1 { column as f64 / (plot_width - 1) as f64 } else { 0.0 };
+            let label = format_value(x_bounds.0 + fraction * (x_bounds.1 - x_bounds.0));
+            let length = label.chars().count() as isize;
+
+            let x = (plot_x + column) as isize;
+            rectmanager.set_character(self.rect_id, x, axis_y, '┴')?;
+            let start = cmp::max(cmp::min(x - length / 2, width as isize - length), 0);
+            if start >= label_end {
+                draw_text(rectmanager, self.rect_id, start, axis_y + 1, &label)?;
+                label_end = start + length + 1;
+            }
+        }
+
+        for y in 0 .. cmp::min(height, plot_height + 2) as isize {
+            for x in 0 .. width as isize {
+                rectmanager.set_character_style(self.rect_id, x, y, self.axis_style)?;
+            }
+        }
+
+        // Lines
+        if self.canvas.get_pixel_size() != (plot_width * 2, plot_height * 4) {
+            self.canvas.resize(rectmanager, plot_width, plot_height)?;
+        }
+        rectmanager.set_position(self.canvas.get_rect_id(), plot_x as isize, 0)?;
+
+        self.canvas.clear();
+        for i in 0 .. self.series.len() {
+            self.canvas.set_color(self.series[i].color);
+            let pixels: Vec<(isize, isize)> = self.series[i].points.iter()
+                .filter_map(|point| self.get_pixel(*point, (x_bounds, y_bounds)))
+                .collect();
+
+            if pixels.len() == 1 {
+                self.canvas.set_pixel(pixels[0].0, pixels[0].1);
+            }
+            for pair in pixels.windows(2) {
+                self.canvas.line(pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+            }
+        }
+        self.canvas.flush(rectmanager)?;
+        self.drawn_bounds = Some((x_bounds, y_bounds));
+
+        Ok(())
+    }
+
+    // Drop points over the maximum. Returns true if any were dropped.
+    fn trim_points(&mut self) -> bool {
+        let mut trimmed = false;
+        if let Some(max_points) = self.max_points {
+            for series in self.series.iter_mut() {
+                while series.points.len() > max_points {
+                    series.points.pop_front();
+                    trimmed = true;
+                }
+            }
+        }
+        trimmed
+    }
+
+    fn get_bounds(&self) -> (Bounds, Bounds) {
+        let points = || self.series.iter().flat_map(|series| series.points.iter());
+        let x_bounds = self.x_bounds
+            .or_else(|| get_bounds(points().map(|(x, _)| *x)))
+            .unwrap_or((0.0, 1.0));
+        let y_bounds = self.y_bounds
+            .or_else(|| get_bounds(points().map(|(_, y)| *y)))
+            .unwrap_or((0.0, 1.0));
+        (x_bounds, y_bounds)
+    }
+
+    // Get the canvas pixel of a point, which may be off the canvas if it's out of bounds. None if it can't be drawn.
+    fn get_pixel(&self, point: (f64, f64), bounds: (Bounds, Bounds)) -> Option<(isize, isize)> {
+        let (width, height) = self.canvas.get_pixel_size();
+        let x = get_fraction(point.0, bounds.0) * width.saturating_sub(1) as f64;
+        let y = (1.0 - get_fraction(point.1, bounds.1)) * height.saturating_sub(1) as f64;
+        if x.is_finite() && y.is_finite() {
+            Some((x.round() as isize, y.round() as isize))
+        } else {
+            None
+        }
+    }
+}
+
+/// Labelled bars, drawn to an eighth of a cell.
+/// Vertical bars grow up from a row of labels; horizontal bars grow right from a column of labels.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::BarChart;
+/// use wrecked::layout::Direction;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut chart = BarChart::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(chart.get_rect_id(), 30, 3);
+/// chart.set_direction(&mut rectmanager, Direction::Horizontal);
+/// chart.set_bar_width(&mut rectmanager, 1, 0);
+/// chart.set_bars(&mut rectmanager, &[("cpu", 50.0), ("mem", 100.0), ("disk", 12.5)]);
+///
+/// assert_eq!(rectmanager.get_character(chart.get_rect_id(), 0, 0).ok(), Some(' '));
+/// assert_eq!(rectmanager.get_character(chart.get_rect_id(), 1, 0).ok(), Some('c'));
+///
+/// rectmanager.kill();
+/// ```
+pub struct BarChart {
+    rect_id: usize,
+    bars: Vec<(String, f64)>,
+    direction: Direction,
+    bar_width: usize,
+    gap: usize,
+    max: Option<f64>,
+    show_values: bool,
+    bar_style: Style
+}
+
+impl BarChart {
+    /// Create an empty, vertical BarChart with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<BarChart, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        Ok(BarChart {
+            rect_id,
+            bars: Vec::new(),
+            direction: Direction::Vertical,
+            bar_width: 3,
+            gap: 1,
+            max: None,
+            show_values: true,
+            bar_style: Style::default()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Replace the bars with (label, value) pairs.
+    pub fn set_bars(&mut self, rectmanager: &mut RectManager, bars: &[(&str, f64)]) -> Result<(), WreckedError> {
+        self.bars = bars.iter().map(|(label, value)| (label.to_string(), *value)).collect();
+        self.refresh(rectmanager)
+    }
+
+    /// Change the value of a single bar.
+    pub fn set_value(&mut self, rectmanager: &mut RectManager, index: usize, value: f64) -> Result<(), WreckedError> {
+        if let Some(bar) = self.bars.get_mut(index) {
+            bar.1 = value;
+        }
+        self.refresh(rectmanager)
+    }
+
+    pub fn get_value(&self, index: usize) -> Option<f64> {
+        self.bars.get(index).map(|(_, value)| *value)
+    }
+
+    /// Vertical (the default) or horizontal bars.
+    pub fn set_direction(&mut self, rectmanager: &mut RectManager, direction: Direction) -> Result<(), WreckedError> {
+        self.direction = direction;
+        self.refresh(rectmanager)
+    }
+
+    /// Set the thickness of each bar and the space between them, in cells.
+    pub fn set_bar_width(&mut self, rectmanager: &mut RectManager, bar_width: usize, gap: usize) -> Result<(), WreckedError> {
+        self.bar_width = cmp::max(bar_width, 1);
+        self.gap = gap;
+        self.refresh(rectmanager)
+    }
+
+    /// Fix the value of a full length bar, or use the largest value (None).
+    pub fn set_max(&mut self, rectmanager: &mut RectManager, max: Option<f64>) -> Result<(), WreckedError> {
+        self.max = max;
+        self.refresh(rectmanager)
+    }
+
+    /// Show each bar's value above it (vertical) or after it (horizontal). On by default.
+    pub fn set_show_values(&mut self, rectmanager: &mut RectManager, show_values: bool) -> Result<(), WreckedError> {
+        self.show_values = show_values;
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_bar_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.bar_style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the bars. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.clear_character_styles(self.rect_id)?;
+
+        let max = match self.max {
+            Some(max) => max,
+            None => self.bars.iter().map(|(_, value)| *value).fold(0.0, f64::max)
+        };
+        let scale = |value: f64| if max > 0.0 { value / max } else { 0.0 };
+
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        let has_labels = self.bars.iter().any(|(label, _)| !label.is_empty());
+
+        match self.direction {
+            Direction::Vertical => {
+                let value_rows = if self.show_values { 1 } else { 0 };
+                let label_rows = if has_labels { 1 } else { 0 };
+                let bar_height = height.saturating_sub(value_rows + label_rows);
+
+                for (i, (label, value)) in self.bars.iter().enumerate() {
+                    let x = i * (self.bar_width + self.gap);
+                    if x >= width {
+                        break;
+                    }
+                    let bar_width = cmp::min(self.bar_width, width - x);
+
+                    for (row, character) in get_column_characters(scale(*value), bar_height).into_iter().enumerate() {
+                        let y = (value_rows + row) as isize;
+                        for column in x .. x + bar_width {
+                            rectmanager.set_character(self.rect_id, column as isize, y, character)?;
+                            if character != ' ' {
+                                rectmanager.set_character_style(self.rect_id, column as isize, y, self.bar_style)?;
+                            }
+                        }
+                    }
+
+                    if self.show_values && height > 0 {
+                        draw_text(rectmanager, self.rect_id, x as isize, 0, &fit_text(&format_value(*value), bar_width, Alignment::Center))?;
+                    }
+                    if has_labels && height > value_rows {
+                        draw_text(rectmanager, self.rect_id, x as isize, height as isize - 1, &fit_text(label, bar_width, Alignment::Center))?;
+                    }
+                }
+            }
+            Direction::Horizontal => {
+                let label_width = self.bars.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+                let value_width = if self.show_values {
+                    self.bars.iter().map(|(_, value)| format_value(*value).chars().count() + 1).max().unwrap_or(0)
+                } else {
+                    0
+                };
+                let bar_x = if has_labels { label_width + 1 } else { 0 };
+                let bar_length = width.saturating_sub(bar_x + value_width);
+
+                for (i, (label, value)) in self.bars.iter().enumerate() {
+                    let y = i * (self.bar_width + self.gap);
+                    if y >= height {
+                        break;
+                    }
+
+                    let bar = get_bar_string(scale(*value), bar_length);
+                    let filled = bar.trim_end().chars().count();
+                    for row in y .. cmp::min(y + self.bar_width, height) {
+                        draw_text(rectmanager, self.rect_id, bar_x as isize, row as isize, &bar)?;
+                        for x in bar_x .. bar_x + filled {
+                            rectmanager.set_character_style(self.rect_id, x as isize, row as isize, self.bar_style)?;
+                        }
+                    }
+
+                    // Labels and values go on the middle row of the bar
+                    let row = (y + cmp::min(self.bar_width, height - y) / 2) as isize;
+                    if has_labels {
+                        draw_text(rectmanager, self.rect_id, 0, row, &fit_text(label, label_width, Alignment::Right))?;
+                    }
+                    if self.show_values {
+                        draw_text(rectmanager, self.rect_id, (bar_x + filled + 1) as isize, row, &format_value(*value))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A compact line of bars showing the latest values of a series, newest on the right.
+/// It keeps as many values as the rect is wide; taller rects give the bars more resolution.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Sparkline;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut sparkline = Sparkline::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(sparkline.get_rect_id(), 4, 1);
+/// for value in [1.0, 2.0, 4.0, 8.0, 4.0] {
+///     sparkline.push(&mut rectmanager, value);
+/// }
+///
+/// let line: String = (0 .. 4).map(|x| rectmanager.get_character(sparkline.get_rect_id(), x, 0).ok().unwrap()).collect();
+/// assert_eq!(line, "▂▄█▄");
+///
+/// rectmanager.kill();
+/// ```
+pub struct Sparkline {
+    rect_id: usize,
+    values: VecDeque<f64>,
+    max: Option<f64>,
+    style: Style
+}
+
+impl Sparkline {
+    /// Create an empty Sparkline with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<Sparkline, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        Ok(Sparkline {
+            rect_id,
+            values: VecDeque::new(),
+            max: None,
+            style: Style::default()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Add a value on the right, dropping the oldest one if there's no room left.
+    pub fn push(&mut self, rectmanager: &mut RectManager, value: f64) -> Result<(), WreckedError> {
+        self.values.push_back(value);
+        self.refresh(rectmanager)
+    }
+
+    /// Replace all the values.
+    pub fn set_values(&mut self, rectmanager: &mut RectManager, values: &[f64]) -> Result<(), WreckedError> {
+        self.values = values.iter().copied().collect();
+        self.refresh(rectmanager)
+    }
+
+    /// Get the values currently shown, oldest first.
+    pub fn get_values(&self) -> Vec<f64> {
+        self.values.iter().copied().collect()
+    }
+
+    /// Fix the value of a full height bar, or use the largest value shown (None).
+    pub fn set_max(&mut self, rectmanager: &mut RectManager, max: Option<f64>) -> Result<(), WreckedError> {
+        self.max = max;
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the bars. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        while self.values.len() > width {
+            self.values.pop_front();
+        }
+
+        let max = match self.max {
+            Some(max) => max,
+            None => self.values.iter().copied().fold(0.0, f64::max)
+        };
+
+        // Values are right-aligned
+        let start = width - self.values.len();
+        for x in 0 .. width {
+            let fraction = match x.checked_sub(start).and_then(|index| self.values.get(index)) {
+                Some(value) if max > 0.0 => value / max,
+                _ => 0.0
+            };
+
+            for (y, character) in get_column_characters(fraction, height).into_iter().enumerate() {
+                rectmanager.set_character(self.rect_id, x as isize, y as isize, character)?;
+                if character != ' ' {
+                    rectmanager.set_character_style(self.rect_id, x as isize, y as isize, self.style)?;
+                } else {
+                    rectmanager.unset_character_style(self.rect_id, x as isize, y as isize)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts values into equal-width bins and shows the counts as bars, with the range of the bins below them.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Histogram;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut histogram = Histogram::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(histogram.get_rect_id(), 20, 6);
+/// histogram.set_bins(&mut rectmanager, 4);
+/// histogram.set_range(&mut rectmanager, Some((0.0, 100.0)));
+/// histogram.set_values(&mut rectmanager, &[5.0, 10.0, 30.0, 99.0, 100.0, 140.0]);
+///
+/// assert_eq!(histogram.get_counts(), vec![2, 1, 0, 2]);
+///
+/// rectmanager.kill();
+/// ```
+pub struct Histogram {
+    rect_id: usize,
+    chart: BarChart,
+    values: Vec<f64>,
+    bins: usize,
+    range: Option<(f64, f64)>
+}
+
+impl Histogram {
+    /// Create an empty Histogram with 10 bins, with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<Histogram, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let mut chart = BarChart::new(rectmanager, rect_id)?;
+        chart.show_values = false;
+        chart.gap = 0;
+
+        Ok(Histogram {
+            rect_id,
+            chart,
+            values: Vec::new(),
+            bins: 10,
+            range: None
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn set_bins(&mut self, rectmanager: &mut RectManager, bins: usize) -> Result<(), WreckedError> {
+        self.bins = cmp::max(bins, 1);
+        self.refresh(rectmanager)
+    }
+
+    /// Fix the range covered by the bins, or fit it to the values (None). Values outside of the range aren't counted.
+    pub fn set_range(&mut self, rectmanager: &mut RectManager, range: Option<(f64, f64)>) -> Result<(), WreckedError> {
+        self.range = range;
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_values(&mut self, rectmanager: &mut RectManager, values: &[f64]) -> Result<(), WreckedError> {
+        self.values = values.to_vec();
+        self.refresh(rectmanager)
+    }
+
+    pub fn push(&mut self, rectmanager: &mut RectManager, value: f64) -> Result<(), WreckedError> {
+        self.values.push(value);
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_bar_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.chart.set_bar_style(rectmanager, style)
+    }
+
+    /// Get the number of values in each bin.
+    pub fn get_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.bins];
+        let range = match self.get_range() {
+            Some(range) => range,
+            None => {
+                return counts;
+            }
+        };
+
+        for value in self.values.iter() {
+            let fraction = get_fraction(*value, range);
+            if (0.0 ..= 1.0).contains(&fraction) {
+                // The top of the range goes in the last bin
+                let bin = cmp::min((fraction * self.bins as f64) as usize, self.bins - 1);
+                counts[bin] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Redraw the histogram. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.resize(self.chart.rect_id, width, height.saturating_sub(1))?;
+
+        self.chart.bar_width = cmp::max(width / self.bins, 1);
+        let counts: Vec<(&str, f64)> = self.get_counts().into_iter().map(|count| ("", count as f64)).collect();
+        self.chart.set_bars(rectmanager, &counts)?;
+
+        if let (Some((min, max)), true) = (self.get_range(), height > 0) {
+            let y = height as isize - 1;
+            let max_label = format_value(max);
+            draw_text(rectmanager, self.rect_id, 0, y, &format_value(min))?;
+            draw_text(rectmanager, self.rect_id, (self.chart.bar_width * self.bins) as isize - max_label.chars().count() as isize, y, &max_label)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_range(&self) -> Option<(f64, f64)> {
+        self.range.or_else(|| get_bounds(self.values.iter().copied()))
+    }
+}
@@ -0,0 +1,420 @@
+use std::cmp;
+use crate::{RectManager, WreckedError, Style, Color, ROOT};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use crate::layout::{Anchor, HorizontalAnchor, VerticalAnchor};
+use super::{TextInput, fit_text, draw_box, Alignment};
+
+/// How a built-in dialog was closed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum DialogResponse {
+    /// An alert was acknowledged
+    Dismissed,
+    /// A confirm dialog was answered yes (true) or no (false)
+    Confirmed(bool),
+    /// A prompt was submitted with the given text, or cancelled (None)
+    Prompted(Option<String>)
+}
+
+/// What happened to an event passed to DialogStack::handle_event().
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum DialogEvent {
+    /// No dialog is open; the event can be handled by the rest of the interface
+    Ignored,
+    /// The event was used or swallowed by the dialog on top
+    Captured,
+    /// The dialog on top was made with push(). It's up to the caller to pass the event to whatever is in it.
+    /// The event shouldn't reach anything underneath.
+    Forward(usize),
+    /// The built-in dialog with the given rect id was closed
+    Closed(usize, DialogResponse)
+}
+
+enum DialogKind {
+    Custom,
+    Alert,
+    Confirm(bool),
+    Prompt(TextInput)
+}
+
+struct Dialog {
+    rect_id: usize,
+    kind: DialogKind,
+    // Where the terminal's cursor was before the dialog was opened
+    saved_cursor: Option<(usize, isize, isize)>,
+    // (x, width) of each button on the bottom row
+    buttons: Vec<(isize, usize)>
+}
+
+/// A stack of modal dialogs, centered over ROOT.
+/// While any dialog is open, all input should be passed to the stack first; only events it returns as Ignored
+/// should reach the rest of the interface.
+///
+/// Results of the built-in dialogs (alert, confirm and prompt) come back through handle_event().
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::{DialogStack, DialogEvent, DialogResponse};
+/// use wrecked::input::{InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+/// rectmanager.resize(ROOT, 80, 24);
+///
+/// let mut dialogs = DialogStack::new();
+/// let quit_dialog = dialogs.confirm(&mut rectmanager, "Quit", "Discard unsaved changes?").ok().unwrap();
+///
+/// // Everything is captured while the dialog is open
+/// let result = dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('q'))).ok().unwrap();
+/// assert_eq!(result, DialogEvent::Captured);
+///
+/// let result = dialogs.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('y'))).ok().unwrap();
+/// assert_eq!(result, DialogEvent::Closed(quit_dialog, DialogResponse::Confirmed(true)));
+/// assert!(!dialogs.is_open());
+///
+/// rectmanager.kill();
+/// ```
+pub struct DialogStack {
+    dialogs: Vec<Dialog>,
+    dim: bool,
+    button_style: Style,
+    active_button_style: Style
+}
+
+impl Default for DialogStack {
+    fn default() -> DialogStack {
+        DialogStack::new()
+    }
+}
+
+impl DialogStack {
+    pub fn new() -> DialogStack {
+        DialogStack {
+            dialogs: Vec::new(),
+            dim: true,
+            button_style: Style::default(),
+            active_button_style: Style {
+                invert: true,
+                ..Style::default()
+            }
+        }
+    }
+
+    /// Dim everything beneath the top dialog (on by default).
+    pub fn set_dim(&mut self, rectmanager: &mut RectManager, dim: bool) -> Result<(), WreckedError> {
+        self.dim = dim;
+        for dialog in self.dialogs.iter() {
+            rectmanager.set_dim_below(dialog.rect_id, dim)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.dialogs.is_empty()
+    }
+
+    /// Get the rect id of the dialog on top.
+    pub fn get_top(&self) -> Option<usize> {
+        self.dialogs.last().map(|dialog| dialog.rect_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dialogs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dialogs.is_empty()
+    }
+
+    /// Open an empty dialog of the given size, returning its rect to draw into.
+    /// Input for it comes back from handle_event() as DialogEvent::Forward.
+    pub fn push(&mut self, rectmanager: &mut RectManager, width: usize, height: usize) -> Result<usize, WreckedError> {
+        self.open(rectmanager, width, height, DialogKind::Custom)
+    }
+
+    /// Close the dialog on top, deleting its rect and putting the terminal's cursor back where it was.
+    /// Returns the id of the closed dialog.
+    pub fn pop(&mut self, rectmanager: &mut RectManager) -> Result<Option<usize>, WreckedError> {
+        match self.dialogs.pop() {
+            Some(dialog) => {
+                rectmanager.delete_rect(dialog.rect_id)?;
+                match dialog.saved_cursor {
+                    Some((rect_id, x, y)) if rectmanager.has_rect(rect_id) => {
+                        rectmanager.set_cursor(rect_id, x, y)?;
+                    }
+                    _ => {
+                        rectmanager.hide_cursor();
+                    }
+                }
+                Ok(Some(dialog.rect_id))
+            }
+            None => {
+                Ok(None)
+            }
+        }
+    }
+
+    /// Show a message with an OK button.
+    pub fn alert(&mut self, rectmanager: &mut RectManager, title: &str, message: &str) -> Result<usize, WreckedError> {
+        let lines = self.wrap_message(rectmanager, message);
+        let (width, height) = self.get_dialog_size(rectmanager, title, &lines, &["OK"], 0);
+        let rect_id = self.open(rectmanager, width, height, DialogKind::Alert)?;
+        self.draw_frame(rectmanager, rect_id, title, &lines)?;
+        self.draw_buttons(rectmanager, &["OK"], 0)?;
+        Ok(rect_id)
+    }
+
+    /// Ask a yes or no question. Answered with Enter, y or n; Escape answers no.
+    pub fn confirm(&mut self, rectmanager: &mut RectManager, title: &str, message: &str) -> Result<usize, WreckedError> {
+        let lines = self.wrap_message(rectmanager, message);
+        let (width, height) = self.get_dialog_size(rectmanager, title, &lines, &["Yes", "No"], 0);
+        let rect_id = self.open(rectmanager, width, height, DialogKind::Confirm(true))?;
+        self.draw_frame(rectmanager, rect_id, title, &lines)?;
+        self.draw_buttons(rectmanager, &["Yes", "No"], 0)?;
+        Ok(rect_id)
+    }
+
+    /// Ask for a line of text. Submitted with Enter; Escape cancels.
+    pub fn prompt(&mut self, rectmanager: &mut RectManager, title: &str, message: &str, default: &str) -> Result<usize, WreckedError> {
+        let lines = self.wrap_message(rectmanager, message);
+        let (width, height) = self.get_dialog_size(rectmanager, title, &lines, &["OK", "Cancel"], 2);
+        let rect_id = self.open(rectmanager, width, height, DialogKind::Custom)?;
+        self.draw_frame(rectmanager, rect_id, title, &lines)?;
+
+        let mut input = TextInput::new(rectmanager, rect_id)?;
+        rectmanager.resize(input.get_rect_id(), width.saturating_sub(4), 1)?;
+        rectmanager.set_position(input.get_rect_id(), 2, height as isize - 4)?;
+        rectmanager.set_bg_color(input.get_rect_id(), Color::BRIGHTBLACK)?;
+        input.set_text(rectmanager, default)?;
+        input.focus(rectmanager)?;
+
+        if let Some(dialog) = self.dialogs.last_mut() {
+            dialog.kind = DialogKind::Prompt(input);
+        }
+        self.draw_buttons(rectmanager, &["OK", "Cancel"], 0)?;
+        Ok(rect_id)
+    }
+
+    /// Pass an event to the dialog on top.
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<DialogEvent, WreckedError> {
+        let (rect_id, button_count) = match self.dialogs.last() {
+            Some(dialog) => {
+                (dialog.rect_id, dialog.buttons.len())
+            }
+            None => {
+                return Ok(DialogEvent::Ignored);
+            }
+        };
+
+        // Work out which button, if any, was chosen
+        let mut chosen = None;
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                let dialog = self.dialogs.last_mut().unwrap();
+                match &mut dialog.kind {
+                    DialogKind::Custom => {
+                        return Ok(DialogEvent::Forward(rect_id));
+                    }
+                    DialogKind::Alert => {
+                        if let Key::Enter | Key::Escape | Key::Char(' ') = key {
+                            chosen = Some(0);
+                        }
+                    }
+                    DialogKind::Confirm(choice) => {
+                        match key {
+                            Key::Left | Key::Right | Key::Tab | Key::BackTab => {
+                                *choice = !*choice;
+                                let active = if *choice { 0 } else { 1 };
+                                self.draw_buttons(rectmanager, &["Yes", "No"], active)?;
+                            }
+                            Key::Enter | Key::Char(' ') => {
+                                chosen = Some(if *choice { 0 } else { 1 });
+                            }
+                            Key::Char('y') | Key::Char('Y') => {
+                                chosen = Some(0);
+                            }
+                            Key::Char('n') | Key::Char('N') | Key::Escape => {
+                                chosen = Some(1);
+                            }
+                            _ => ()
+                        }
+                    }
+                    DialogKind::Prompt(input) => {
+                        match key {
+                            Key::Enter => {
+                                chosen = Some(0);
+                            }
+                            Key::Escape => {
+                                chosen = Some(1);
+                            }
+                            _ => {
+                                input.handle_event(rectmanager, &InputEvent::Key(*key, *modifiers))?;
+                            }
+                        }
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                let dialog = self.dialogs.last_mut().unwrap();
+                if let DialogKind::Custom = dialog.kind {
+                    return Ok(DialogEvent::Forward(rect_id));
+                }
+
+                if mouse.action == MouseAction::Press && mouse.button == MouseButton::Left {
+                    let (offset_x, offset_y) = rectmanager.get_absolute_offset(rect_id).unwrap_or((0, 0));
+                    let height = rectmanager.get_rect_height(rect_id) as isize;
+                    if mouse.y - offset_y == height - 2 {
+                        let x = mouse.x - offset_x;
+                        chosen = dialog.buttons.iter().position(|(start, width)| x >= *start && x < *start + *width as isize);
+                    } else if let DialogKind::Prompt(input) = &mut dialog.kind {
+                        input.handle_event(rectmanager, event)?;
+                    }
+                }
+            }
+        }
+
+        match chosen {
+            Some(button) if button < button_count => {
+                let dialog = self.dialogs.last().unwrap();
+                let response = match &dialog.kind {
+                    DialogKind::Alert | DialogKind::Custom => DialogResponse::Dismissed,
+                    DialogKind::Confirm(_) => DialogResponse::Confirmed(button == 0),
+                    DialogKind::Prompt(input) => {
+                        if button == 0 {
+                            DialogResponse::Prompted(Some(input.get_text()))
+                        } else {
+                            DialogResponse::Prompted(None)
+                        }
+                    }
+                };
+                self.pop(rectmanager)?;
+                Ok(DialogEvent::Closed(rect_id, response))
+            }
+            _ => {
+                Ok(DialogEvent::Captured)
+            }
+        }
+    }
+
+    fn open(&mut self, rectmanager: &mut RectManager, width: usize, height: usize, kind: DialogKind) -> Result<usize, WreckedError> {
+        let (root_width, root_height) = rectmanager.get_rect_size(ROOT).unwrap_or((0, 0));
+        let rect_id = rectmanager.new_rect(ROOT)?;
+        rectmanager.resize(rect_id, width, height)?;
+        rectmanager.set_position(
+            rect_id,
+            (root_width as isize - width as isize) / 2,
+            (root_height as isize - height as isize) / 2
+        )?;
+        // Stay centered if the terminal is resized
+        rectmanager.set_anchor(rect_id, Anchor::new(HorizontalAnchor::Center, VerticalAnchor::Center))?;
+        rectmanager.set_dim_below(rect_id, self.dim)?;
+
+        let saved_cursor = rectmanager.get_cursor();
+        rectmanager.hide_cursor();
+
+        self.dialogs.push(Dialog {
+            rect_id,
+            kind,
+            saved_cursor,
+            buttons: Vec::new()
+        });
+
+        Ok(rect_id)
+    }
+
+    // Break the message into lines that fit in a dialog narrower than ROOT
+    fn wrap_message(&self, rectmanager: &RectManager, message: &str) -> Vec<String> {
+        let max_width = cmp::max(rectmanager.get_width().saturating_sub(8), 10);
+        let mut lines = Vec::new();
+        for paragraph in message.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let line_length = line.chars().count();
+                if line_length > 0 && line_length + 1 + word.chars().count() > max_width {
+                    lines.push(line);
+                    line = String::new();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line += word;
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    // Width fits the title, message and buttons; height fits the border, message, extra rows and buttons
+    fn get_dialog_size(&self, rectmanager: &RectManager, title: &str, lines: &[String], buttons: &[&str], extra_rows: usize) -> (usize, usize) {
+        let content_width = lines.iter()
+            .map(|line| line.chars().count())
+            .chain(Some(title.chars().count() + 2))
+            .chain(Some(buttons.iter().map(|label| label.chars().count() + 5).sum::<usize>()))
+            .fold(20, cmp::max);
+
+        let width = cmp::min(content_width + 4, rectmanager.get_width());
+        let height = cmp::min(lines.len() + extra_rows + 4, rectmanager.get_height());
+        (width, height)
+    }
+
+    fn draw_frame(&self, rectmanager: &mut RectManager, rect_id: usize, title: &str, lines: &[String]) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(rect_id).unwrap_or((0, 0));
+        let bottom = height as isize - 1;
+
+        draw_box(rectmanager, rect_id)?;
+
+        if !title.is_empty() && width > 4 {
+            let label = fit_text(&format!(" {} ", title), cmp::min(title.chars().count() + 2, width - 4), Alignment::Left);
+            for (offset, character) in label.chars().enumerate() {
+                rectmanager.set_character(rect_id, 2 + offset as isize, 0, character)?;
+                rectmanager.set_character_style(rect_id, 2 + offset as isize, 0, Style { bold: true, ..Style::default() })?;
+            }
+        }
+
+        for (y, line) in lines.iter().enumerate() {
+            let y = y as isize + 1;
+            if y >= bottom - 1 {
+                break;
+            }
+            let fitted = fit_text(line, width.saturating_sub(4), Alignment::Left);
+            for (offset, character) in fitted.chars().enumerate() {
+                rectmanager.set_character(rect_id, 2 + offset as isize, y, character)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Draw "[ Label ]" buttons centered on the row above the bottom border of the top dialog
+    fn draw_buttons(&mut self, rectmanager: &mut RectManager, labels: &[&str], active: usize) -> Result<(), WreckedError> {
+        let dialog = match self.dialogs.last_mut() {
+            Some(dialog) => dialog,
+            None => {
+                return Ok(());
+            }
+        };
+        let (width, height) = rectmanager.get_rect_size(dialog.rect_id).unwrap_or((0, 0));
+        if height < 3 {
+            return Ok(());
+        }
+        let y = height as isize - 2;
+
+        let texts: Vec<String> = labels.iter().map(|label| format!("[ {} ]", label)).collect();
+        let total_width = texts.iter().map(|text| text.chars().count()).sum::<usize>() + texts.len().saturating_sub(1);
+        let mut x = (width as isize - total_width as isize) / 2;
+
+        dialog.buttons.clear();
+        for (i, text) in texts.iter().enumerate() {
+            let style = if i == active { self.active_button_style } else { self.button_style };
+            for (offset, character) in text.chars().enumerate() {
+                let position = x + offset as isize;
+                if position > 0 && position < width as isize - 1 {
+                    rectmanager.set_character(dialog.rect_id, position, y, character)?;
+                    rectmanager.set_character_style(dialog.rect_id, position, y, style)?;
+                }
+            }
+            let length = text.chars().count();
+            dialog.buttons.push((x, length));
+            x += length as isize + 1;
+        }
+
+        Ok(())
+    }
+}
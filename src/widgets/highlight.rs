@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use crate::{Style, Color};
+
+/// A styled run of characters in a line. start and end are character (not byte) indices.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub style: Style
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, style: Style) -> Span {
+        Span { start, end, style }
+    }
+}
+
+/// Splits a line of text into styled spans.
+/// Lines are highlighted independently of each other, so results can be cached by the line's content.
+/// # Example
+/// ```
+/// use wrecked::{Style, Color};
+/// use wrecked::widgets::{Highlighter, Span};
+///
+/// // Show lines that mention errors in red
+/// struct ErrorHighlighter;
+/// impl Highlighter for ErrorHighlighter {
+///     fn highlight_line(&self, line: &str) -> Vec<Span> {
+///         if line.contains("ERROR") {
+///             let style = Style { foreground_color: Some(Color::RED), ..Style::default() };
+///             vec![Span::new(0, line.chars().count(), style)]
+///         } else {
+///             Vec::new()
+///         }
+///     }
+/// }
+///
+/// assert_eq!(ErrorHighlighter.highlight_line("INFO ok"), vec![]);
+/// ```
+pub trait Highlighter {
+    fn highlight_line(&self, line: &str) -> Vec<Span>;
+}
+
+/// Get the style of each character of a line of `length` characters.
+/// Where spans overlap, the later span wins.
+pub fn get_character_styles(spans: &[Span], length: usize) -> Vec<Option<Style>> {
+    let mut styles = vec![None; length];
+    for span in spans.iter() {
+        for style in styles.iter_mut().take(span.end).skip(span.start) {
+            *style = Some(span.style);
+        }
+    }
+    styles
+}
+
+// Highlighted styles of lines, keyed by their text, used by the widgets that accept a Highlighter
+#[derive(Default)]
+pub(crate) struct HighlightCache {
+    highlighter: Option<Box<dyn Highlighter>>,
+    lines: HashMap<String, Vec<Option<Style>>>
+}
+
+impl HighlightCache {
+    // Keep the cache from growing without bound as lines are edited
+    const MAX_LINES: usize = 4096;
+
+    pub(crate) fn set_highlighter(&mut self, highlighter: Option<Box<dyn Highlighter>>) {
+        self.highlighter = highlighter;
+        self.lines.clear();
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.highlighter.is_some()
+    }
+
+    pub(crate) fn get_styles(&mut self, line: &str) -> Vec<Option<Style>> {
+        let highlighter = match &self.highlighter {
+            Some(highlighter) => highlighter,
+            None => {
+                return vec![None; line.chars().count()];
+            }
+        };
+
+        if let Some(styles) = self.lines.get(line) {
+            return styles.clone();
+        }
+
+        let styles = get_character_styles(&highlighter.highlight_line(line), line.chars().count());
+        if self.lines.len() >= HighlightCache::MAX_LINES {
+            self.lines.clear();
+        }
+        self.lines.insert(line.to_string(), styles.clone());
+
+        styles
+    }
+}
+
+fn colored(color: Color) -> Style {
+    Style {
+        foreground_color: Some(color),
+        ..Style::default()
+    }
+}
+
+fn is_number_start(character: char) -> bool {
+    character.is_ascii_digit() || character == '-'
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.parse::<f64>().is_ok()
+}
+
+// Find the end of a quoted string starting at `start`, allowing for escaped quotes
+fn find_string_end(characters: &[char], start: usize) -> usize {
+    let quote = characters[start];
+    let mut i = start + 1;
+    while i < characters.len() {
+        if characters[i] == '\\' {
+            i += 2;
+        } else if characters[i] == quote {
+            return i + 1;
+        } else {
+            i += 1;
+        }
+    }
+    characters.len()
+}
+
+/// Highlights JSON: keys, strings, numbers and literals (true, false, null).
+pub struct JsonHighlighter {
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub literal: Style,
+    pub punctuation: Style
+}
+
+impl Default for JsonHighlighter {
+    fn default() -> JsonHighlighter {
+        JsonHighlighter {
+            key: colored(Color::BLUE),
+            string: colored(Color::GREEN),
+            number: colored(Color::YELLOW),
+            literal: colored(Color::MAGENTA),
+            punctuation: Style::default()
+        }
+    }
+}
+
+impl Highlighter for JsonHighlighter {
+    fn highlight_line(&self, line: &str) -> Vec<Span> {
+        let characters: Vec<char> = line.chars().collect();
+        let mut spans = Vec::new();
+        let mut i = 0;
+
+        while i < characters.len() {
+            let character = characters[i];
+            if character == '"' {
+                let end = find_string_end(&characters, i);
+                // A string followed by a colon is a key
+                let is_key = characters[end ..].iter()
+                    .find(|c| !c.is_whitespace())
+                    .map(|c| *c == ':')
+                    .unwrap_or(false);
+
+                let style = if is_key { self.key } else { self.string };
+                spans.push(Span::new(i, end, style));
+                i = end;
+            } else if is_number_start(character) {
+                let mut end = i + 1;
+                while end < characters.len() && (characters[end].is_ascii_alphanumeric() || "+-.".contains(characters[end])) {
+                    end += 1;
+                }
+                let word: String = characters[i .. end].iter().collect();
+                if is_number(&word) {
+                    spans.push(Span::new(i, end, self.number));
+                }
+                i = end;
+            } else if character.is_ascii_alphabetic() {
+                let mut end = i + 1;
+                while end < characters.len() && characters[end].is_ascii_alphabetic() {
+                    end += 1;
+                }
+                let word: String = characters[i .. end].iter().collect();
+                if word == "true" || word == "false" || word == "null" {
+                    spans.push(Span::new(i, end, self.literal));
+                }
+                i = end;
+            } else {
+                if "{}[]:,".contains(character) && self.punctuation != Style::default() {
+                    spans.push(Span::new(i, i + 1, self.punctuation));
+                }
+                i += 1;
+            }
+        }
+
+        spans
+    }
+}
+
+/// Highlights INI and TOML-like files: [sections], key = value pairs and # or ; comments.
+pub struct IniHighlighter {
+    pub section: Style,
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub literal: Style,
+    pub comment: Style
+}
+
+impl Default for IniHighlighter {
+    fn default() -> IniHighlighter {
+        IniHighlighter {
+            section: Style {
+                bold: true,
+                ..colored(Color::BLUE)
+            },
+            key: colored(Color::CYAN),
+            string: colored(Color::GREEN),
+            number: colored(Color::YELLOW),
+            literal: colored(Color::MAGENTA),
+            comment: colored(Color::BRIGHTBLACK)
+        }
+    }
+}
+
+impl IniHighlighter {
+    fn highlight_value(&self, characters: &[char], start: usize, spans: &mut Vec<Span>) {
+        let mut i = start;
+        while i < characters.len() {
+            let character = characters[i];
+            if character == '"' || character == '\'' {
+                let end = find_string_end(characters, i);
+                spans.push(Span::new(i, end, self.string));
+                i = end;
+            } else if (character == '#' || character == ';') && (i == start || characters[i - 1].is_whitespace()) {
+                spans.push(Span::new(i, characters.len(), self.comment));
+                return;
+            } else if character.is_whitespace() || ",[]{}=".contains(character) {
+                i += 1;
+            } else {
+                let mut end = i + 1;
+                while end < characters.len() && !characters[end].is_whitespace() && !",[]{}".contains(characters[end]) {
+                    end += 1;
+                }
+                let word: String = characters[i .. end].iter().collect();
+                if is_number(&word) {
+                    spans.push(Span::new(i, end, self.number));
+                } else if word == "true" || word == "false" {
+                    spans.push(Span::new(i, end, self.literal));
+                }
+                i = end;
+            }
+        }
+    }
+}
+
+impl Highlighter for IniHighlighter {
+    fn highlight_line(&self, line: &str) -> Vec<Span> {
+        let characters: Vec<char> = line.chars().collect();
+        let mut spans = Vec::new();
+
+        let indent = characters.iter().take_while(|c| c.is_whitespace()).count();
+        match characters.get(indent) {
+            None => {}
+            Some('#') | Some(';') => {
+                spans.push(Span::new(indent, characters.len(), self.comment));
+            }
+            Some('[') => {
+                let end = match characters.iter().rposition(|c| *c == ']') {
+                    Some(position) => position + 1,
+                    None => characters.len()
+                };
+                spans.push(Span::new(indent, end, self.section));
+                self.highlight_value(&characters, end, &mut spans);
+            }
+            Some(_) => {
+                match characters.iter().position(|c| *c == '=' || *c == ':') {
+                    Some(separator) => {
+                        let mut key_end = separator;
+                        while key_end > indent && characters[key_end - 1].is_whitespace() {
+                            key_end -= 1;
+                        }
+                        spans.push(Span::new(indent, key_end, self.key));
+                        self.highlight_value(&characters, separator + 1, &mut spans);
+                    }
+                    None => {
+                        // A value continued from the previous line
+                        self.highlight_value(&characters, indent, &mut spans);
+                    }
+                }
+            }
+        }
+
+        spans
+    }
+}
+
+/// Highlights unified diffs and patches.
+pub struct DiffHighlighter {
+    pub header: Style,
+    pub hunk: Style,
+    pub added: Style,
+    pub removed: Style
+}
+
+impl Default for DiffHighlighter {
+    fn default() -> DiffHighlighter {
+        DiffHighlighter {
+            header: Style {
+                bold: true,
+                ..Style::default()
+            },
+            hunk: colored(Color::CYAN),
+            added: colored(Color::GREEN),
+            removed: colored(Color::RED)
+        }
+    }
+}
+
+impl Highlighter for DiffHighlighter {
+    fn highlight_line(&self, line: &str) -> Vec<Span> {
+        let length = line.chars().count();
+        let style = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") || line.starts_with("index ") {
+            Some(self.header)
+        } else if line.starts_with("@@") {
+            Some(self.hunk)
+        } else if line.starts_with('+') {
+            Some(self.added)
+        } else if line.starts_with('-') {
+            Some(self.removed)
+        } else {
+            None
+        };
+
+        match style {
+            Some(style) if length > 0 => vec![Span::new(0, length, style)],
+            _ => Vec::new()
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use std::cmp;
+use std::collections::BTreeSet;
+use crate::{RectManager, WreckedError, Style};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use super::get_local_position;
+use super::highlight::{Highlighter, HighlightCache};
+
+/// Provides the rows displayed by a ListView.
+/// Rows are only requested when they are visible, so the data can be as large as needed.
+pub trait ListDataSource {
+    /// The total number of rows
+    fn len(&self) -> usize;
+
+    /// The text of a single row
+    fn get_row(&self, index: usize) -> String;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ListDataSource for Vec<String> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get_row(&self, index: usize) -> String {
+        self[index].clone()
+    }
+}
+
+impl ListDataSource for Vec<&str> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get_row(&self, index: usize) -> String {
+        self[index].to_string()
+    }
+}
+
+/// A scrolling list of rows with a cursor and (optionally) multiple selected rows.
+/// All the rows are drawn into a single rect, which is only as big as the visible part of the list.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::ListView;
+/// use wrecked::input::{InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+///
+/// let rows: Vec<String> = (0 .. 100000).map(|i| format!("Process {}", i)).collect();
+/// let mut list = ListView::new(&mut rectmanager, ROOT, rows).ok().unwrap();
+/// rectmanager.resize(list.get_rect_id(), 20, 10);
+/// list.refresh(&mut rectmanager);
+///
+/// list.handle_event(&mut rectmanager, &InputEvent::key(Key::End));
+/// assert_eq!(list.get_cursor(), Some(99999));
+///
+/// rectmanager.kill();
+/// ```
+pub struct ListView<S: ListDataSource> {
+    rect_id: usize,
+    source: S,
+    cursor: usize,
+    // index of the first visible row
+    top: usize,
+    selection: BTreeSet<usize>,
+    multi_select: bool,
+    cursor_style: Style,
+    selection_style: Style,
+    highlight: HighlightCache
+}
+
+impl<S: ListDataSource> ListView<S> {
+    /// Create a ListView with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, source: S) -> Result<ListView<S>, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+
+        Ok(ListView {
+            rect_id,
+            source,
+            cursor: 0,
+            top: 0,
+            selection: BTreeSet::new(),
+            multi_select: false,
+            cursor_style: Style {
+                invert: true,
+                ..Style::default()
+            },
+            selection_style: Style {
+                bold: true,
+                ..Style::default()
+            },
+            highlight: HighlightCache::default()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_source(&self) -> &S {
+        &self.source
+    }
+
+    /// Access the data source in order to change it. Call refresh() afterwards.
+    pub fn get_source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Allow more than one row to be selected at a time.
+    /// When multi-select is off, the selected row is always the one under the cursor.
+    pub fn set_multi_select(&mut self, multi_select: bool) {
+        self.multi_select = multi_select;
+        self.selection.clear();
+    }
+
+    /// Set the style of the row under the cursor.
+    pub fn set_cursor_style(&mut self, style: Style) {
+        self.cursor_style = style;
+    }
+
+    /// Set the style of selected rows.
+    pub fn set_selection_style(&mut self, style: Style) {
+        self.selection_style = style;
+    }
+
+    /// Style rows with a Highlighter. The cursor and selection styles are used in place of it on those rows.
+    pub fn set_highlighter(&mut self, rectmanager: &mut RectManager, highlighter: Option<Box<dyn Highlighter>>) -> Result<(), WreckedError> {
+        self.highlight.set_highlighter(highlighter);
+        self.refresh(rectmanager)
+    }
+
+    /// Get the index of the row under the cursor. None if the list is empty.
+    pub fn get_cursor(&self) -> Option<usize> {
+        if self.source.is_empty() {
+            None
+        } else {
+            Some(self.cursor)
+        }
+    }
+
+    /// Get the index of the first visible row.
+    pub fn get_top(&self) -> usize {
+        self.top
+    }
+
+    /// Move the cursor, scrolling the list so that it stays visible.
+    pub fn set_cursor(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        self.cursor = cmp::min(index, self.source.len().saturating_sub(1));
+
+        let height = self.get_height(rectmanager);
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if height > 0 && self.cursor >= self.top + height {
+            self.top = self.cursor + 1 - height;
+        }
+
+        self.refresh(rectmanager)
+    }
+
+    /// Scroll the list without moving the cursor.
+    pub fn scroll_to(&mut self, rectmanager: &mut RectManager, top: usize) -> Result<(), WreckedError> {
+        let height = self.get_height(rectmanager);
+        self.top = cmp::min(top, self.source.len().saturating_sub(height));
+        self.refresh(rectmanager)
+    }
+
+    /// Get the indices of the selected rows, in order.
+    /// When multi-select is off, this is the row under the cursor.
+    pub fn get_selection(&self) -> Vec<usize> {
+        if self.multi_select {
+            self.selection.iter().copied().collect()
+        } else {
+            self.get_cursor().into_iter().collect()
+        }
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        if self.multi_select {
+            self.selection.contains(&index)
+        } else {
+            Some(index) == self.get_cursor()
+        }
+    }
+
+    pub fn select(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        if !self.multi_select {
+            self.set_cursor(rectmanager, index)
+        } else {
+            if index < self.source.len() {
+                self.selection.insert(index);
+            }
+            self.refresh(rectmanager)
+        }
+    }
+
+    pub fn deselect(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        self.selection.remove(&index);
+        self.refresh(rectmanager)
+    }
+
+    pub fn clear_selection(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.selection.clear();
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the visible rows. Needs to be called after the data source or the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let length = self.source.len();
+        self.cursor = cmp::min(self.cursor, length.saturating_sub(1));
+        self.selection.retain(|index| *index < length);
+
+        let (width, height) = match rectmanager.get_rect_size(self.rect_id) {
+            Some(size) => size,
+            None => {
+                return Err(WreckedError::NotFound(self.rect_id));
+            }
+        };
+        self.top = cmp::min(self.top, length.saturating_sub(height));
+
+        for line in 0 .. height {
+            let index = self.top + line;
+            let y = line as isize;
+            if index < length {
+                let text = self.source.get_row(index);
+                let mut characters = text.chars();
+                for x in 0 .. width {
+                    match characters.next() {
+                        Some(character) => {
+                            rectmanager.set_character(self.rect_id, x as isize, y, character)?;
+                        }
+                        None => {
+                            rectmanager.unset_character(self.rect_id, x as isize, y)?;
+                        }
+                    }
+                }
+
+                let styles = if index == self.cursor {
+                    vec![Some(self.cursor_style); width]
+                } else if self.multi_select && self.selection.contains(&index) {
+                    vec![Some(self.selection_style); width]
+                } else {
+                    self.highlight.get_styles(&text)
+                };
+                self.set_line_styles(rectmanager, y, width, &styles)?;
+            } else {
+                for x in 0 .. width {
+                    rectmanager.unset_character(self.rect_id, x as isize, y)?;
+                }
+                self.set_line_styles(rectmanager, y, width, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle keyboard navigation and mouse clicks.
+    /// Returns true if the event was used by the list.
+    ///
+    /// * Up/Down/PageUp/PageDown/Home/End move the cursor
+    /// * With multi-select on, Space toggles the selection of the row under the cursor, and shift extends the selection as the cursor moves
+    /// * Clicking a row moves the cursor to it. Ctrl-click toggles its selection and shift-click selects everything in between
+    /// * The mouse wheel scrolls the list
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        let height = self.get_height(rectmanager);
+        let last = self.source.len().saturating_sub(1);
+
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                let target = match key {
+                    Key::Up => Some(self.cursor.saturating_sub(1)),
+                    Key::Down => Some(cmp::min(self.cursor + 1, last)),
+                    Key::PageUp => Some(self.cursor.saturating_sub(cmp::max(height, 1))),
+                    Key::PageDown => Some(cmp::min(self.cursor + cmp::max(height, 1), last)),
+                    Key::Home => Some(0),
+                    Key::End => Some(last),
+                    _ => None
+                };
+
+                match target {
+                    Some(target) => {
+                        if self.multi_select && modifiers.shift {
+                            self.select_range(self.cursor, target);
+                        }
+                        self.set_cursor(rectmanager, target)?;
+                        Ok(true)
+                    }
+                    None => {
+                        if *key == Key::Char(' ') && self.multi_select && !self.source.is_empty() {
+                            self.toggle_selection(self.cursor);
+                            self.refresh(rectmanager)?;
+                            Ok(true)
+                        } else {
+                            Ok(false)
+                        }
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                let line = match self.get_line_at(rectmanager, mouse.x, mouse.y) {
+                    Some(line) => line,
+                    None => {
+                        return Ok(false);
+                    }
+                };
+
+                match (mouse.action, mouse.button) {
+                    (MouseAction::Press, MouseButton::Left) => {
+                        let index = self.top + line;
+                        if index < self.source.len() {
+                            if self.multi_select && mouse.modifiers.ctrl {
+                                self.toggle_selection(index);
+                            } else if self.multi_select && mouse.modifiers.shift {
+                                self.select_range(self.cursor, index);
+                            }
+                            self.set_cursor(rectmanager, index)?;
+                        }
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelUp) => {
+                        self.scroll_to(rectmanager, self.top.saturating_sub(3))?;
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelDown) => {
+                        self.scroll_to(rectmanager, self.top + 3)?;
+                        Ok(true)
+                    }
+                    _ => {
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+
+    fn toggle_selection(&mut self, index: usize) {
+        if !self.selection.remove(&index) {
+            self.selection.insert(index);
+        }
+    }
+
+    fn select_range(&mut self, from: usize, to: usize) {
+        for index in cmp::min(from, to) ..= cmp::max(from, to) {
+            self.selection.insert(index);
+        }
+    }
+
+    fn set_line_styles(&self, rectmanager: &mut RectManager, y: isize, width: usize, styles: &[Option<Style>]) -> Result<(), WreckedError> {
+        for x in 0 .. width {
+            match styles.get(x).copied().flatten() {
+                Some(style) => {
+                    rectmanager.set_character_style(self.rect_id, x as isize, y, style)?;
+                }
+                None => {
+                    rectmanager.unset_character_style(self.rect_id, x as isize, y)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_height(&self, rectmanager: &RectManager) -> usize {
+        match rectmanager.get_rect_size(self.rect_id) {
+            Some((_, height)) => height,
+            None => 0
+        }
+    }
+
+    // Convert an absolute position to a visible line of the list
+    fn get_line_at(&self, rectmanager: &RectManager, x: isize, y: isize) -> Option<usize> {
+        get_local_position(rectmanager, self.rect_id, x, y).map(|(_, line)| line)
+    }
+}
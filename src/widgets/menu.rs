@@ -0,0 +1,913 @@
+use std::cmp;
+use crate::{RectManager, WreckedError, Style, Color, ROOT};
+use crate::input::{InputEvent, Key, Modifiers, MouseAction, MouseButton};
+use crate::layout::{Anchor, HorizontalAnchor, VerticalAnchor};
+use super::{draw_box, get_local_position};
+
+/// An entry in a menu.
+/// A `&` in the label marks the character after it as the item's mnemonic: pressing that key while the menu is open
+/// chooses the item (`&&` is a literal ampersand).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MenuItem {
+    pub label: String,
+    /// Returned in MenuEvent::Activated when the item is chosen
+    pub action: String,
+    /// A key that chooses the item without opening the menu. It's shown to the right of the label.
+    pub accelerator: Option<(Key, Modifiers)>,
+    pub enabled: bool,
+    pub separator: bool,
+    /// Items of a submenu opened from this item. An item with a submenu has no action of its own.
+    pub submenu: Vec<MenuItem>
+}
+
+impl MenuItem {
+    pub fn new(label: &str, action: &str) -> MenuItem {
+        MenuItem {
+            label: label.to_string(),
+            action: action.to_string(),
+            accelerator: None,
+            enabled: true,
+            separator: false,
+            submenu: Vec::new()
+        }
+    }
+
+    pub fn with_accelerator(label: &str, action: &str, key: Key, modifiers: Modifiers) -> MenuItem {
+        MenuItem {
+            accelerator: Some((key, modifiers)),
+            ..MenuItem::new(label, action)
+        }
+    }
+
+    /// A horizontal line between groups of items.
+    pub fn separator() -> MenuItem {
+        MenuItem {
+            separator: true,
+            ..MenuItem::new("", "")
+        }
+    }
+
+    pub fn submenu(label: &str, items: Vec<MenuItem>) -> MenuItem {
+        MenuItem {
+            submenu: items,
+            ..MenuItem::new(label, "")
+        }
+    }
+
+    fn is_selectable(&self) -> bool {
+        self.enabled && !self.separator
+    }
+}
+
+/// What happened to an event passed to a MenuBar or ContextMenu.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum MenuEvent {
+    /// The event wasn't used; it can be handled by the rest of the interface
+    Ignored,
+    /// The event was used or swallowed by an open menu
+    Captured,
+    /// An item was chosen, by its action. Any open menus have been closed.
+    Activated(String),
+    /// The menus were closed without choosing anything
+    Closed
+}
+
+// Split "&File" into ("File", Some(0))
+fn parse_label(label: &str) -> (String, Option<usize>) {
+    let mut text = String::new();
+    let mut mnemonic = None;
+    let mut characters = label.chars();
+    let mut length = 0;
+    while let Some(character) = characters.next() {
+        if character == '&' {
+            match characters.next() {
+                Some('&') => {
+                    text.push('&');
+                }
+                Some(next) => {
+                    if mnemonic.is_none() {
+                        mnemonic = Some(length);
+                    }
+                    text.push(next);
+                }
+                None => {
+                    break;
+                }
+            }
+        } else {
+            text.push(character);
+        }
+        length += 1;
+    }
+    (text, mnemonic)
+}
+
+fn get_mnemonic(label: &str) -> Option<char> {
+    let (text, mnemonic) = parse_label(label);
+    mnemonic.and_then(|index| text.chars().nth(index)).map(|character| character.to_ascii_lowercase())
+}
+
+fn format_accelerator(key: Key, modifiers: Modifiers) -> String {
+    let mut text = String::new();
+    if modifiers.ctrl {
+        text += "Ctrl+";
+    }
+    if modifiers.alt {
+        text += "Alt+";
+    }
+    if modifiers.shift {
+        text += "Shift+";
+    }
+    match key {
+        Key::Char(' ') => text += "Space",
+        Key::Char(character) => text.extend(character.to_uppercase()),
+        Key::F(number) => text += &format!("F{}", number),
+        _ => text += &format!("{:?}", key)
+    }
+    text
+}
+
+fn find_accelerator(items: &[MenuItem], key: Key, modifiers: Modifiers) -> Option<&MenuItem> {
+    for item in items.iter() {
+        if !item.is_selectable() {
+            continue;
+        }
+        if item.accelerator == Some((key, modifiers)) && item.submenu.is_empty() {
+            return Some(item);
+        }
+        if let Some(found) = find_accelerator(&item.submenu, key, modifiers) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn set_item_enabled(items: &mut [MenuItem], action: &str, enabled: bool) {
+    for item in items.iter_mut() {
+        if !item.separator && item.submenu.is_empty() && item.action == action {
+            item.enabled = enabled;
+        }
+        set_item_enabled(&mut item.submenu, action, enabled);
+    }
+}
+
+// Place a span of `size` at `start`, or ending at `flipped_end` if it would overflow `limit`
+fn place(start: isize, flipped_end: isize, size: usize, limit: usize) -> isize {
+    let size = size as isize;
+    let limit = limit as isize;
+    if start + size <= limit {
+        start
+    } else if flipped_end - size >= 0 {
+        flipped_end - size
+    } else {
+        cmp::max(limit - size, 0)
+    }
+}
+
+struct MenuLevel {
+    rect_id: usize,
+    items: Vec<MenuItem>,
+    highlighted: Option<usize>
+}
+
+// An open menu and the submenus opened from it.
+// Each is drawn in its own rect attached to ROOT so it's above everything else.
+struct MenuPopup {
+    levels: Vec<MenuLevel>,
+    // Where the terminal's cursor was before the menu was opened
+    saved_cursor: Option<(usize, isize, isize)>,
+    highlight_style: Style,
+    disabled_style: Style
+}
+
+impl MenuPopup {
+    fn new() -> MenuPopup {
+        MenuPopup {
+            levels: Vec::new(),
+            saved_cursor: None,
+            highlight_style: Style {
+                invert: true,
+                ..Style::default()
+            },
+            disabled_style: Style {
+                foreground_color: Some(Color::BRIGHTBLACK),
+                ..Style::default()
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        !self.levels.is_empty()
+    }
+
+    // Open a menu at x.0, y.0, or ending at x.1, y.1 on whichever axis it would overflow ROOT
+    fn open_level(&mut self, rectmanager: &mut RectManager, items: Vec<MenuItem>, x: (isize, isize), y: (isize, isize)) -> Result<usize, WreckedError> {
+        if self.levels.is_empty() {
+            self.saved_cursor = rectmanager.get_cursor();
+            rectmanager.hide_cursor();
+        }
+
+        let (root_width, root_height) = rectmanager.get_rect_size(ROOT).unwrap_or((0, 0));
+        let (width, height) = MenuPopup::get_level_size(&items);
+        let (width, height) = (cmp::min(width, root_width), cmp::min(height, root_height));
+
+        let rect_id = rectmanager.new_rect(ROOT)?;
+        rectmanager.resize(rect_id, width, height)?;
+        rectmanager.set_position(
+            rect_id,
+            place(x.0, x.1, width, root_width),
+            place(y.0, y.1, height, root_height)
+        )?;
+
+        let highlighted = items.iter().position(|item| item.is_selectable());
+        self.levels.push(MenuLevel {
+            rect_id,
+            items,
+            highlighted
+        });
+        self.draw_level(rectmanager, self.levels.len() - 1)?;
+
+        Ok(rect_id)
+    }
+
+    fn close_level(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        if let Some(level) = self.levels.pop() {
+            rectmanager.delete_rect(level.rect_id)?;
+        }
+
+        if self.levels.is_empty() {
+            match self.saved_cursor.take() {
+                Some((rect_id, x, y)) if rectmanager.has_rect(rect_id) => {
+                    rectmanager.set_cursor(rect_id, x, y)?;
+                }
+                _ => ()
+            }
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        while self.is_open() {
+            self.close_level(rectmanager)?;
+        }
+        Ok(())
+    }
+
+    // Widths of the label and accelerator columns, and whether there's a column for submenu arrows
+    fn get_columns(items: &[MenuItem]) -> (usize, usize, bool) {
+        let mut label_width = 0;
+        let mut accelerator_width = 0;
+        let mut has_submenu = false;
+        for item in items.iter() {
+            label_width = cmp::max(label_width, parse_label(&item.label).0.chars().count());
+            if let Some((key, modifiers)) = item.accelerator {
+                accelerator_width = cmp::max(accelerator_width, format_accelerator(key, modifiers).chars().count());
+            }
+            has_submenu |= !item.submenu.is_empty();
+        }
+        (label_width, accelerator_width, has_submenu)
+    }
+
+    fn get_level_size(items: &[MenuItem]) -> (usize, usize) {
+        let (label_width, accelerator_width, has_submenu) = MenuPopup::get_columns(items);
+        let mut width = label_width + 4;
+        if accelerator_width > 0 {
+            width += accelerator_width + 2;
+        }
+        if has_submenu {
+            width += 2;
+        }
+        (width, items.len() + 2)
+    }
+
+    fn draw_level(&self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        let level = &self.levels[index];
+        let rect_id = level.rect_id;
+        let (width, height) = rectmanager.get_rect_size(rect_id).unwrap_or((0, 0));
+        if width < 2 || height < 2 {
+            return Ok(());
+        }
+        let (right, bottom) = (width as isize - 1, height as isize - 1);
+        let (label_width, accelerator_width, has_submenu) = MenuPopup::get_columns(&level.items);
+
+        rectmanager.clear_characters(rect_id)?;
+        rectmanager.clear_character_styles(rect_id)?;
+        draw_box(rectmanager, rect_id)?;
+
+        for (i, item) in level.items.iter().enumerate() {
+            let y = i as isize + 1;
+            if y >= bottom {
+                break;
+            }
+
+            if item.separator {
+                rectmanager.set_character(rect_id, 0, y, '├')?;
+                rectmanager.set_character(rect_id, right, y, '┤')?;
+                for x in 1 .. right {
+                    rectmanager.set_character(rect_id, x, y, '─')?;
+                }
+                continue;
+            }
+
+            let (label, mnemonic) = parse_label(&item.label);
+            let mut text = format!(" {}{}", label, " ".repeat(label_width - label.chars().count()));
+            if accelerator_width > 0 {
+                let accelerator = match item.accelerator {
+                    Some((key, modifiers)) => format_accelerator(key, modifiers),
+                    None => String::new()
+                };
+                text += &format!("  {:>1$}", accelerator, accelerator_width);
+            }
+            if has_submenu {
+                text += if item.submenu.is_empty() { "  " } else { " ▸" };
+            }
+            text.push(' ');
+
+            let style = if level.highlighted == Some(i) {
+                self.highlight_style
+            } else if !item.enabled {
+                self.disabled_style
+            } else {
+                Style::default()
+            };
+
+            for (offset, character) in text.chars().enumerate() {
+                let x = offset as isize + 1;
+                if x >= right {
+                    break;
+                }
+                let character_style = if mnemonic == Some(offset.wrapping_sub(1)) && item.enabled {
+                    Style { underline: true, ..style }
+                } else {
+                    style
+                };
+                rectmanager.set_character(rect_id, x, y, character)?;
+                if character_style != Style::default() {
+                    rectmanager.set_character_style(rect_id, x, y, character_style)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_highlighted(&mut self, rectmanager: &mut RectManager, index: Option<usize>) -> Result<(), WreckedError> {
+        if let Some(level) = self.levels.last_mut() {
+            if level.highlighted != index {
+                level.highlighted = index;
+                self.draw_level(rectmanager, self.levels.len() - 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Move the highlight to the next selectable item in the given direction, wrapping around
+    fn move_highlight(&mut self, rectmanager: &mut RectManager, delta: isize) -> Result<(), WreckedError> {
+        let level = match self.levels.last() {
+            Some(level) => level,
+            None => {
+                return Ok(());
+            }
+        };
+
+        let count = level.items.len() as isize;
+        let mut index = match level.highlighted {
+            Some(highlighted) => highlighted as isize,
+            None if delta > 0 => -1,
+            None => count
+        };
+        for _ in 0 .. count {
+            index = (index + delta).rem_euclid(count);
+            if level.items[index as usize].is_selectable() {
+                return self.set_highlighted(rectmanager, Some(index as usize));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn open_submenu(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (rect_id, index, items) = match self.levels.last() {
+            Some(MenuLevel { rect_id, highlighted: Some(index), items }) if !items[*index].submenu.is_empty() => {
+                (*rect_id, *index, items[*index].submenu.clone())
+            }
+            _ => {
+                return Ok(());
+            }
+        };
+
+        let (offset_x, offset_y) = rectmanager.get_absolute_offset(rect_id).unwrap_or((0, 0));
+        let width = rectmanager.get_rect_width(rect_id) as isize;
+        let row = offset_y + index as isize;
+        self.open_level(rectmanager, items, (offset_x + width, offset_x), (row, row + 2))?;
+        Ok(())
+    }
+
+    // Choose the highlighted item of the innermost menu
+    fn activate(&mut self, rectmanager: &mut RectManager) -> Result<MenuEvent, WreckedError> {
+        let item = match self.levels.last() {
+            Some(MenuLevel { highlighted: Some(index), items, .. }) => items[*index].clone(),
+            _ => {
+                return Ok(MenuEvent::Captured);
+            }
+        };
+
+        if !item.is_selectable() {
+            Ok(MenuEvent::Captured)
+        } else if !item.submenu.is_empty() {
+            self.open_submenu(rectmanager)?;
+            Ok(MenuEvent::Captured)
+        } else {
+            self.close(rectmanager)?;
+            Ok(MenuEvent::Activated(item.action))
+        }
+    }
+
+    // Find the menu level and item under an absolute position
+    fn get_item_at(&self, rectmanager: &RectManager, x: isize, y: isize) -> Option<(usize, Option<usize>)> {
+        for (depth, level) in self.levels.iter().enumerate().rev() {
+            if let Some((x, y)) = get_local_position(rectmanager, level.rect_id, x, y) {
+                let width = rectmanager.get_rect_width(level.rect_id);
+                // The border around the items isn't part of any of them
+                let item = if y > 0 && y <= level.items.len() && x > 0 && x < width - 1 {
+                    Some(y - 1)
+                } else {
+                    None
+                };
+                return Some((depth, item));
+            }
+        }
+        None
+    }
+
+    // Handle an event while open. Keys that aren't used return Ignored so the owner can handle them.
+    fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<MenuEvent, WreckedError> {
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                match key {
+                    Key::Up => {
+                        self.move_highlight(rectmanager, -1)?;
+                        Ok(MenuEvent::Captured)
+                    }
+                    Key::Down => {
+                        self.move_highlight(rectmanager, 1)?;
+                        Ok(MenuEvent::Captured)
+                    }
+                    Key::Right => {
+                        let has_submenu = match self.levels.last() {
+                            Some(MenuLevel { highlighted: Some(index), items, .. }) => !items[*index].submenu.is_empty(),
+                            _ => false
+                        };
+                        if has_submenu {
+                            self.open_submenu(rectmanager)?;
+                            Ok(MenuEvent::Captured)
+                        } else {
+                            Ok(MenuEvent::Ignored)
+                        }
+                    }
+                    Key::Left | Key::Escape if self.levels.len() > 1 => {
+                        self.close_level(rectmanager)?;
+                        Ok(MenuEvent::Captured)
+                    }
+                    Key::Left => {
+                        Ok(MenuEvent::Ignored)
+                    }
+                    Key::Enter | Key::Char(' ') => {
+                        self.activate(rectmanager)
+                    }
+                    Key::Escape => {
+                        self.close(rectmanager)?;
+                        Ok(MenuEvent::Closed)
+                    }
+                    Key::Char(character) if !modifiers.ctrl && !modifiers.alt => {
+                        let character = character.to_ascii_lowercase();
+                        let found = self.levels.last().and_then(|level| {
+                            level.items.iter().position(|item| {
+                                item.is_selectable() && get_mnemonic(&item.label) == Some(character)
+                            })
+                        });
+                        match found {
+                            Some(index) => {
+                                self.set_highlighted(rectmanager, Some(index))?;
+                                self.activate(rectmanager)
+                            }
+                            None => {
+                                Ok(MenuEvent::Ignored)
+                            }
+                        }
+                    }
+                    _ => {
+                        Ok(MenuEvent::Ignored)
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                match self.get_item_at(rectmanager, mouse.x, mouse.y) {
+                    Some((depth, item)) => {
+                        let selectable = item.filter(|index| !self.levels[depth].items[*index].separator);
+                        match (mouse.action, selectable) {
+                            (MouseAction::Press, Some(index)) | (MouseAction::Move, Some(index)) | (MouseAction::Drag, Some(index)) => {
+                                // Close any submenus opened from other items
+                                while self.levels.len() > depth + 1 {
+                                    let opened_from = self.levels[depth].highlighted;
+                                    if opened_from == Some(index) && mouse.action != MouseAction::Press {
+                                        break;
+                                    }
+                                    self.close_level(rectmanager)?;
+                                }
+                                self.set_highlighted(rectmanager, Some(index))?;
+
+                                if mouse.action == MouseAction::Press && mouse.button == MouseButton::Left && self.levels.len() == depth + 1 {
+                                    self.activate(rectmanager)
+                                } else {
+                                    Ok(MenuEvent::Captured)
+                                }
+                            }
+                            _ => {
+                                Ok(MenuEvent::Captured)
+                            }
+                        }
+                    }
+                    None => {
+                        if mouse.action == MouseAction::Press {
+                            self.close(rectmanager)?;
+                            Ok(MenuEvent::Closed)
+                        } else {
+                            Ok(MenuEvent::Captured)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A row of menu titles, each opening a dropdown menu.
+///
+/// * Alt and a title's mnemonic, or F10 for the first menu, opens a menu from the keyboard
+/// * Left/Right move between menus, Up/Down between items, and Enter chooses an item
+/// * Escape or clicking outside the menus closes them
+/// * Item accelerators work whether or not a menu is open
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::{MenuBar, MenuItem, MenuEvent};
+/// use wrecked::input::{InputEvent, Key, Modifiers};
+/// let mut rectmanager = RectManager::new();
+/// rectmanager.resize(ROOT, 80, 24);
+///
+/// let mut menubar = MenuBar::new(&mut rectmanager, ROOT).ok().unwrap();
+/// menubar.add_menu(&mut rectmanager, "&File", vec![
+///     MenuItem::new("&Open", "open"),
+///     MenuItem::with_accelerator("&Save", "save", Key::Char('s'), Modifiers { ctrl: true, ..Modifiers::none() }),
+///     MenuItem::separator(),
+///     MenuItem::new("&Quit", "quit")
+/// ]);
+///
+/// let result = menubar.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('s'))).ok().unwrap();
+/// assert_eq!(result, MenuEvent::Activated("save".to_string()));
+///
+/// menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::F(10)));
+/// assert_eq!(menubar.get_open_menu(), Some(0));
+/// let result = menubar.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('q'))).ok().unwrap();
+/// assert_eq!(result, MenuEvent::Activated("quit".to_string()));
+/// assert!(!menubar.is_open());
+///
+/// rectmanager.kill();
+/// ```
+pub struct MenuBar {
+    rect_id: usize,
+    // (title, items)
+    menus: Vec<(String, Vec<MenuItem>)>,
+    open: Option<usize>,
+    popup: MenuPopup,
+    // First column and width of each title in the bar, as of the last refresh
+    title_positions: Vec<(isize, usize)>
+}
+
+impl MenuBar {
+    /// Create a MenuBar with a new rect attached to the given parent.
+    /// The bar is one row high and as wide as the parent, which it follows when resized.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<MenuBar, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let width = rectmanager.get_rect_width(parent_id);
+        rectmanager.resize(rect_id, width, 1)?;
+
+        let mut anchor = Anchor::new(HorizontalAnchor::Left, VerticalAnchor::Top);
+        anchor.stretch_horizontal = true;
+        rectmanager.set_anchor(rect_id, anchor)?;
+
+        Ok(MenuBar {
+            rect_id,
+            menus: Vec::new(),
+            open: None,
+            popup: MenuPopup::new(),
+            title_positions: Vec::new()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Add a menu to the end of the bar, returning its index.
+    pub fn add_menu(&mut self, rectmanager: &mut RectManager, title: &str, items: Vec<MenuItem>) -> Result<usize, WreckedError> {
+        self.menus.push((title.to_string(), items));
+        self.refresh(rectmanager)?;
+        Ok(self.menus.len() - 1)
+    }
+
+    /// Enable or disable every item with the given action. Takes effect the next time its menu is opened.
+    pub fn set_item_enabled(&mut self, action: &str, enabled: bool) {
+        for (_, items) in self.menus.iter_mut() {
+            set_item_enabled(items, action, enabled);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Get the index of the open menu.
+    pub fn get_open_menu(&self) -> Option<usize> {
+        self.open
+    }
+
+    /// Open the menu at the given index below its title, closing any other.
+    pub fn open_menu(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        if index >= self.menus.len() {
+            return Ok(());
+        }
+        self.popup.close(rectmanager)?;
+        self.open = Some(index);
+        self.refresh(rectmanager)?;
+
+        let (offset_x, offset_y) = rectmanager.get_absolute_offset(self.rect_id).unwrap_or((0, 0));
+        let (start, length) = self.title_positions[index];
+        let x = offset_x + start;
+        self.popup.open_level(rectmanager, self.menus[index].1.clone(), (x, x + length as isize), (offset_y + 1, offset_y))?;
+        Ok(())
+    }
+
+    /// Close the open menu, along with any submenus.
+    pub fn close(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.popup.close(rectmanager)?;
+        self.open = None;
+        self.refresh(rectmanager)
+    }
+
+    /// Set the style of the open title and of highlighted items.
+    pub fn set_highlight_style(&mut self, style: Style) {
+        self.popup.highlight_style = style;
+    }
+
+    pub fn set_disabled_style(&mut self, style: Style) {
+        self.popup.disabled_style = style;
+    }
+
+    /// Redraw the titles. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let width = rectmanager.get_rect_width(self.rect_id) as isize;
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.clear_character_styles(self.rect_id)?;
+
+        self.title_positions.clear();
+        let mut x = 0;
+        for (i, (title, _)) in self.menus.iter().enumerate() {
+            let (label, mnemonic) = parse_label(title);
+            let length = label.chars().count() + 2;
+            self.title_positions.push((x, length));
+
+            let style = if self.open == Some(i) {
+                self.popup.highlight_style
+            } else {
+                Style::default()
+            };
+            for (offset, character) in format!(" {} ", label).chars().enumerate() {
+                let position = x + offset as isize;
+                if position >= width {
+                    break;
+                }
+                let character_style = if mnemonic == Some(offset.wrapping_sub(1)) {
+                    Style { underline: true, ..style }
+                } else {
+                    style
+                };
+                rectmanager.set_character(self.rect_id, position, 0, character)?;
+                if character_style != Style::default() {
+                    rectmanager.set_character_style(self.rect_id, position, 0, character_style)?;
+                }
+            }
+
+            x += length as isize;
+        }
+
+        Ok(())
+    }
+
+    /// Handle opening, navigating and choosing from menus, and item accelerators.
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<MenuEvent, WreckedError> {
+        let result = match event {
+            InputEvent::Key(key, modifiers) => {
+                match self.open {
+                    Some(open) => {
+                        match self.popup.handle_event(rectmanager, event)? {
+                            MenuEvent::Ignored => {
+                                let count = self.menus.len();
+                                match key {
+                                    Key::Left => {
+                                        self.open_menu(rectmanager, (open + count - 1) % count)?;
+                                        MenuEvent::Captured
+                                    }
+                                    Key::Right => {
+                                        self.open_menu(rectmanager, (open + 1) % count)?;
+                                        MenuEvent::Captured
+                                    }
+                                    _ => {
+                                        match self.activate_accelerator(rectmanager, *key, *modifiers)? {
+                                            MenuEvent::Ignored => MenuEvent::Captured,
+                                            result => result
+                                        }
+                                    }
+                                }
+                            }
+                            result => result
+                        }
+                    }
+                    None => {
+                        let opened = match key {
+                            Key::Char(character) if modifiers.alt && !modifiers.ctrl => {
+                                let character = character.to_ascii_lowercase();
+                                self.menus.iter().position(|(title, _)| get_mnemonic(title) == Some(character))
+                            }
+                            Key::F(10) if modifiers.is_none() && !self.menus.is_empty() => Some(0),
+                            _ => None
+                        };
+
+                        match opened {
+                            Some(index) => {
+                                self.open_menu(rectmanager, index)?;
+                                MenuEvent::Captured
+                            }
+                            None => {
+                                self.activate_accelerator(rectmanager, *key, *modifiers)?
+                            }
+                        }
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                let on_bar = match get_local_position(rectmanager, self.rect_id, mouse.x, mouse.y) {
+                    Some((x, 0)) if !(self.open.is_some() && mouse.action == MouseAction::Release) => Some(x as isize),
+                    _ => None
+                };
+
+                if let Some(x) = on_bar {
+                    let clicked = self.title_positions.iter().position(|(start, length)| {
+                        x >= *start && x < *start + *length as isize
+                    });
+
+                    match (mouse.action, clicked) {
+                        (MouseAction::Press, Some(index)) if mouse.button == MouseButton::Left => {
+                            if self.open == Some(index) {
+                                self.close(rectmanager)?;
+                                MenuEvent::Closed
+                            } else {
+                                self.open_menu(rectmanager, index)?;
+                                MenuEvent::Captured
+                            }
+                        }
+                        (MouseAction::Move, Some(index)) | (MouseAction::Drag, Some(index)) if self.open.is_some() => {
+                            if self.open != Some(index) {
+                                self.open_menu(rectmanager, index)?;
+                            }
+                            MenuEvent::Captured
+                        }
+                        (MouseAction::Press, _) if self.open.is_some() => {
+                            self.close(rectmanager)?;
+                            MenuEvent::Closed
+                        }
+                        _ => {
+                            if self.open.is_some() {
+                                MenuEvent::Captured
+                            } else {
+                                MenuEvent::Ignored
+                            }
+                        }
+                    }
+                } else if self.open.is_some() {
+                    self.popup.handle_event(rectmanager, event)?
+                } else {
+                    MenuEvent::Ignored
+                }
+            }
+        };
+
+        // The popup closes itself when an item is chosen or it's dismissed
+        if self.open.is_some() && !self.popup.is_open() {
+            self.open = None;
+            self.refresh(rectmanager)?;
+        }
+
+        Ok(result)
+    }
+
+    fn activate_accelerator(&mut self, rectmanager: &mut RectManager, key: Key, modifiers: Modifiers) -> Result<MenuEvent, WreckedError> {
+        let action = self.menus.iter()
+            .find_map(|(_, items)| find_accelerator(items, key, modifiers))
+            .map(|item| item.action.clone());
+
+        match action {
+            Some(action) => {
+                if self.open.is_some() {
+                    self.close(rectmanager)?;
+                }
+                Ok(MenuEvent::Activated(action))
+            }
+            None => {
+                Ok(MenuEvent::Ignored)
+            }
+        }
+    }
+}
+
+/// A menu opened at a point, usually where the mouse was right-clicked.
+/// It opens to the left of and/or above the point when there isn't room for it to the right and below.
+/// While it's open, it captures all input.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::{ContextMenu, MenuItem, MenuEvent};
+/// use wrecked::input::{InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+/// rectmanager.resize(ROOT, 80, 24);
+///
+/// let mut context_menu = ContextMenu::new();
+/// context_menu.open(&mut rectmanager, 10, 5, vec![
+///     MenuItem::new("Cut", "cut"),
+///     MenuItem { enabled: false, ..MenuItem::new("Paste", "paste") },
+///     MenuItem::new("Select All", "select_all")
+/// ]);
+///
+/// // The disabled item is skipped
+/// context_menu.handle_event(&mut rectmanager, &InputEvent::key(Key::Down));
+/// let result = context_menu.handle_event(&mut rectmanager, &InputEvent::key(Key::Enter)).ok().unwrap();
+/// assert_eq!(result, MenuEvent::Activated("select_all".to_string()));
+///
+/// rectmanager.kill();
+/// ```
+pub struct ContextMenu {
+    popup: MenuPopup
+}
+
+impl Default for ContextMenu {
+    fn default() -> ContextMenu {
+        ContextMenu::new()
+    }
+}
+
+impl ContextMenu {
+    pub fn new() -> ContextMenu {
+        ContextMenu {
+            popup: MenuPopup::new()
+        }
+    }
+
+    /// Open the menu with its corner at the given absolute position, closing it first if it's already open.
+    /// Returns the id of the menu's rect.
+    pub fn open(&mut self, rectmanager: &mut RectManager, x: isize, y: isize, items: Vec<MenuItem>) -> Result<usize, WreckedError> {
+        self.popup.close(rectmanager)?;
+        self.popup.open_level(rectmanager, items, (x, x + 1), (y, y + 1))
+    }
+
+    /// Close the menu, along with any submenus.
+    pub fn close(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.popup.close(rectmanager)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.popup.is_open()
+    }
+
+    /// Set the style of highlighted items.
+    pub fn set_highlight_style(&mut self, style: Style) {
+        self.popup.highlight_style = style;
+    }
+
+    pub fn set_disabled_style(&mut self, style: Style) {
+        self.popup.disabled_style = style;
+    }
+
+    /// Handle navigating and choosing items. Returns Ignored only when the menu isn't open.
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<MenuEvent, WreckedError> {
+        if !self.popup.is_open() {
+            return Ok(MenuEvent::Ignored);
+        }
+
+        match self.popup.handle_event(rectmanager, event)? {
+            MenuEvent::Ignored => Ok(MenuEvent::Captured),
+            result => Ok(result)
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Reusable components built on top of rects.
+//! Widgets don't own the RectManager; each method that needs to draw takes it as an argument.
+
+use crate::{RectManager, WreckedError};
+
+pub mod highlight;
+pub mod list;
+pub mod table;
+pub mod text_input;
+pub mod text_area;
+pub mod tabs;
+pub mod dialog;
+pub mod menu;
+pub mod tree;
+pub mod progress;
+pub mod canvas;
+pub mod chart;
+#[cfg(unix)]
+pub mod terminal;
+pub use highlight::{Highlighter, Span, JsonHighlighter, IniHighlighter, DiffHighlighter};
+pub use list::{ListView, ListDataSource};
+pub use table::{Table, Column, ColumnWidth, SortDirection};
+pub use text_input::TextInput;
+pub use text_area::{TextArea, Clipboard};
+pub use tabs::Tabs;
+pub use dialog::{DialogStack, DialogEvent, DialogResponse};
+pub use menu::{MenuBar, ContextMenu, MenuItem, MenuEvent};
+pub use tree::{TreeView, TreeDataSource};
+pub use progress::{Progress, ProgressBar, Gauge, Spinner};
+pub use canvas::{Canvas, CanvasMode};
+pub use chart::{LineChart, BarChart, Histogram, Sparkline};
+#[cfg(unix)]
+pub use terminal::Terminal;
+
+/// Horizontal alignment of text within a fixed width.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Right,
+    Center
+}
+
+/// Pad or truncate `text` to exactly `width` characters.
+/// Text that doesn't fit is cut short and ends with an ellipsis.
+pub(crate) fn fit_text(text: &str, width: usize, alignment: Alignment) -> String {
+    let length = text.chars().count();
+    if length > width {
+        if width == 0 {
+            return String::new();
+        }
+        let mut output: String = text.chars().take(width - 1).collect();
+        output.push('…');
+        return output;
+    }
+
+    let space = width - length;
+    let (left, right) = match alignment {
+        Alignment::Left => (0, space),
+        Alignment::Right => (space, 0),
+        Alignment::Center => (space / 2, space - (space / 2))
+    };
+
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+/// Draw a box around the edges of a rect, leaving the inside alone.
+/// Rects too small to have an inside are left alone entirely.
+pub(crate) fn draw_box(rectmanager: &mut RectManager, rect_id: usize) -> Result<(), WreckedError> {
+    let (width, height) = rectmanager.get_rect_size(rect_id).unwrap_or((0, 0));
+    if width < 2 || height < 2 {
+        return Ok(());
+    }
+    let (right, bottom) = (width as isize - 1, height as isize - 1);
+
+    for x in 1 .. right {
+        rectmanager.set_character(rect_id, x, 0, '─')?;
+        rectmanager.set_character(rect_id, x, bottom, '─')?;
+    }
+    for y in 1 .. bottom {
+        rectmanager.set_character(rect_id, 0, y, '│')?;
+        rectmanager.set_character(rect_id, right, y, '│')?;
+    }
+    rectmanager.set_character(rect_id, 0, 0, '┌')?;
+    rectmanager.set_character(rect_id, right, 0, '┐')?;
+    rectmanager.set_character(rect_id, 0, bottom, '└')?;
+    rectmanager.set_character(rect_id, right, bottom, '┘')?;
+
+    Ok(())
+}
+
+/// Convert an absolute position, like a mouse event's, to a position inside the given rect.
+/// None if the position is outside of the rect.
+pub(crate) fn get_local_position(rectmanager: &RectManager, rect_id: usize, x: isize, y: isize) -> Option<(usize, usize)> {
+    let (offset_x, offset_y) = rectmanager.get_absolute_offset(rect_id)?;
+    let (width, height) = rectmanager.get_rect_size(rect_id)?;
+
+    if x >= offset_x && x < offset_x + width as isize && y >= offset_y && y < offset_y + height as isize {
+        Some(((x - offset_x) as usize, (y - offset_y) as usize))
+    } else {
+        None
+    }
+}
+
+/// Hide the terminal's cursor if it's in the given rect. Left alone if another widget has taken it,
+/// so that focusing one widget before blurring another keeps the cursor.
+pub(crate) fn release_cursor(rectmanager: &mut RectManager, rect_id: usize) {
+    if rectmanager.get_cursor().map(|(cursor_rect_id, _, _)| cursor_rect_id) == Some(rect_id) {
+        rectmanager.hide_cursor();
+    }
+}
@@ -0,0 +1,505 @@
+use std::cmp;
+use std::io;
+use std::time::{Duration, Instant};
+use crate::{RectManager, WreckedError, Style};
+use super::{fit_text, Alignment};
+
+// Partially filled cells, by eighths
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const VERTICAL_EIGHTHS: [char; 8] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
+// Draw `fraction` of `width` cells as a bar, to the nearest eighth of a cell
+pub(crate) fn get_bar_string(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * width as f64 * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let remainder = eighths % 8;
+    if remainder > 0 {
+        bar.push(EIGHTHS[remainder]);
+    }
+    let length = bar.chars().count();
+    bar + &" ".repeat(width - length)
+}
+
+// Draw `fraction` of `height` cells as a column filled from the bottom, to the nearest eighth of a cell.
+// The characters are given from top to bottom.
+pub(crate) fn get_column_characters(fraction: f64, height: usize) -> Vec<char> {
+    let eighths = (fraction.clamp(0.0, 1.0) * height as f64 * 8.0).round() as usize;
+    (0 .. height).rev().map(|row| {
+        let filled = cmp::min(eighths.saturating_sub(row * 8), 8);
+        if filled == 8 { '█' } else { VERTICAL_EIGHTHS[filled] }
+    }).collect()
+}
+
+fn get_frame_index(elapsed: Duration, interval: Duration, frame_count: usize) -> usize {
+    if frame_count == 0 || interval.as_nanos() == 0 {
+        0
+    } else {
+        (elapsed.as_nanos() / interval.as_nanos()) as usize % frame_count
+    }
+}
+
+// 65 -> "1:05", 3725 -> "1:02:05"
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, (seconds / 60) % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn format_rate(rate: f64, unit: &str) -> String {
+    if rate >= 1_000_000.0 {
+        format!("{:.1}M{}/s", rate / 1_000_000.0, unit)
+    } else if rate >= 1_000.0 {
+        format!("{:.1}k{}/s", rate / 1_000.0, unit)
+    } else {
+        format!("{:.1}{}/s", rate, unit)
+    }
+}
+
+/// Tracks how much of a task is done, along with its throughput and estimated time remaining.
+/// Progress can be drawn in a rect with a ProgressBar, or written inline (eg, by a build script) without a RectManager.
+/// write_inline() is the only inline output: ProgressBar, Gauge and Spinner all need a RectManager.
+///
+/// A Progress without a total is indeterminate, and is shown with a spinner instead of a bar,
+/// so it also stands in for a Spinner inline.
+/// # Example
+/// ```
+/// use wrecked::widgets::Progress;
+///
+/// let mut progress = Progress::new(200);
+/// progress.label = "Copying".to_string();
+/// progress.inc(50);
+/// assert_eq!(progress.format(24), "Copying ██▊          25%");
+///
+/// // Redraws the same line each time it's written
+/// let mut output = Vec::new();
+/// progress.write_inline(&mut output, 24).ok().unwrap();
+/// assert!(output.starts_with(b"\rCopying"));
+/// ```
+pub struct Progress {
+    pub label: String,
+    /// Show the percentage done after the bar (on by default)
+    pub show_percentage: bool,
+    /// Show the estimated time remaining
+    pub show_eta: bool,
+    /// Show the throughput, in units per second
+    pub show_rate: bool,
+    /// Unit shown with the throughput, eg "B" for "12.5kB/s"
+    pub unit: String,
+    done: u64,
+    total: Option<u64>,
+    started: Instant
+}
+
+impl Progress {
+    /// Track a task with `total` steps, starting now.
+    pub fn new(total: u64) -> Progress {
+        Progress {
+            label: String::new(),
+            show_percentage: true,
+            show_eta: false,
+            show_rate: false,
+            unit: String::new(),
+            done: 0,
+            total: Some(total),
+            started: Instant::now()
+        }
+    }
+
+    /// Track a task of unknown length, starting now.
+    pub fn indeterminate() -> Progress {
+        Progress {
+            total: None,
+            ..Progress::new(0)
+        }
+    }
+
+    pub fn get_done(&self) -> u64 {
+        self.done
+    }
+
+    pub fn set_done(&mut self, done: u64) {
+        self.done = done;
+    }
+
+    pub fn inc(&mut self, amount: u64) {
+        self.done = self.done.saturating_add(amount);
+    }
+
+    pub fn get_total(&self) -> Option<u64> {
+        self.total
+    }
+
+    pub fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+    }
+
+    /// Set when the task started, which the throughput and time remaining are measured from.
+    pub fn set_start_time(&mut self, started: Instant) {
+        self.started = started;
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Get the portion of the task that's done, from 0 to 1. None if the progress is indeterminate.
+    pub fn get_fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None
+        }
+    }
+
+    /// Get the average number of steps done per second.
+    pub fn get_rate(&self) -> f64 {
+        let elapsed = self.get_elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimate the time remaining from the average rate. None if it can't be estimated yet.
+    pub fn get_eta(&self) -> Option<Duration> {
+        let total = self.total?;
+        let rate = self.get_rate();
+        if rate > 0.0 {
+            Some(Duration::from_secs_f64(total.saturating_sub(self.done) as f64 / rate))
+        } else {
+            None
+        }
+    }
+
+    /// Split the line into the text before the bar, the bar itself and the text after it.
+    /// An indeterminate Progress has a spinner in place of a bar.
+    pub fn get_parts(&self, width: usize) -> (String, String, String) {
+        let mut suffix = String::new();
+        if self.show_rate {
+            suffix += &format!(" {}", format_rate(self.get_rate(), &self.unit));
+        }
+
+        match self.get_fraction() {
+            Some(fraction) => {
+                let prefix = if self.label.is_empty() {
+                    String::new()
+                } else {
+                    format!("{} ", self.label)
+                };
+                if self.show_percentage {
+                    suffix = format!(" {:>3}%{}", (fraction * 100.0).floor() as usize, suffix);
+                }
+                if self.show_eta {
+                    match self.get_eta() {
+                        Some(eta) => suffix += &format!(" ETA {}", format_duration(eta)),
+                        None => suffix += " ETA -:--"
+                    }
+                }
+
+                let bar_width = width.saturating_sub(prefix.chars().count() + suffix.chars().count());
+                (prefix, get_bar_string(fraction, bar_width), suffix)
+            }
+            None => {
+                let frame = SPINNER_FRAMES[get_frame_index(self.get_elapsed(), SPINNER_INTERVAL, SPINNER_FRAMES.len())];
+                let prefix = if self.label.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", self.label)
+                };
+                (frame.to_string(), prefix, suffix)
+            }
+        }
+    }
+
+    /// Format the progress as a line of exactly `width` characters.
+    pub fn format(&self, width: usize) -> String {
+        let (prefix, bar, suffix) = self.get_parts(width);
+        fit_text(&format!("{}{}{}", prefix, bar, suffix), width, Alignment::Left)
+    }
+
+    /// Write the progress over the current line of a terminal, without moving to the next one.
+    /// Write a newline once the task is finished.
+    pub fn write_inline<W: io::Write>(&self, output: &mut W, width: usize) -> io::Result<()> {
+        write!(output, "\r{}\x1B[K", self.format(width).trim_end())?;
+        output.flush()
+    }
+}
+
+/// A horizontal bar showing a Progress in the first row of a rect, to an eighth of a cell.
+/// To show the same bar inline, use the Progress's write_inline().
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::ProgressBar;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut progress_bar = ProgressBar::new(&mut rectmanager, ROOT, 1000).ok().unwrap();
+/// rectmanager.resize(progress_bar.get_rect_id(), 40, 1);
+/// progress_bar.get_progress_mut().show_eta = true;
+///
+/// for _ in 0 .. 10 {
+///     progress_bar.inc(&mut rectmanager, 100);
+///     rectmanager.render();
+/// }
+/// assert_eq!(progress_bar.get_progress().get_fraction(), Some(1.0));
+///
+/// rectmanager.kill();
+/// ```
+pub struct ProgressBar {
+    rect_id: usize,
+    progress: Progress,
+    bar_style: Style
+}
+
+impl ProgressBar {
+    /// Create a ProgressBar of a task with `total` steps, with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, total: u64) -> Result<ProgressBar, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        Ok(ProgressBar {
+            rect_id,
+            progress: Progress::new(total),
+            bar_style: Style::default()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Access the Progress in order to change it or what's shown. Call refresh() afterwards.
+    pub fn get_progress_mut(&mut self) -> &mut Progress {
+        &mut self.progress
+    }
+
+    pub fn set_done(&mut self, rectmanager: &mut RectManager, done: u64) -> Result<(), WreckedError> {
+        self.progress.set_done(done);
+        self.refresh(rectmanager)
+    }
+
+    pub fn inc(&mut self, rectmanager: &mut RectManager, amount: u64) -> Result<(), WreckedError> {
+        self.progress.inc(amount);
+        self.refresh(rectmanager)
+    }
+
+    /// Set the style of the filled part of the bar.
+    pub fn set_bar_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.bar_style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the bar. Needs to be called after the size of the rect changes, and regularly if showing the
+    /// time remaining or a spinner.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let width = rectmanager.get_rect_width(self.rect_id);
+        let (prefix, bar, suffix) = self.progress.get_parts(width);
+        let bar_start = prefix.chars().count();
+        let bar_end = bar_start + bar.chars().count();
+
+        let mut characters = prefix.chars().chain(bar.chars()).chain(suffix.chars());
+        for x in 0 .. width {
+            match characters.next() {
+                Some(character) => {
+                    rectmanager.set_character(self.rect_id, x as isize, 0, character)?;
+                }
+                None => {
+                    rectmanager.unset_character(self.rect_id, x as isize, 0)?;
+                }
+            }
+
+            if x >= bar_start && x < bar_end && self.progress.total.is_some() {
+                rectmanager.set_character_style(self.rect_id, x as isize, 0, self.bar_style)?;
+            } else {
+                rectmanager.unset_character_style(self.rect_id, x as isize, 0)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A vertical bar filling a rect from the bottom up, to an eighth of a cell.
+/// Gauges are only drawn in a rect; there's no inline form.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Gauge;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut gauge = Gauge::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(gauge.get_rect_id(), 2, 4);
+/// gauge.set_value(&mut rectmanager, 0.5);
+///
+/// assert_eq!(rectmanager.get_character(gauge.get_rect_id(), 0, 3).ok(), Some('█'));
+/// assert_eq!(rectmanager.get_character(gauge.get_rect_id(), 0, 1).ok(), Some(' '));
+///
+/// rectmanager.kill();
+/// ```
+pub struct Gauge {
+    rect_id: usize,
+    value: f64,
+    style: Style
+}
+
+impl Gauge {
+    /// Create an empty Gauge with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<Gauge, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        Ok(Gauge {
+            rect_id,
+            value: 0.0,
+            style: Style::default()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    /// Set how full the gauge is, from 0 to 1.
+    pub fn set_value(&mut self, rectmanager: &mut RectManager, value: f64) -> Result<(), WreckedError> {
+        self.value = value.clamp(0.0, 1.0);
+        self.refresh(rectmanager)
+    }
+
+    /// Set the style of the filled part of the gauge.
+    pub fn set_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the gauge. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        let characters = get_column_characters(self.value, height);
+
+        for (y, character) in characters.into_iter().enumerate() {
+            for x in 0 .. width {
+                rectmanager.set_character(self.rect_id, x as isize, y as isize, character)?;
+                if character != ' ' {
+                    rectmanager.set_character_style(self.rect_id, x as isize, y as isize, self.style)?;
+                } else {
+                    rectmanager.unset_character_style(self.rect_id, x as isize, y as isize)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An animated indicator for tasks of unknown length, followed by a label.
+/// The animation is driven by the time since it was created: call update() regularly, eg from a render loop.
+/// Inline, an indeterminate Progress shows the default frames.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Spinner;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut spinner = Spinner::new(&mut rectmanager, ROOT, "Connecting").ok().unwrap();
+/// assert_eq!(rectmanager.get_rect_size(spinner.get_rect_id()), Some((12, 1)));
+///
+/// for _ in 0 .. 5 {
+///     spinner.update(&mut rectmanager);
+///     rectmanager.render();
+/// }
+///
+/// rectmanager.kill();
+/// ```
+pub struct Spinner {
+    rect_id: usize,
+    label: String,
+    frames: Vec<char>,
+    interval: Duration,
+    started: Instant,
+    frame: usize,
+    style: Style
+}
+
+impl Spinner {
+    /// Create a Spinner with a new rect attached to the given parent. The rect is sized to fit the label.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, label: &str) -> Result<Spinner, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let mut spinner = Spinner {
+            rect_id,
+            label: label.to_string(),
+            frames: SPINNER_FRAMES.to_vec(),
+            interval: SPINNER_INTERVAL,
+            started: Instant::now(),
+            frame: 0,
+            style: Style::default()
+        };
+        spinner.set_label(rectmanager, label)?;
+        Ok(spinner)
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Change the label, resizing the rect to fit.
+    pub fn set_label(&mut self, rectmanager: &mut RectManager, label: &str) -> Result<(), WreckedError> {
+        self.label = label.to_string();
+        let width = if label.is_empty() { 1 } else { label.chars().count() + 2 };
+        rectmanager.resize(self.rect_id, width, 1)?;
+        self.refresh(rectmanager)
+    }
+
+    /// Change the animation. Each frame is shown for `interval`.
+    pub fn set_frames(&mut self, rectmanager: &mut RectManager, frames: &[char], interval: Duration) -> Result<(), WreckedError> {
+        self.frames = frames.to_vec();
+        self.interval = interval;
+        self.frame = 0;
+        self.started = Instant::now();
+        self.refresh(rectmanager)
+    }
+
+    /// Set the style of the animated character.
+    pub fn set_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Get the character currently shown.
+    pub fn get_frame(&self) -> char {
+        self.frames.get(self.frame).copied().unwrap_or(' ')
+    }
+
+    /// Move the animation on to the frame for the current time.
+    /// Returns true if it changed.
+    pub fn update(&mut self, rectmanager: &mut RectManager) -> Result<bool, WreckedError> {
+        let frame = get_frame_index(self.started.elapsed(), self.interval, self.frames.len());
+        if frame == self.frame {
+            Ok(false)
+        } else {
+            self.frame = frame;
+            self.refresh(rectmanager)?;
+            Ok(true)
+        }
+    }
+
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.set_character(self.rect_id, 0, 0, self.get_frame())?;
+        rectmanager.set_character_style(self.rect_id, 0, 0, self.style)?;
+        if !self.label.is_empty() {
+            rectmanager.set_string(self.rect_id, 2, 0, &self.label)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+use std::cmp;
+use std::cmp::Ordering;
+use crate::{RectManager, WreckedError, Style};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use crate::layout::{Constraint, solve_constraints};
+use super::{Alignment, fit_text, get_local_position};
+
+/// How the width of a table column is decided.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ColumnWidth {
+    /// Exactly this many cells
+    Fixed(usize),
+    /// A percentage of the width of the table
+    Percentage(usize),
+    /// Wide enough for the header and the widest cell in the column
+    Auto,
+    /// At least this many cells, sharing any leftover space with other Min columns
+    Min(usize)
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SortDirection {
+    Ascending,
+    Descending
+}
+
+impl SortDirection {
+    fn indicator(&self) -> char {
+        match self {
+            SortDirection::Ascending => '▲',
+            SortDirection::Descending => '▼'
+        }
+    }
+
+    fn reversed(&self) -> SortDirection {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending
+        }
+    }
+}
+
+/// A column of a Table.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Column {
+    pub title: String,
+    pub width: ColumnWidth,
+    pub alignment: Alignment
+}
+
+impl Column {
+    pub fn new(title: &str, width: ColumnWidth) -> Column {
+        Column {
+            title: title.to_string(),
+            width,
+            alignment: Alignment::Left
+        }
+    }
+
+    /// Shorthand for creating a column with the given alignment
+    pub fn aligned(title: &str, width: ColumnWidth, alignment: Alignment) -> Column {
+        Column {
+            title: title.to_string(),
+            width,
+            alignment
+        }
+    }
+}
+
+/// Rows of text split into columns, with a header and a selectable row.
+/// The header takes the first line of the rect and is underlined by a border; the rows fill the rest.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::{Table, Column, ColumnWidth, SortDirection};
+/// use wrecked::widgets::Alignment;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut table = Table::new(&mut rectmanager, ROOT, vec![
+///     Column::new("Name", ColumnWidth::Min(6)),
+///     Column::aligned("CPU", ColumnWidth::Fixed(5), Alignment::Right)
+/// ]).ok().unwrap();
+/// rectmanager.resize(table.get_rect_id(), 20, 5);
+///
+/// table.set_rows(&mut rectmanager, vec![
+///     vec!["init".to_string(), "0.1".to_string()],
+///     vec!["cargo".to_string(), "98.2".to_string()]
+/// ]);
+/// table.sort(&mut rectmanager, 1, SortDirection::Descending);
+/// assert_eq!(table.get_rows()[0][0], "cargo");
+///
+/// rectmanager.kill();
+/// ```
+pub struct Table {
+    rect_id: usize,
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    selected: Option<usize>,
+    // index of the first visible row
+    top: usize,
+    sort: Option<(usize, SortDirection)>,
+    borders: bool,
+    header_style: Style,
+    selection_style: Style
+}
+
+impl Table {
+    /// Create a Table with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, columns: Vec<Column>) -> Result<Table, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+
+        Ok(Table {
+            rect_id,
+            columns,
+            rows: Vec::new(),
+            selected: None,
+            top: 0,
+            sort: None,
+            borders: true,
+            header_style: Style {
+                bold: true,
+                ..Style::default()
+            },
+            selection_style: Style {
+                invert: true,
+                ..Style::default()
+            }
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_columns(&self) -> &Vec<Column> {
+        &self.columns
+    }
+
+    pub fn set_columns(&mut self, rectmanager: &mut RectManager, columns: Vec<Column>) -> Result<(), WreckedError> {
+        self.columns = columns;
+        self.refresh(rectmanager)
+    }
+
+    pub fn get_rows(&self) -> &Vec<Vec<String>> {
+        &self.rows
+    }
+
+    /// Replace all the rows of the table. Missing cells are left blank.
+    pub fn set_rows(&mut self, rectmanager: &mut RectManager, rows: Vec<Vec<String>>) -> Result<(), WreckedError> {
+        self.rows = rows;
+        self.refresh(rectmanager)
+    }
+
+    pub fn push_row(&mut self, rectmanager: &mut RectManager, row: Vec<String>) -> Result<(), WreckedError> {
+        self.rows.push(row);
+        self.refresh(rectmanager)
+    }
+
+    /// Draw lines between the columns and under the header (on by default).
+    pub fn set_borders(&mut self, rectmanager: &mut RectManager, borders: bool) -> Result<(), WreckedError> {
+        self.borders = borders;
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_header_style(&mut self, style: Style) {
+        self.header_style = style;
+    }
+
+    pub fn set_selection_style(&mut self, style: Style) {
+        self.selection_style = style;
+    }
+
+    pub fn get_selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Select a row, scrolling the table so that it's visible. None clears the selection.
+    pub fn set_selected(&mut self, rectmanager: &mut RectManager, index: Option<usize>) -> Result<(), WreckedError> {
+        self.selected = match index {
+            Some(index) if !self.rows.is_empty() => Some(cmp::min(index, self.rows.len() - 1)),
+            _ => None
+        };
+
+        if let Some(index) = self.selected {
+            let visible_rows = self.get_visible_row_count(rectmanager);
+            if index < self.top {
+                self.top = index;
+            } else if visible_rows > 0 && index >= self.top + visible_rows {
+                self.top = index + 1 - visible_rows;
+            }
+        }
+
+        self.refresh(rectmanager)
+    }
+
+    pub fn get_sort(&self) -> Option<(usize, SortDirection)> {
+        self.sort
+    }
+
+    /// Show a sort indicator next to a column's title without reordering the rows.
+    /// Useful when the rows are already sorted by whatever provides them.
+    pub fn set_sort_indicator(&mut self, rectmanager: &mut RectManager, sort: Option<(usize, SortDirection)>) -> Result<(), WreckedError> {
+        self.sort = sort;
+        self.refresh(rectmanager)
+    }
+
+    /// Sort the rows by the given column and show the sort indicator in its header.
+    /// Finite numbers are compared numerically and sorted before text, which is compared as text.
+    /// The selection follows the row that was selected.
+    pub fn sort(&mut self, rectmanager: &mut RectManager, column: usize, direction: SortDirection) -> Result<(), WreckedError> {
+        let selected_row = self.selected.map(|index| self.rows[index].clone());
+
+        self.rows.sort_by(|a, b| {
+            let ordering = compare_cells(
+                a.get(column).map(|s| s.as_str()).unwrap_or(""),
+                b.get(column).map(|s| s.as_str()).unwrap_or("")
+            );
+            match direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse()
+            }
+        });
+
+        if let Some(selected_row) = selected_row {
+            self.selected = self.rows.iter().position(|row| *row == selected_row);
+        }
+
+        self.sort = Some((column, direction));
+        self.refresh(rectmanager)
+    }
+
+    /// Get the width of each column at the table's current size.
+    pub fn get_column_widths(&self, rectmanager: &RectManager) -> Vec<usize> {
+        let width = rectmanager.get_rect_width(self.rect_id);
+        let separators = if self.borders {
+            self.columns.len().saturating_sub(1)
+        } else {
+            0
+        };
+
+        let constraints: Vec<Constraint> = self.columns.iter().enumerate().map(|(i, column)| {
+            match column.width {
+                ColumnWidth::Fixed(n) => Constraint::Fixed(n),
+                ColumnWidth::Percentage(p) => Constraint::Percentage(p),
+                ColumnWidth::Min(n) => Constraint::Min(n),
+                ColumnWidth::Auto => Constraint::Fixed(self.get_content_width(i))
+            }
+        }).collect();
+
+        solve_constraints(width.saturating_sub(separators), &constraints)
+    }
+
+    /// Redraw the table. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = match rectmanager.get_rect_size(self.rect_id) {
+            Some(size) => size,
+            None => {
+                return Err(WreckedError::NotFound(self.rect_id));
+            }
+        };
+
+        let visible_rows = self.get_visible_row_count(rectmanager);
+        self.top = cmp::min(self.top, self.rows.len().saturating_sub(visible_rows));
+        if let Some(index) = self.selected {
+            if index >= self.rows.len() {
+                self.selected = self.rows.len().checked_sub(1);
+            }
+        }
+
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.clear_character_styles(self.rect_id)?;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let widths = self.get_column_widths(rectmanager);
+        let step = if self.borders { 1 } else { 0 };
+
+        // Header
+        let mut x = 0;
+        for (i, (column, column_width)) in self.columns.iter().zip(widths.iter()).enumerate() {
+            let title = match self.sort {
+                Some((sorted, direction)) if sorted == i => {
+                    format!("{} {}", column.title, direction.indicator())
+                }
+                _ => column.title.clone()
+            };
+            self.draw_cell(rectmanager, x, 0, *column_width, &title, column.alignment)?;
+            x += column_width + step;
+        }
+        for x in 0 .. width {
+            rectmanager.set_character_style(self.rect_id, x as isize, 0, self.header_style)?;
+        }
+
+        let first_row_y = if self.borders { 2 } else { 1 };
+        if self.borders {
+            let mut x = 0;
+            for (i, column_width) in widths.iter().enumerate() {
+                for offset in 0 .. *column_width {
+                    self.draw_character(rectmanager, x + offset, 1, '─')?;
+                }
+                x += column_width;
+                if i < widths.len() - 1 {
+                    self.draw_character(rectmanager, x, 0, '│')?;
+                    self.draw_character(rectmanager, x, 1, '┼')?;
+                    for y in first_row_y .. height {
+                        self.draw_character(rectmanager, x, y, '│')?;
+                    }
+                    x += 1;
+                }
+            }
+        }
+
+        // Rows
+        for line in 0 .. visible_rows {
+            let index = self.top + line;
+            let y = first_row_y + line;
+            let row = match self.rows.get(index) {
+                Some(row) => row,
+                None => {
+                    break;
+                }
+            };
+
+            let mut x = 0;
+            for (i, (column, column_width)) in self.columns.iter().zip(widths.iter()).enumerate() {
+                let text = row.get(i).map(|s| s.as_str()).unwrap_or("");
+                self.draw_cell(rectmanager, x, y, *column_width, text, column.alignment)?;
+                x += column_width + step;
+            }
+
+            if self.selected == Some(index) {
+                for x in 0 .. width {
+                    rectmanager.set_character_style(self.rect_id, x as isize, y as isize, self.selection_style)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle keyboard navigation and mouse clicks.
+    /// Returns true if the event was used by the table.
+    ///
+    /// * Up/Down/PageUp/PageDown/Home/End move the selection
+    /// * Clicking a row selects it
+    /// * Clicking a column's header sorts by that column, reversing the direction if it's already sorted by it
+    /// * The mouse wheel scrolls the rows
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        let visible_rows = self.get_visible_row_count(rectmanager);
+        let last = self.rows.len().saturating_sub(1);
+
+        match event {
+            InputEvent::Key(key, _) => {
+                let current = self.selected.unwrap_or(0);
+                let target = match key {
+                    Key::Up => Some(current.saturating_sub(1)),
+                    Key::Down => {
+                        if self.selected.is_none() {
+                            Some(0)
+                        } else {
+                            Some(cmp::min(current + 1, last))
+                        }
+                    }
+                    Key::PageUp => Some(current.saturating_sub(cmp::max(visible_rows, 1))),
+                    Key::PageDown => Some(cmp::min(current + cmp::max(visible_rows, 1), last)),
+                    Key::Home => Some(0),
+                    Key::End => Some(last),
+                    _ => None
+                };
+
+                match target {
+                    Some(target) => {
+                        self.set_selected(rectmanager, Some(target))?;
+                        Ok(true)
+                    }
+                    None => {
+                        Ok(false)
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                let (x, y) = match get_local_position(rectmanager, self.rect_id, mouse.x, mouse.y) {
+                    Some(position) => position,
+                    None => {
+                        return Ok(false);
+                    }
+                };
+                let first_row_y = if self.borders { 2 } else { 1 };
+
+                match (mouse.action, mouse.button) {
+                    (MouseAction::Press, MouseButton::Left) => {
+                        if y == 0 {
+                            if let Some(column) = self.get_column_at(rectmanager, x) {
+                                let direction = match self.sort {
+                                    Some((sorted, direction)) if sorted == column => direction.reversed(),
+                                    _ => SortDirection::Ascending
+                                };
+                                self.sort(rectmanager, column, direction)?;
+                            }
+                        } else if y >= first_row_y {
+                            let index = self.top + (y - first_row_y);
+                            if index < self.rows.len() {
+                                self.set_selected(rectmanager, Some(index))?;
+                            }
+                        }
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelUp) => {
+                        self.top = self.top.saturating_sub(3);
+                        self.refresh(rectmanager)?;
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelDown) => {
+                        self.top += 3;
+                        self.refresh(rectmanager)?;
+                        Ok(true)
+                    }
+                    _ => {
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+
+    // Widest of the header (with room for a sort indicator) and the cells of a column
+    fn get_content_width(&self, column: usize) -> usize {
+        let header_width = self.columns[column].title.chars().count() + 2;
+        self.rows.iter()
+            .filter_map(|row| row.get(column))
+            .map(|cell| cell.chars().count())
+            .fold(header_width, cmp::max)
+    }
+
+    fn get_visible_row_count(&self, rectmanager: &RectManager) -> usize {
+        let header_height = if self.borders { 2 } else { 1 };
+        rectmanager.get_rect_height(self.rect_id).saturating_sub(header_height)
+    }
+
+    fn get_column_at(&self, rectmanager: &RectManager, x: usize) -> Option<usize> {
+        let step = if self.borders { 1 } else { 0 };
+        let mut left = 0;
+        for (i, column_width) in self.get_column_widths(rectmanager).iter().enumerate() {
+            if x >= left && x < left + column_width {
+                return Some(i);
+            }
+            left += column_width + step;
+        }
+        None
+    }
+
+    fn draw_cell(&self, rectmanager: &mut RectManager, x: usize, y: usize, width: usize, text: &str, alignment: Alignment) -> Result<(), WreckedError> {
+        let table_width = rectmanager.get_rect_width(self.rect_id);
+        if width == 0 || x >= table_width {
+            return Ok(());
+        }
+
+        let fitted = fit_text(text, cmp::min(width, table_width - x), alignment);
+        rectmanager.set_string(self.rect_id, x as isize, y as isize, &fitted)
+    }
+
+    fn draw_character(&self, rectmanager: &mut RectManager, x: usize, y: usize, character: char) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.rect_id).unwrap_or((0, 0));
+        if x < width && y < height {
+            rectmanager.set_character(self.rect_id, x as isize, y as isize, character)?;
+        }
+        Ok(())
+    }
+}
+
+// Numbers come before text so that the ordering stays total when a column mixes the two.
+// Only finite values count as numbers, since f64 also parses words like "inf" and "NaN".
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    let parse = |cell: &str| cell.trim().parse::<f64>().ok().filter(|value| value.is_finite());
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b)
+    }
+}
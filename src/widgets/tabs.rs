@@ -0,0 +1,321 @@
+use crate::{RectManager, WreckedError, Style};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use crate::layout::{Layout, Direction, Constraint, Anchor, HorizontalAnchor, VerticalAnchor};
+use super::get_local_position;
+
+/// A bar of tab titles above a content area showing one page at a time.
+/// Each page is a rect that fills the content area; only the active page is enabled.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Tabs;
+/// use wrecked::input::{InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut tabs = Tabs::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(tabs.get_rect_id(), 40, 10);
+/// let users_page = tabs.add_page(&mut rectmanager, "Users").ok().unwrap();
+/// let logs_page = tabs.add_page(&mut rectmanager, "Logs").ok().unwrap();
+/// rectmanager.set_string(logs_page, 0, 0, "No errors");
+///
+/// assert!(rectmanager.is_rect_enabled(users_page));
+/// tabs.handle_event(&mut rectmanager, &InputEvent::key(Key::Char('2')));
+/// assert!(!rectmanager.is_rect_enabled(users_page));
+/// assert!(rectmanager.is_rect_enabled(logs_page));
+///
+/// rectmanager.kill();
+/// ```
+pub struct Tabs {
+    rect_id: usize,
+    bar_id: usize,
+    content_id: usize,
+    // (title, page rect)
+    pages: Vec<(String, usize)>,
+    active: Option<usize>,
+    active_style: Style,
+    inactive_style: Style,
+    number_keys: bool,
+    // First column and width of each title in the bar, as of the last refresh
+    title_positions: Vec<(isize, usize)>
+}
+
+impl Tabs {
+    /// Create a Tabs widget with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<Tabs, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let bar_id = rectmanager.new_rect(rect_id)?;
+        let content_id = rectmanager.new_rect(rect_id)?;
+
+        rectmanager.set_constraint(bar_id, Constraint::Fixed(1))?;
+        rectmanager.set_constraint(content_id, Constraint::Fill(1))?;
+        rectmanager.set_layout(rect_id, Layout::new(Direction::Vertical))?;
+
+        Ok(Tabs {
+            rect_id,
+            bar_id,
+            content_id,
+            pages: Vec::new(),
+            active: None,
+            active_style: Style {
+                invert: true,
+                bold: true,
+                ..Style::default()
+            },
+            inactive_style: Style::default(),
+            number_keys: true,
+            title_positions: Vec::new()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Get the id of the rect the titles are drawn in.
+    pub fn get_bar_rect_id(&self) -> usize {
+        self.bar_id
+    }
+
+    /// Get the id of the rect the pages are attached to.
+    pub fn get_content_rect_id(&self) -> usize {
+        self.content_id
+    }
+
+    /// Add a page, returning the id of its rect. The first page added becomes the active one.
+    pub fn add_page(&mut self, rectmanager: &mut RectManager, title: &str) -> Result<usize, WreckedError> {
+        let page_id = rectmanager.new_rect(self.content_id)?;
+        self.setup_page(rectmanager, page_id)?;
+        self.pages.push((title.to_string(), page_id));
+
+        if self.active.is_none() {
+            self.active = Some(self.pages.len() - 1);
+        } else {
+            rectmanager.disable(page_id)?;
+        }
+
+        self.refresh(rectmanager)?;
+        Ok(page_id)
+    }
+
+    /// Add an existing rect as a page. It's attached to the content area and resized to fill it.
+    pub fn add_page_rect(&mut self, rectmanager: &mut RectManager, title: &str, page_id: usize) -> Result<(), WreckedError> {
+        if rectmanager.has_parent(page_id) {
+            rectmanager.detach(page_id)?;
+        }
+        rectmanager.attach(page_id, self.content_id)?;
+        self.setup_page(rectmanager, page_id)?;
+        self.pages.push((title.to_string(), page_id));
+
+        if self.active.is_none() {
+            self.active = Some(self.pages.len() - 1);
+            rectmanager.enable(page_id)?;
+        } else {
+            rectmanager.disable(page_id)?;
+        }
+
+        self.refresh(rectmanager)
+    }
+
+    /// Remove a page, deleting its rect.
+    pub fn remove_page(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        if index >= self.pages.len() {
+            return Ok(());
+        }
+
+        let (_, page_id) = self.pages.remove(index);
+        rectmanager.delete_rect(page_id)?;
+
+        self.active = match self.active {
+            _ if self.pages.is_empty() => None,
+            Some(active) if active > index || active == self.pages.len() => Some(active - 1),
+            active => active
+        };
+        if let Some(active) = self.active {
+            rectmanager.enable(self.pages[active].1)?;
+        }
+
+        self.refresh(rectmanager)
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Get the rect id of the page at the given index.
+    pub fn get_page(&self, index: usize) -> Option<usize> {
+        self.pages.get(index).map(|(_, page_id)| *page_id)
+    }
+
+    pub fn set_title(&mut self, rectmanager: &mut RectManager, index: usize, title: &str) -> Result<(), WreckedError> {
+        if let Some(page) = self.pages.get_mut(index) {
+            page.0 = title.to_string();
+        }
+        self.refresh(rectmanager)
+    }
+
+    /// Get the index of the active page.
+    pub fn get_active(&self) -> Option<usize> {
+        self.active
+    }
+
+    /// Show the page at the given index, hiding the previously active one.
+    pub fn set_active(&mut self, rectmanager: &mut RectManager, index: usize) -> Result<(), WreckedError> {
+        if index >= self.pages.len() || self.active == Some(index) {
+            return Ok(());
+        }
+
+        if let Some(active) = self.active {
+            rectmanager.disable(self.pages[active].1)?;
+        }
+        rectmanager.enable(self.pages[index].1)?;
+        self.active = Some(index);
+
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_active_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.active_style = style;
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_inactive_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.inactive_style = style;
+        self.refresh(rectmanager)
+    }
+
+    /// Switch pages with the number keys 1-9 (on by default).
+    /// Turn this off if the pages contain inputs that should receive those keys.
+    pub fn set_number_keys(&mut self, number_keys: bool) {
+        self.number_keys = number_keys;
+    }
+
+    /// Redraw the tab bar. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let width = rectmanager.get_rect_width(self.bar_id) as isize;
+        rectmanager.clear_characters(self.bar_id)?;
+        rectmanager.clear_character_styles(self.bar_id)?;
+
+        // " Title " for each page, with a line between them
+        self.title_positions.clear();
+        let mut x = 0;
+        for (title, _) in self.pages.iter() {
+            let length = title.chars().count() + 2;
+            self.title_positions.push((x, length));
+            x += length as isize + 1;
+        }
+
+        // Scroll the bar if needed to keep the active title in view
+        let shift = match self.active.and_then(|active| self.title_positions.get(active)) {
+            Some((start, length)) if *start + *length as isize > width => {
+                *start + *length as isize - width
+            }
+            _ => 0
+        };
+        for position in self.title_positions.iter_mut() {
+            position.0 -= shift;
+        }
+
+        for (i, (title, _)) in self.pages.iter().enumerate() {
+            let (start, length) = self.title_positions[i];
+            let style = if self.active == Some(i) {
+                self.active_style
+            } else {
+                self.inactive_style
+            };
+
+            let label = format!(" {} ", title);
+            for (offset, character) in label.chars().enumerate() {
+                let x = start + offset as isize;
+                if x >= 0 && x < width {
+                    rectmanager.set_character(self.bar_id, x, 0, character)?;
+                    rectmanager.set_character_style(self.bar_id, x, 0, style)?;
+                }
+            }
+
+            let separator_x = start + length as isize;
+            if i < self.pages.len() - 1 && separator_x >= 0 && separator_x < width {
+                rectmanager.set_character(self.bar_id, separator_x, 0, '│')?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle page switching.
+    /// Returns true if the event was used by the tabs.
+    ///
+    /// * Ctrl-PageUp/Ctrl-PageDown switch to the previous/next page
+    /// * 1-9 switch to that page, if number keys are on
+    /// * Clicking a title switches to its page
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        if self.pages.is_empty() {
+            return Ok(false);
+        }
+
+        let page_count = self.pages.len();
+        let active = self.active.unwrap_or(0);
+
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                let target = match key {
+                    Key::PageUp if modifiers.ctrl => Some((active + page_count - 1) % page_count),
+                    Key::PageDown if modifiers.ctrl => Some((active + 1) % page_count),
+                    Key::Char(character) if self.number_keys && modifiers.is_none() => {
+                        match character.to_digit(10) {
+                            Some(digit) if digit > 0 && (digit as usize) <= page_count => Some(digit as usize - 1),
+                            _ => None
+                        }
+                    }
+                    _ => None
+                };
+
+                match target {
+                    Some(target) => {
+                        self.set_active(rectmanager, target)?;
+                        Ok(true)
+                    }
+                    None => {
+                        Ok(false)
+                    }
+                }
+            }
+            InputEvent::Mouse(mouse) => {
+                if mouse.action != MouseAction::Press || mouse.button != MouseButton::Left {
+                    return Ok(false);
+                }
+
+                let x = match get_local_position(rectmanager, self.bar_id, mouse.x, mouse.y) {
+                    Some((x, 0)) => x as isize,
+                    _ => {
+                        return Ok(false);
+                    }
+                };
+                let clicked = self.title_positions.iter().position(|(start, length)| {
+                    x >= *start && x < *start + *length as isize
+                });
+
+                match clicked {
+                    Some(index) => {
+                        self.set_active(rectmanager, index)?;
+                        Ok(true)
+                    }
+                    None => {
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+
+    // Make a page fill the content area, and keep filling it when the content area is resized
+    fn setup_page(&self, rectmanager: &mut RectManager, page_id: usize) -> Result<(), WreckedError> {
+        let (width, height) = rectmanager.get_rect_size(self.content_id).unwrap_or((0, 0));
+        rectmanager.resize(page_id, width, height)?;
+        rectmanager.set_position(page_id, 0, 0)?;
+
+        let mut anchor = Anchor::new(HorizontalAnchor::Left, VerticalAnchor::Top);
+        anchor.stretch_horizontal = true;
+        anchor.stretch_vertical = true;
+        rectmanager.set_anchor(page_id, anchor)
+    }
+}
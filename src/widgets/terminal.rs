@@ -0,0 +1,452 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::ptr;
+use crate::{RectManager, WreckedError, Style};
+use crate::input::{InputEvent, Key, Modifiers, MouseAction, MouseButton, MouseEvent};
+use crate::vt::{self, Emulator};
+use super::release_cursor;
+
+// Most output read from the child in a single update(), so a busy child can't hold up the caller
+const MAX_READ: usize = 65536;
+
+/// A terminal emulator running a command in a pseudo-terminal, showing its screen in a rect.
+/// The child's output is read in update(), which should be called regularly (eg, each time around the main loop).
+/// # Example
+/// ```
+/// use std::process::Command;
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::Terminal;
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut terminal = Terminal::new(&mut rectmanager, ROOT).ok().unwrap();
+/// terminal.resize(&mut rectmanager, 20, 2);
+/// terminal.spawn(&mut rectmanager, Command::new("true")).ok().unwrap();
+///
+/// // Output can also be fed in directly
+/// terminal.process_output(&mut rectmanager, b"\x1B[31mHello\r\n\x1B[0mWorld");
+/// assert_eq!(rectmanager.get_character(terminal.get_rect_id(), 0, 1).ok(), Some('W'));
+///
+/// rectmanager.kill();
+/// ```
+pub struct Terminal {
+    rect_id: usize,
+    emulator: Emulator,
+    master: Option<File>,
+    child: Option<Child>,
+    // The end of a utf8 character left unfinished by the last read
+    pending: Vec<u8>,
+    focused: bool
+}
+
+impl Terminal {
+    /// Create a Terminal with a new rect attached to the given parent. No command is run until spawn() is called.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<Terminal, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let (width, height) = rectmanager.get_rect_size(rect_id).unwrap_or((1, 1));
+
+        Ok(Terminal {
+            rect_id,
+            emulator: Emulator::new(width, height),
+            master: None,
+            child: None,
+            pending: Vec::new(),
+            focused: false
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Run a command in a new pseudo-terminal the size of the rect. TERM is set to xterm-256color unless the
+    /// command sets it. Any command already running is killed.
+    pub fn spawn(&mut self, rectmanager: &mut RectManager, mut command: Command) -> Result<(), WreckedError> {
+        self.kill()?;
+        self.sync_size(rectmanager)?;
+
+        let (width, height) = self.emulator.get_size();
+        let (master, slave) = open_pty(width, height)?;
+        let failure = |error: io::Error| WreckedError::ProcessFailure(error.to_string());
+
+        if !command.get_envs().any(|(key, _)| key == "TERM") {
+            command.env("TERM", "xterm-256color");
+        }
+        command.stdin(Stdio::from(slave.try_clone().map_err(failure)?))
+            .stdout(Stdio::from(slave.try_clone().map_err(failure)?))
+            .stderr(Stdio::from(slave));
+        unsafe {
+            // Start a new session, with the pseudo-terminal as its controlling terminal
+            command.pre_exec(|| {
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        self.child = Some(command.spawn().map_err(failure)?);
+        // Dropping the command closes the parent's copies of the slave, so reads fail once the child is gone
+        drop(command);
+        self.master = Some(master);
+        Ok(())
+    }
+
+    /// Check if the command is still running.
+    pub fn is_running(&mut self) -> bool {
+        match self.child.as_mut() {
+            Some(child) => matches!(child.try_wait(), Ok(None)),
+            None => false
+        }
+    }
+
+    /// Get the exit code of the command, once it's finished. None while running, or if it was killed by a signal.
+    pub fn get_exit_code(&mut self) -> Option<i32> {
+        match self.child.as_mut() {
+            Some(child) => child.try_wait().ok().flatten().and_then(|status| status.code()),
+            None => None
+        }
+    }
+
+    /// Check if the command has switched to the alternate screen, as full screen programs do.
+    pub fn is_alternate_screen(&self) -> bool {
+        self.emulator.is_alternate_screen()
+    }
+
+    /// Kill the command, if it's running.
+    pub fn kill(&mut self) -> Result<(), WreckedError> {
+        if let Some(mut child) = self.child.take() {
+            if let Ok(None) = child.try_wait() {
+                child.kill().map_err(|error| WreckedError::ProcessFailure(error.to_string()))?;
+            }
+            child.wait().ok();
+        }
+        self.master = None;
+        Ok(())
+    }
+
+    /// Read any output from the command, and follow changes to the rect's size.
+    /// Returns true if there was output.
+    pub fn update(&mut self, rectmanager: &mut RectManager) -> Result<bool, WreckedError> {
+        if self.sync_size(rectmanager)? {
+            self.refresh(rectmanager)?;
+        }
+
+        let mut output = Vec::new();
+        if let Some(master) = self.master.as_mut() {
+            let mut buffer = [0; 4096];
+            while output.len() < MAX_READ {
+                match master.read(&mut buffer) {
+                    Ok(0) => {
+                        break;
+                    }
+                    Ok(length) => {
+                        output.extend_from_slice(&buffer[.. length]);
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    // WouldBlock when there's nothing to read, or EIO once the child has closed the terminal
+                    Err(_) => {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if output.is_empty() {
+            Ok(false)
+        } else {
+            self.process_output(rectmanager, &output)?;
+            Ok(true)
+        }
+    }
+
+    /// Run output through the terminal as if the command had written it.
+    pub fn process_output(&mut self, rectmanager: &mut RectManager, bytes: &[u8]) -> Result<(), WreckedError> {
+        let text = vt::decode_utf8(&mut self.pending, bytes);
+        self.emulator.feed(&text);
+        let responses = self.emulator.take_responses();
+        if !responses.is_empty() && self.master.is_some() {
+            self.write(&responses)?;
+        }
+        self.refresh(rectmanager)
+    }
+
+    /// Send input to the command, as if it were typed.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), WreckedError> {
+        match self.master.as_mut() {
+            Some(master) => master.write_all(bytes).map_err(|error| WreckedError::ProcessFailure(error.to_string())),
+            None => Err(WreckedError::ProcessFailure("No command is running".to_string()))
+        }
+    }
+
+    /// Resize the rect, and tell the command its terminal has changed size.
+    pub fn resize(&mut self, rectmanager: &mut RectManager, width: usize, height: usize) -> Result<(), WreckedError> {
+        rectmanager.resize(self.rect_id, width, height)?;
+        self.sync_size(rectmanager)?;
+        self.refresh(rectmanager)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Show the terminal's cursor where the command has put it.
+    pub fn focus(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = true;
+        self.refresh(rectmanager)
+    }
+
+    /// Hide the terminal's cursor.
+    pub fn blur(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = false;
+        release_cursor(rectmanager, self.rect_id);
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the rect from the terminal's screen.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = self.emulator.get_size();
+        for y in 0 .. height {
+            for x in 0 .. width {
+                if let Some(cell) = self.emulator.get_cell(x, y) {
+                    rectmanager.set_character(self.rect_id, x as isize, y as isize, cell.character)?;
+                    if cell.style == Style::default() {
+                        rectmanager.unset_character_style(self.rect_id, x as isize, y as isize)?;
+                    } else {
+                        rectmanager.set_character_style(self.rect_id, x as isize, y as isize, cell.style)?;
+                    }
+                }
+            }
+        }
+
+        if self.focused {
+            if self.emulator.is_cursor_visible() {
+                let (x, y) = self.emulator.get_cursor();
+                rectmanager.set_cursor(self.rect_id, x as isize, y as isize)?;
+            } else {
+                rectmanager.hide_cursor();
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward an event to the command:
+    /// * Keys are sent as an xterm would send them
+    /// * Mouse events within the rect are sent if the command has turned on mouse reporting
+    ///
+    /// Returns true if the event was sent.
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        if self.master.is_none() {
+            return Ok(false);
+        }
+
+        let bytes = match event {
+            InputEvent::Key(key, modifiers) => {
+                encode_key(*key, *modifiers, self.emulator.is_application_cursor())
+            }
+            InputEvent::Mouse(mouse) => {
+                let (offset_x, offset_y) = rectmanager.get_absolute_offset(self.rect_id).unwrap_or((0, 0));
+                let (width, height) = self.emulator.get_size();
+                let (x, y) = (mouse.x - offset_x, mouse.y - offset_y);
+                if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                    return Ok(false);
+                }
+                self.encode_mouse(mouse, x as usize, y as usize)
+            }
+        };
+
+        if bytes.is_empty() {
+            Ok(false)
+        } else {
+            self.write(&bytes)?;
+            Ok(true)
+        }
+    }
+
+    // Encode a mouse event at (x, y) of the screen in the reporting mode the command asked for, if any
+    fn encode_mouse(&self, mouse: &MouseEvent, x: usize, y: usize) -> Vec<u8> {
+        let is_set = |mode: u32| self.emulator.is_private_mode_set(mode);
+        let reported = match mouse.action {
+            MouseAction::Press | MouseAction::Release => is_set(1000) || is_set(1002) || is_set(1003),
+            MouseAction::Drag => is_set(1002) || is_set(1003),
+            MouseAction::Move => is_set(1003)
+        };
+        if !reported {
+            return Vec::new();
+        }
+
+        let sgr = is_set(1006);
+        let mut button = match mouse.button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+            MouseButton::WheelUp => 64,
+            MouseButton::WheelDown => 65,
+            MouseButton::None => 3
+        };
+        if mouse.action == MouseAction::Release && !sgr {
+            // The older encoding doesn't say which button was released
+            button = 3;
+        }
+        if mouse.action == MouseAction::Drag || mouse.action == MouseAction::Move {
+            button += 32;
+        }
+        if mouse.modifiers.shift {
+            button += 4;
+        }
+        if mouse.modifiers.alt {
+            button += 8;
+        }
+        if mouse.modifiers.ctrl {
+            button += 16;
+        }
+
+        if sgr {
+            let end = if mouse.action == MouseAction::Release { 'm' } else { 'M' };
+            format!("\x1B[<{};{};{}{}", button, x + 1, y + 1, end).into_bytes()
+        } else {
+            let encode = |value: usize| (32 + value).min(255) as u8;
+            vec![0x1B, b'[', b'M', encode(button), encode(x + 1), encode(y + 1)]
+        }
+    }
+
+    // Match the screen and pseudo-terminal to the rect's size. Returns true if it changed.
+    fn sync_size(&mut self, rectmanager: &RectManager) -> Result<bool, WreckedError> {
+        let (width, height) = match rectmanager.get_rect_size(self.rect_id) {
+            Some(size) => (size.0.max(1), size.1.max(1)),
+            None => {
+                return Err(WreckedError::NotFound(self.rect_id));
+            }
+        };
+        if self.emulator.get_size() == (width, height) {
+            return Ok(false);
+        }
+
+        self.emulator.resize(width, height);
+        if let Some(master) = self.master.as_ref() {
+            set_pty_size(master.as_raw_fd(), width, height)?;
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        self.kill().ok();
+    }
+}
+
+fn get_winsize(width: usize, height: usize) -> libc::winsize {
+    libc::winsize {
+        ws_row: height.min(u16::MAX as usize) as u16,
+        ws_col: width.min(u16::MAX as usize) as u16,
+        ws_xpixel: 0,
+        ws_ypixel: 0
+    }
+}
+
+// Open a pseudo-terminal, returning its master (non-blocking) and slave ends
+fn open_pty(width: usize, height: usize) -> Result<(File, File), WreckedError> {
+    let failure = || WreckedError::ProcessFailure(io::Error::last_os_error().to_string());
+    let size = get_winsize(width, height);
+    let mut master: RawFd = -1;
+    let mut slave: RawFd = -1;
+
+    unsafe {
+        if libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), &size) == -1 {
+            return Err(failure());
+        }
+        let (master, slave) = (File::from_raw_fd(master), File::from_raw_fd(slave));
+
+        // Neither end should be inherited by the child, except as its stdio
+        for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+            if libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+                return Err(failure());
+            }
+        }
+        let flags = libc::fcntl(master.as_raw_fd(), libc::F_GETFL);
+        if flags == -1 || libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+            return Err(failure());
+        }
+
+        Ok((master, slave))
+    }
+}
+
+fn set_pty_size(fd: RawFd, width: usize, height: usize) -> Result<(), WreckedError> {
+    let size = get_winsize(width, height);
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &size) } == -1 {
+        Err(WreckedError::ProcessFailure(io::Error::last_os_error().to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+// The bytes an xterm sends for a key
+fn encode_key(key: Key, modifiers: Modifiers, application_cursor: bool) -> Vec<u8> {
+    // Modifiers are sent as a parameter of 1 + a bitmask
+    let modifier_code = 1 + modifiers.shift as u8 + (modifiers.alt as u8) * 2 + (modifiers.ctrl as u8) * 4;
+    let cursor_key = |letter: char| -> Vec<u8> {
+        if !modifiers.is_none() {
+            format!("\x1B[1;{}{}", modifier_code, letter).into_bytes()
+        } else if application_cursor {
+            format!("\x1BO{}", letter).into_bytes()
+        } else {
+            format!("\x1B[{}", letter).into_bytes()
+        }
+    };
+    let tilde_key = |code: u8| -> Vec<u8> {
+        if modifiers.is_none() {
+            format!("\x1B[{}~", code).into_bytes()
+        } else {
+            format!("\x1B[{};{}~", code, modifier_code).into_bytes()
+        }
+    };
+
+    match key {
+        Key::Char(character) => {
+            let mut bytes = if modifiers.alt { vec![0x1B] } else { Vec::new() };
+            if modifiers.ctrl && character == ' ' {
+                bytes.push(0);
+            } else if modifiers.ctrl && character == '?' {
+                bytes.push(0x7F);
+            } else if modifiers.ctrl && ('@' ..= '~').contains(&character) {
+                bytes.push(character as u8 & 0x1F);
+            } else {
+                bytes.extend(character.to_string().bytes());
+            }
+            bytes
+        }
+        Key::Enter => b"\r".to_vec(),
+        Key::Tab => b"\t".to_vec(),
+        Key::BackTab => b"\x1B[Z".to_vec(),
+        Key::Backspace => {
+            if modifiers.ctrl { vec![0x08] } else { vec![0x7F] }
+        }
+        Key::Escape => vec![0x1B],
+        Key::Up => cursor_key('A'),
+        Key::Down => cursor_key('B'),
+        Key::Right => cursor_key('C'),
+        Key::Left => cursor_key('D'),
+        Key::Home => cursor_key('H'),
+        Key::End => cursor_key('F'),
+        Key::Insert => tilde_key(2),
+        Key::Delete => tilde_key(3),
+        Key::PageUp => tilde_key(5),
+        Key::PageDown => tilde_key(6),
+        Key::F(number @ 1 ..= 4) => {
+            let letter = (b'P' + number - 1) as char;
+            if modifiers.is_none() {
+                format!("\x1BO{}", letter).into_bytes()
+            } else {
+                format!("\x1B[1;{}{}", modifier_code, letter).into_bytes()
+            }
+        }
+        Key::F(number @ 5 ..= 12) => {
+            tilde_key([15, 17, 18, 19, 20, 21, 23, 24][(number - 5) as usize])
+        }
+        Key::F(_) => Vec::new()
+    }
+}
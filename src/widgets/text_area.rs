@@ -0,0 +1,823 @@
+use std::cmp;
+use crate::{RectManager, WreckedError, Style, Color};
+use crate::input::{InputEvent, Key, Modifiers, MouseAction, MouseButton};
+use super::{get_local_position, release_cursor};
+use super::highlight::{Highlighter, HighlightCache};
+
+/// Hooks for connecting a TextArea to a clipboard.
+/// Without one, a TextArea copies to and pastes from a buffer of its own.
+pub trait Clipboard {
+    fn copy(&mut self, text: &str);
+    fn paste(&mut self) -> Option<String>;
+}
+
+// A copy of the text to return to with undo/redo
+#[derive(Clone)]
+struct Snapshot {
+    lines: Vec<Vec<char>>,
+    cursor: (usize, usize)
+}
+
+// Kinds of edits. Consecutive edits of the same kind are undone together.
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other
+}
+
+// The part of a line displayed on one row: (line, start column, end column)
+type VisualRow = (usize, usize, usize);
+
+/// Multi-line text editor with selection, undo/redo and an optional gutter with line numbers.
+///
+/// The editor is made of a container rect, with a rect for the gutter and one for the text inside of it.
+/// Only rows that changed since the last refresh are redrawn.
+///
+/// Keys:
+/// * Arrows, Home/End, PageUp/PageDown move the cursor; Ctrl-Home/Ctrl-End go to the start/end of the text
+/// * Holding shift while moving selects text. Ctrl-A selects everything
+/// * Ctrl-C, Ctrl-X and Ctrl-V copy, cut and paste
+/// * Ctrl-Z undoes and Ctrl-Y redoes
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::TextArea;
+/// use wrecked::input::{parse_input, InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut editor = TextArea::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(editor.get_rect_id(), 40, 10);
+/// editor.set_text(&mut rectmanager, "[server]\nport = 80");
+///
+/// editor.handle_event(&mut rectmanager, &InputEvent::key(Key::Backspace));
+/// for event in parse_input(b"8080") {
+///     editor.handle_event(&mut rectmanager, &event);
+/// }
+/// assert_eq!(editor.get_text(), "[server]\nport = 88080");
+///
+/// editor.handle_event(&mut rectmanager, &InputEvent::ctrl(Key::Char('z')));
+/// assert_eq!(editor.get_text(), "[server]\nport = 8");
+///
+/// rectmanager.kill();
+/// ```
+pub struct TextArea {
+    rect_id: usize,
+    gutter_id: usize,
+    text_id: usize,
+
+    lines: Vec<Vec<char>>,
+    // (line, column)
+    cursor: (usize, usize),
+    // The other end of the selection, if there is one
+    anchor: Option<(usize, usize)>,
+    focused: bool,
+
+    wrap: bool,
+    line_numbers: bool,
+    tab_width: usize,
+    // First visible row, and first visible column when not wrapping
+    scroll: (usize, usize),
+    // Set when the view needs to move to keep the cursor visible
+    follow_cursor: bool,
+
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    undo_limit: usize,
+    last_edit: EditKind,
+
+    clipboard: Option<Box<dyn Clipboard>>,
+    local_clipboard: String,
+
+    selection_style: Style,
+    gutter_style: Style,
+    highlight: HighlightCache,
+
+    // What was drawn on each visible row during the last refresh
+    drawn_rows: Vec<Vec<(Option<char>, Option<Style>)>>,
+    drawn_gutter: Vec<String>,
+    drawn_size: (usize, usize, usize)
+}
+
+impl TextArea {
+    /// Create a TextArea with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<TextArea, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+        let gutter_id = rectmanager.new_rect(rect_id)?;
+        let text_id = rectmanager.new_rect(rect_id)?;
+
+        Ok(TextArea {
+            rect_id,
+            gutter_id,
+            text_id,
+            lines: vec![Vec::new()],
+            cursor: (0, 0),
+            anchor: None,
+            focused: false,
+            wrap: false,
+            line_numbers: true,
+            tab_width: 4,
+            scroll: (0, 0),
+            follow_cursor: true,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undo_limit: 100,
+            last_edit: EditKind::Other,
+            clipboard: None,
+            local_clipboard: String::new(),
+            selection_style: Style {
+                invert: true,
+                ..Style::default()
+            },
+            gutter_style: Style {
+                foreground_color: Some(Color::BRIGHTBLACK),
+                ..Style::default()
+            },
+            highlight: HighlightCache::default(),
+            drawn_rows: Vec::new(),
+            drawn_gutter: Vec::new(),
+            drawn_size: (0, 0, 0)
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    /// Get the id of the rect the line numbers are drawn in.
+    pub fn get_gutter_rect_id(&self) -> usize {
+        self.gutter_id
+    }
+
+    /// Get the id of the rect the text is drawn in.
+    pub fn get_text_rect_id(&self) -> usize {
+        self.text_id
+    }
+
+    pub fn get_text(&self) -> String {
+        self.lines.iter()
+            .map(|line| line.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Replace the text, moving the cursor to the end of it and clearing the selection and the undo history.
+    pub fn set_text(&mut self, rectmanager: &mut RectManager, text: &str) -> Result<(), WreckedError> {
+        self.lines = text.split('\n').map(|line| line.chars().collect()).collect();
+        let last_line = self.lines.len() - 1;
+        self.cursor = (last_line, self.lines[last_line].len());
+        self.anchor = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit = EditKind::Other;
+        self.follow_cursor = true;
+        self.refresh(rectmanager)
+    }
+
+    pub fn get_line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Get the position of the cursor as (line, column).
+    pub fn get_cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, rectmanager: &mut RectManager, line: usize, column: usize) -> Result<(), WreckedError> {
+        self.anchor = None;
+        self.move_cursor((line, column));
+        self.refresh(rectmanager)
+    }
+
+    /// Get the start and end of the selection, in order.
+    pub fn get_selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        match self.anchor {
+            Some(anchor) if anchor != self.cursor => {
+                Some((cmp::min(anchor, self.cursor), cmp::max(anchor, self.cursor)))
+            }
+            _ => None
+        }
+    }
+
+    pub fn get_selected_text(&self) -> Option<String> {
+        let (start, end) = self.get_selection()?;
+        let mut output = String::new();
+        for line in start.0 ..= end.0 {
+            let from = if line == start.0 { start.1 } else { 0 };
+            let to = if line == end.0 { end.1 } else { self.lines[line].len() };
+            output.extend(&self.lines[line][from .. to]);
+            if line != end.0 {
+                output.push('\n');
+            }
+        }
+        Some(output)
+    }
+
+    /// Select from `start` to `end`, leaving the cursor at `end`.
+    pub fn set_selection(&mut self, rectmanager: &mut RectManager, start: (usize, usize), end: (usize, usize)) -> Result<(), WreckedError> {
+        self.move_cursor(start);
+        self.anchor = Some(self.cursor);
+        self.move_cursor(end);
+        self.refresh(rectmanager)
+    }
+
+    pub fn select_all(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let last_line = self.lines.len() - 1;
+        self.set_selection(rectmanager, (0, 0), (last_line, self.lines[last_line].len()))
+    }
+
+    pub fn clear_selection(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.anchor = None;
+        self.refresh(rectmanager)
+    }
+
+    /// Wrap long lines onto the following rows instead of scrolling horizontally (off by default).
+    pub fn set_wrap(&mut self, rectmanager: &mut RectManager, wrap: bool) -> Result<(), WreckedError> {
+        self.wrap = wrap;
+        self.scroll = (0, 0);
+        self.follow_cursor = true;
+        self.refresh(rectmanager)
+    }
+
+    /// Show line numbers in the gutter (on by default).
+    pub fn set_line_numbers(&mut self, rectmanager: &mut RectManager, line_numbers: bool) -> Result<(), WreckedError> {
+        self.line_numbers = line_numbers;
+        self.refresh(rectmanager)
+    }
+
+    /// Set the number of spaces inserted by the tab key.
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width;
+    }
+
+    /// Set the number of edits that can be undone.
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.undo_limit = limit;
+        self.trim_undo_stack();
+    }
+
+    /// Use a system clipboard (or anything else) for copying and pasting.
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = Some(clipboard);
+    }
+
+    pub fn set_selection_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.selection_style = style;
+        self.drawn_rows.clear();
+        self.refresh(rectmanager)
+    }
+
+    /// Style the text with a Highlighter. Lines are only highlighted again when their text changes.
+    pub fn set_highlighter(&mut self, rectmanager: &mut RectManager, highlighter: Option<Box<dyn Highlighter>>) -> Result<(), WreckedError> {
+        self.highlight.set_highlighter(highlighter);
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_gutter_style(&mut self, rectmanager: &mut RectManager, style: Style) -> Result<(), WreckedError> {
+        self.gutter_style = style;
+        self.drawn_gutter.clear();
+        self.refresh(rectmanager)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Show the terminal's cursor in the editor.
+    pub fn focus(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = true;
+        self.refresh(rectmanager)
+    }
+
+    /// Hide the terminal's cursor.
+    pub fn blur(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = false;
+        release_cursor(rectmanager, self.text_id);
+        self.refresh(rectmanager)
+    }
+
+    /// Insert text at the cursor, replacing the selection.
+    pub fn insert_text(&mut self, rectmanager: &mut RectManager, text: &str) -> Result<(), WreckedError> {
+        self.save_undo(EditKind::Other);
+        self.delete_selection();
+        self.insert_at_cursor(text);
+        self.refresh(rectmanager)
+    }
+
+    /// Copy the selection to the clipboard.
+    pub fn copy(&mut self) {
+        if let Some(text) = self.get_selected_text() {
+            match self.clipboard.as_mut() {
+                Some(clipboard) => {
+                    clipboard.copy(&text);
+                }
+                None => {
+                    self.local_clipboard = text;
+                }
+            }
+        }
+    }
+
+    /// Copy the selection to the clipboard and delete it.
+    pub fn cut(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        if self.get_selection().is_some() {
+            self.copy();
+            self.save_undo(EditKind::Other);
+            self.delete_selection();
+        }
+        self.refresh(rectmanager)
+    }
+
+    /// Insert the contents of the clipboard at the cursor.
+    pub fn paste(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let text = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.paste(),
+            None => Some(self.local_clipboard.clone())
+        };
+
+        match text {
+            Some(text) if !text.is_empty() => {
+                self.insert_text(rectmanager, &text)
+            }
+            _ => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Undo the last edit. Returns false if there was nothing to undo.
+    pub fn undo(&mut self, rectmanager: &mut RectManager) -> Result<bool, WreckedError> {
+        match self.undo_stack.pop() {
+            Some(snapshot) => {
+                self.redo_stack.push(self.get_snapshot());
+                self.restore_snapshot(snapshot);
+                self.refresh(rectmanager)?;
+                Ok(true)
+            }
+            None => {
+                Ok(false)
+            }
+        }
+    }
+
+    /// Redo the last undone edit. Returns false if there was nothing to redo.
+    pub fn redo(&mut self, rectmanager: &mut RectManager) -> Result<bool, WreckedError> {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                self.undo_stack.push(self.get_snapshot());
+                self.restore_snapshot(snapshot);
+                self.refresh(rectmanager)?;
+                Ok(true)
+            }
+            None => {
+                Ok(false)
+            }
+        }
+    }
+
+    /// Redraw the rows that have changed. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let (width, height) = match rectmanager.get_rect_size(self.rect_id) {
+            Some(size) => size,
+            None => {
+                return Err(WreckedError::NotFound(self.rect_id));
+            }
+        };
+
+        let gutter_width = if self.line_numbers {
+            cmp::min(self.lines.len().to_string().len() + 1, width)
+        } else {
+            0
+        };
+        let text_width = width - gutter_width;
+
+        if self.drawn_size != (width, height, gutter_width) {
+            rectmanager.resize(self.gutter_id, gutter_width, height)?;
+            rectmanager.set_position(self.gutter_id, 0, 0)?;
+            rectmanager.resize(self.text_id, text_width, height)?;
+            rectmanager.set_position(self.text_id, gutter_width as isize, 0)?;
+            self.drawn_size = (width, height, gutter_width);
+            self.drawn_rows.clear();
+            self.drawn_gutter.clear();
+        }
+
+        let rows = self.get_visual_rows(text_width);
+        let (cursor_row, cursor_column) = self.get_cursor_row(&rows, text_width);
+
+        if self.follow_cursor {
+            if cursor_row < self.scroll.0 {
+                self.scroll.0 = cursor_row;
+            } else if height > 0 && cursor_row >= self.scroll.0 + height {
+                self.scroll.0 = cursor_row + 1 - height;
+            }
+
+            if !self.wrap {
+                if cursor_column < self.scroll.1 {
+                    self.scroll.1 = cursor_column;
+                } else if text_width > 0 && cursor_column >= self.scroll.1 + text_width {
+                    self.scroll.1 = cursor_column + 1 - text_width;
+                }
+            }
+            self.follow_cursor = false;
+        }
+        self.scroll.0 = cmp::min(self.scroll.0, rows.len().saturating_sub(1));
+
+        if self.drawn_rows.len() != height {
+            self.drawn_rows = vec![Vec::new(); height];
+            self.drawn_gutter = vec![String::new(); height];
+        }
+
+        let selection = self.get_selection();
+        let number_width = gutter_width.saturating_sub(1);
+        for y in 0 .. height {
+            let mut row_content = Vec::new();
+            let mut gutter_content = String::new();
+
+            if let Some((line, start, end)) = rows.get(self.scroll.0 + y) {
+                let line_styles = if self.highlight.is_enabled() {
+                    self.highlight.get_styles(&self.lines[*line].iter().collect::<String>())
+                } else {
+                    Vec::new()
+                };
+
+                let first_column = if self.wrap { *start } else { self.scroll.1 };
+                for x in 0 .. text_width {
+                    let column = first_column + x;
+                    if column < *end {
+                        let selected = match selection {
+                            Some((selection_start, selection_end)) => {
+                                (*line, column) >= selection_start && (*line, column) < selection_end
+                            }
+                            None => false
+                        };
+                        let style = if selected {
+                            Some(self.selection_style)
+                        } else {
+                            line_styles.get(column).copied().flatten()
+                        };
+                        row_content.push((Some(self.lines[*line][column]), style));
+                    } else {
+                        row_content.push((None, None));
+                    }
+                }
+
+                if *start == 0 && number_width > 0 {
+                    gutter_content = format!("{:>width$}", line + 1, width = number_width);
+                }
+            }
+
+            if row_content != self.drawn_rows[y] {
+                self.draw_row(rectmanager, y, &row_content, text_width)?;
+                self.drawn_rows[y] = row_content;
+            }
+
+            if gutter_content != self.drawn_gutter[y] {
+                for x in 0 .. number_width {
+                    rectmanager.unset_character(self.gutter_id, x as isize, y as isize)?;
+                }
+                for (x, character) in gutter_content.chars().take(number_width).enumerate() {
+                    rectmanager.set_character(self.gutter_id, x as isize, y as isize, character)?;
+                    rectmanager.set_character_style(self.gutter_id, x as isize, y as isize, self.gutter_style)?;
+                }
+                self.drawn_gutter[y] = gutter_content;
+            }
+        }
+
+        if self.focused {
+            let x = if self.wrap {
+                cursor_column as isize
+            } else {
+                cursor_column as isize - self.scroll.1 as isize
+            };
+            rectmanager.set_cursor(self.text_id, x, cursor_row as isize - self.scroll.0 as isize)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle editing keys and the mouse.
+    /// Returns true if the event was used by the editor.
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                self.handle_key(rectmanager, *key, *modifiers)
+            }
+            InputEvent::Mouse(mouse) => {
+                let in_bounds = get_local_position(rectmanager, self.rect_id, mouse.x, mouse.y).is_some();
+
+                match (mouse.action, mouse.button) {
+                    (MouseAction::Press, MouseButton::Left) if in_bounds => {
+                        let position = self.get_position_at(rectmanager, mouse.x, mouse.y);
+                        if mouse.modifiers.shift && self.anchor.is_none() {
+                            self.anchor = Some(self.cursor);
+                        }
+                        self.focused = true;
+                        self.last_edit = EditKind::Other;
+                        self.move_cursor(position);
+                        // Anchored at the clamped cursor, since the click may be past the end of the line
+                        if !mouse.modifiers.shift {
+                            self.anchor = Some(self.cursor);
+                        }
+                    }
+                    (MouseAction::Drag, MouseButton::Left) if self.focused => {
+                        let position = self.get_position_at(rectmanager, mouse.x, mouse.y);
+                        self.move_cursor(position);
+                    }
+                    (MouseAction::Press, MouseButton::WheelUp) if in_bounds => {
+                        self.scroll.0 = self.scroll.0.saturating_sub(3);
+                    }
+                    (MouseAction::Press, MouseButton::WheelDown) if in_bounds => {
+                        self.scroll.0 += 3;
+                    }
+                    _ => {
+                        return Ok(false);
+                    }
+                }
+
+                self.refresh(rectmanager)?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn handle_key(&mut self, rectmanager: &mut RectManager, key: Key, modifiers: Modifiers) -> Result<bool, WreckedError> {
+        if modifiers.ctrl {
+            match key {
+                Key::Char('z') => {
+                    self.undo(rectmanager)?;
+                    return Ok(true);
+                }
+                Key::Char('y') => {
+                    self.redo(rectmanager)?;
+                    return Ok(true);
+                }
+                Key::Char('a') => {
+                    self.select_all(rectmanager)?;
+                    return Ok(true);
+                }
+                Key::Char('c') => {
+                    self.copy();
+                    return Ok(true);
+                }
+                Key::Char('x') => {
+                    self.cut(rectmanager)?;
+                    return Ok(true);
+                }
+                Key::Char('v') => {
+                    self.paste(rectmanager)?;
+                    return Ok(true);
+                }
+                _ => ()
+            }
+        }
+
+        let height = rectmanager.get_rect_height(self.rect_id);
+        let (line, column) = self.cursor;
+        let last_line = self.lines.len() - 1;
+
+        let target = match key {
+            Key::Left => {
+                if column > 0 {
+                    Some((line, column - 1))
+                } else if line > 0 {
+                    Some((line - 1, self.lines[line - 1].len()))
+                } else {
+                    Some((0, 0))
+                }
+            }
+            Key::Right => {
+                if column < self.lines[line].len() {
+                    Some((line, column + 1))
+                } else if line < last_line {
+                    Some((line + 1, 0))
+                } else {
+                    Some(self.cursor)
+                }
+            }
+            Key::Up => Some((line.saturating_sub(1), column)),
+            Key::Down => Some((cmp::min(line + 1, last_line), column)),
+            Key::PageUp => Some((line.saturating_sub(cmp::max(height, 1)), column)),
+            Key::PageDown => Some((cmp::min(line + cmp::max(height, 1), last_line), column)),
+            Key::Home if modifiers.ctrl => Some((0, 0)),
+            Key::End if modifiers.ctrl => Some((last_line, self.lines[last_line].len())),
+            Key::Home => Some((line, 0)),
+            Key::End => Some((line, self.lines[line].len())),
+            _ => None
+        };
+
+        if let Some(target) = target {
+            if modifiers.shift {
+                if self.anchor.is_none() {
+                    self.anchor = Some(self.cursor);
+                }
+            } else {
+                self.anchor = None;
+            }
+            self.last_edit = EditKind::Other;
+            self.move_cursor(target);
+            self.refresh(rectmanager)?;
+            return Ok(true);
+        }
+
+        match key {
+            Key::Char(character) if !modifiers.ctrl && !modifiers.alt => {
+                self.save_undo(EditKind::Insert);
+                self.delete_selection();
+                self.insert_at_cursor(&character.to_string());
+            }
+            Key::Enter => {
+                self.save_undo(EditKind::Other);
+                self.delete_selection();
+                self.insert_at_cursor("\n");
+            }
+            Key::Tab => {
+                self.save_undo(EditKind::Insert);
+                self.delete_selection();
+                self.insert_at_cursor(&" ".repeat(self.tab_width));
+            }
+            Key::Backspace => {
+                self.save_undo(EditKind::Delete);
+                if !self.delete_selection() {
+                    if column > 0 {
+                        self.lines[line].remove(column - 1);
+                        self.cursor = (line, column - 1);
+                    } else if line > 0 {
+                        let removed = self.lines.remove(line);
+                        let previous_length = self.lines[line - 1].len();
+                        self.lines[line - 1].extend(removed);
+                        self.cursor = (line - 1, previous_length);
+                    }
+                }
+            }
+            Key::Delete => {
+                self.save_undo(EditKind::Delete);
+                if !self.delete_selection() {
+                    if column < self.lines[line].len() {
+                        self.lines[line].remove(column);
+                    } else if line < last_line {
+                        let removed = self.lines.remove(line + 1);
+                        self.lines[line].extend(removed);
+                    }
+                }
+            }
+            _ => {
+                return Ok(false);
+            }
+        }
+
+        self.follow_cursor = true;
+        self.refresh(rectmanager)?;
+        Ok(true)
+    }
+
+    fn move_cursor(&mut self, position: (usize, usize)) {
+        let line = cmp::min(position.0, self.lines.len() - 1);
+        let column = cmp::min(position.1, self.lines[line].len());
+        self.cursor = (line, column);
+        self.follow_cursor = true;
+    }
+
+    fn insert_at_cursor(&mut self, text: &str) {
+        let (line, column) = self.cursor;
+        let tail: Vec<char> = self.lines[line].split_off(column);
+
+        let mut new_lines = text.split('\n');
+        if let Some(first) = new_lines.next() {
+            self.lines[line].extend(first.chars());
+        }
+
+        let mut current_line = line;
+        for new_line in new_lines {
+            current_line += 1;
+            self.lines.insert(current_line, new_line.chars().collect());
+        }
+
+        let new_column = self.lines[current_line].len();
+        self.lines[current_line].extend(tail);
+        self.cursor = (current_line, new_column);
+        self.follow_cursor = true;
+    }
+
+    // Returns false if nothing was selected
+    fn delete_selection(&mut self) -> bool {
+        let (start, end) = match self.get_selection() {
+            Some(selection) => selection,
+            None => {
+                self.anchor = None;
+                return false;
+            }
+        };
+
+        let tail: Vec<char> = self.lines[end.0].split_off(end.1);
+        self.lines.drain(start.0 + 1 ..= end.0);
+        self.lines[start.0].truncate(start.1);
+        self.lines[start.0].extend(tail);
+
+        self.cursor = start;
+        self.anchor = None;
+        self.follow_cursor = true;
+        true
+    }
+
+    fn get_snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            cursor: self.cursor
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.cursor = snapshot.cursor;
+        self.anchor = None;
+        self.last_edit = EditKind::Other;
+        self.follow_cursor = true;
+    }
+
+    // Call before making a change. Runs of typing or deleting are grouped into a single undo step.
+    fn save_undo(&mut self, kind: EditKind) {
+        if kind == EditKind::Other || kind != self.last_edit || self.get_selection().is_some() {
+            self.undo_stack.push(self.get_snapshot());
+            self.trim_undo_stack();
+        }
+        self.redo_stack.clear();
+        self.last_edit = kind;
+    }
+
+    fn trim_undo_stack(&mut self) {
+        if self.undo_stack.len() > self.undo_limit {
+            let excess = self.undo_stack.len() - self.undo_limit;
+            self.undo_stack.drain(0 .. excess);
+        }
+    }
+
+    fn get_visual_rows(&self, text_width: usize) -> Vec<VisualRow> {
+        let mut rows = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if self.wrap && text_width > 0 {
+                // A line that fills its last row gets an empty one after it for the cursor
+                for chunk in 0 ..= line.len() / text_width {
+                    let start = chunk * text_width;
+                    rows.push((i, start, cmp::min(start + text_width, line.len())));
+                }
+            } else {
+                rows.push((i, 0, line.len()));
+            }
+        }
+        rows
+    }
+
+    // Get the row the cursor is on, and its column within that row
+    fn get_cursor_row(&self, rows: &[VisualRow], text_width: usize) -> (usize, usize) {
+        let (line, column) = self.cursor;
+        let first_row = rows.iter().position(|(row_line, _, _)| *row_line == line).unwrap_or(0);
+        if self.wrap && text_width > 0 {
+            (first_row + column / text_width, column % text_width)
+        } else {
+            (first_row, column)
+        }
+    }
+
+    // Convert an absolute position on screen to a (line, column) in the text
+    fn get_position_at(&self, rectmanager: &RectManager, x: isize, y: isize) -> (usize, usize) {
+        let (offset_x, offset_y) = rectmanager.get_absolute_offset(self.text_id).unwrap_or((0, 0));
+        let text_width = rectmanager.get_rect_width(self.text_id);
+        let rows = self.get_visual_rows(text_width);
+
+        let row_index = self.scroll.0 + cmp::max(y - offset_y, 0) as usize;
+        match rows.get(row_index) {
+            Some((line, start, _)) => {
+                let first_column = if self.wrap { *start } else { self.scroll.1 };
+                (*line, first_column + cmp::max(x - offset_x, 0) as usize)
+            }
+            None => {
+                let last_line = self.lines.len() - 1;
+                (last_line, self.lines[last_line].len())
+            }
+        }
+    }
+
+    fn draw_row(&self, rectmanager: &mut RectManager, y: usize, content: &[(Option<char>, Option<Style>)], text_width: usize) -> Result<(), WreckedError> {
+        for x in 0 .. text_width {
+            let (character, style) = content.get(x).copied().unwrap_or((None, None));
+            match character {
+                Some(character) => {
+                    rectmanager.set_character(self.text_id, x as isize, y as isize, character)?;
+                }
+                None => {
+                    rectmanager.unset_character(self.text_id, x as isize, y as isize)?;
+                }
+            }
+
+            match style {
+                Some(style) => {
+                    rectmanager.set_character_style(self.text_id, x as isize, y as isize, style)?;
+                }
+                None => {
+                    rectmanager.unset_character_style(self.text_id, x as isize, y as isize)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+use std::cmp;
+use std::collections::VecDeque;
+use crate::{RectManager, WreckedError, Style, Color};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use super::{get_local_position, release_cursor};
+
+/// A single line of editable text.
+/// While focused, the terminal's own cursor is placed at the editing position.
+///
+/// Enter isn't handled by the widget; call submit() when it's pressed to take the text and add it to the history.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::TextInput;
+/// use wrecked::input::{parse_input, InputEvent, Key};
+/// let mut rectmanager = RectManager::new();
+///
+/// let mut input = TextInput::new(&mut rectmanager, ROOT).ok().unwrap();
+/// rectmanager.resize(input.get_rect_id(), 20, 1);
+/// input.set_history_size(50);
+/// input.focus(&mut rectmanager);
+///
+/// for event in parse_input(b"ls -la") {
+///     input.handle_event(&mut rectmanager, &event);
+/// }
+/// assert_eq!(input.submit(&mut rectmanager).ok().unwrap(), "ls -la");
+///
+/// // Previous entries can be recalled with the Up key
+/// input.handle_event(&mut rectmanager, &InputEvent::key(Key::Up));
+/// assert_eq!(input.get_text(), "ls -la");
+///
+/// rectmanager.kill();
+/// ```
+pub struct TextInput {
+    rect_id: usize,
+    text: Vec<char>,
+    // Position of the cursor, in characters
+    cursor: usize,
+    // Index of the first visible character
+    offset: usize,
+    placeholder: String,
+    placeholder_style: Style,
+    mask: Option<char>,
+    focused: bool,
+
+    history: VecDeque<String>,
+    history_size: usize,
+    // Position while browsing the history, and the text that was being edited before browsing started
+    history_index: Option<usize>,
+    draft: Vec<char>
+}
+
+impl TextInput {
+    /// Create a TextInput with a new rect attached to the given parent.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize) -> Result<TextInput, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+
+        Ok(TextInput {
+            rect_id,
+            text: Vec::new(),
+            cursor: 0,
+            offset: 0,
+            placeholder: String::new(),
+            placeholder_style: Style {
+                foreground_color: Some(Color::BRIGHTBLACK),
+                ..Style::default()
+            },
+            mask: None,
+            focused: false,
+            history: VecDeque::new(),
+            history_size: 0,
+            history_index: None,
+            draft: Vec::new()
+        })
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Replace the text, moving the cursor to the end of it.
+    pub fn set_text(&mut self, rectmanager: &mut RectManager, text: &str) -> Result<(), WreckedError> {
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+        self.history_index = None;
+        self.refresh(rectmanager)
+    }
+
+    /// Get the position of the cursor, in characters from the start of the text.
+    pub fn get_cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, rectmanager: &mut RectManager, cursor: usize) -> Result<(), WreckedError> {
+        self.cursor = cmp::min(cursor, self.text.len());
+        self.refresh(rectmanager)
+    }
+
+    /// Text shown while the input is empty.
+    pub fn set_placeholder(&mut self, rectmanager: &mut RectManager, placeholder: &str) -> Result<(), WreckedError> {
+        self.placeholder = placeholder.to_string();
+        self.refresh(rectmanager)
+    }
+
+    pub fn set_placeholder_style(&mut self, style: Style) {
+        self.placeholder_style = style;
+    }
+
+    /// Display every character as `mask`, eg '*' for passwords. None shows the text as-is.
+    pub fn set_mask(&mut self, rectmanager: &mut RectManager, mask: Option<char>) -> Result<(), WreckedError> {
+        self.mask = mask;
+        self.refresh(rectmanager)
+    }
+
+    /// Set how many submitted entries are remembered. 0 (the default) disables the history.
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history_size = size;
+        while self.history.len() > size {
+            self.history.pop_front();
+        }
+    }
+
+    /// Get the remembered entries, oldest first.
+    pub fn get_history(&self) -> Vec<String> {
+        self.history.iter().cloned().collect()
+    }
+
+    /// Add an entry to the history, dropping the oldest one if the history is full.
+    /// Empty entries and repeats of the latest entry are ignored.
+    pub fn push_history(&mut self, entry: &str) {
+        if self.history_size == 0 || entry.is_empty() || self.history.back().map(|s| s.as_str()) == Some(entry) {
+            return;
+        }
+
+        self.history.push_back(entry.to_string());
+        while self.history.len() > self.history_size {
+            self.history.pop_front();
+        }
+    }
+
+    /// Take the text out of the input, adding it to the history.
+    pub fn submit(&mut self, rectmanager: &mut RectManager) -> Result<String, WreckedError> {
+        let text = self.get_text();
+        self.push_history(&text);
+        self.history_index = None;
+        self.text.clear();
+        self.cursor = 0;
+        self.refresh(rectmanager)?;
+
+        Ok(text)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Show the terminal's cursor in the input.
+    pub fn focus(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = true;
+        self.refresh(rectmanager)
+    }
+
+    /// Hide the terminal's cursor.
+    pub fn blur(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.focused = false;
+        release_cursor(rectmanager, self.rect_id);
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the input. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let width = rectmanager.get_rect_width(self.rect_id);
+
+        // Keep the cursor in view, leaving a cell for it past the end of the text
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if width > 0 && self.cursor >= self.offset + width {
+            self.offset = self.cursor + 1 - width;
+        }
+        // Don't leave empty space at the end if text has been deleted
+        let max_offset = (self.text.len() + 1).saturating_sub(width);
+        self.offset = cmp::min(self.offset, max_offset);
+
+        rectmanager.clear_characters(self.rect_id)?;
+        rectmanager.clear_character_styles(self.rect_id)?;
+
+        if self.text.is_empty() {
+            for (x, character) in self.placeholder.chars().take(width).enumerate() {
+                rectmanager.set_character(self.rect_id, x as isize, 0, character)?;
+                rectmanager.set_character_style(self.rect_id, x as isize, 0, self.placeholder_style)?;
+            }
+        } else {
+            for (x, character) in self.text.iter().skip(self.offset).take(width).enumerate() {
+                rectmanager.set_character(self.rect_id, x as isize, 0, self.mask.unwrap_or(*character))?;
+            }
+        }
+
+        if self.focused {
+            rectmanager.set_cursor(self.rect_id, (self.cursor - self.offset) as isize, 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle editing keys and mouse clicks.
+    /// Returns true if the event was used by the input.
+    ///
+    /// * Printable characters are inserted at the cursor
+    /// * Backspace/Delete remove the character before/after the cursor
+    /// * Left/Right move the cursor, by a word at a time with ctrl held
+    /// * Home/End jump to the start/end of the text
+    /// * Up/Down browse the history
+    /// * Clicking the input focuses it and moves the cursor
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        match event {
+            InputEvent::Key(key, modifiers) => {
+                match key {
+                    Key::Char(character) if !modifiers.ctrl && !modifiers.alt => {
+                        self.text.insert(self.cursor, *character);
+                        self.cursor += 1;
+                    }
+                    Key::Backspace => {
+                        if self.cursor > 0 {
+                            self.cursor -= 1;
+                            self.text.remove(self.cursor);
+                        }
+                    }
+                    Key::Delete => {
+                        if self.cursor < self.text.len() {
+                            self.text.remove(self.cursor);
+                        }
+                    }
+                    Key::Left => {
+                        if modifiers.ctrl {
+                            self.cursor = self.get_previous_word_start();
+                        } else {
+                            self.cursor = self.cursor.saturating_sub(1);
+                        }
+                    }
+                    Key::Right => {
+                        if modifiers.ctrl {
+                            self.cursor = self.get_next_word_end();
+                        } else {
+                            self.cursor = cmp::min(self.cursor + 1, self.text.len());
+                        }
+                    }
+                    Key::Home => {
+                        self.cursor = 0;
+                    }
+                    Key::End => {
+                        self.cursor = self.text.len();
+                    }
+                    Key::Up => {
+                        if !self.browse_history(true) {
+                            return Ok(false);
+                        }
+                    }
+                    Key::Down => {
+                        if !self.browse_history(false) {
+                            return Ok(false);
+                        }
+                    }
+                    _ => {
+                        return Ok(false);
+                    }
+                }
+
+                self.refresh(rectmanager)?;
+                Ok(true)
+            }
+            InputEvent::Mouse(mouse) => {
+                if mouse.action != MouseAction::Press || mouse.button != MouseButton::Left {
+                    return Ok(false);
+                }
+
+                let x = match get_local_position(rectmanager, self.rect_id, mouse.x, mouse.y) {
+                    Some((x, _)) => x,
+                    None => {
+                        return Ok(false);
+                    }
+                };
+
+                self.focused = true;
+                self.cursor = cmp::min(self.offset + x, self.text.len());
+                self.refresh(rectmanager)?;
+                Ok(true)
+            }
+        }
+    }
+
+    // Returns false if there is nowhere to go
+    fn browse_history(&mut self, backwards: bool) -> bool {
+        if self.history.is_empty() {
+            return false;
+        }
+
+        let new_index = match (self.history_index, backwards) {
+            (None, true) => {
+                self.draft = self.text.clone();
+                Some(self.history.len() - 1)
+            }
+            (None, false) => {
+                return false;
+            }
+            (Some(0), true) => {
+                return false;
+            }
+            (Some(index), true) => Some(index - 1),
+            (Some(index), false) => {
+                if index + 1 < self.history.len() {
+                    Some(index + 1)
+                } else {
+                    None
+                }
+            }
+        };
+
+        self.text = match new_index {
+            Some(index) => self.history[index].chars().collect(),
+            None => self.draft.clone()
+        };
+        self.history_index = new_index;
+        self.cursor = self.text.len();
+
+        true
+    }
+
+    fn get_previous_word_start(&self) -> usize {
+        let mut position = self.cursor;
+        while position > 0 && !self.text[position - 1].is_alphanumeric() {
+            position -= 1;
+        }
+        while position > 0 && self.text[position - 1].is_alphanumeric() {
+            position -= 1;
+        }
+        position
+    }
+
+    fn get_next_word_end(&self) -> usize {
+        let mut position = self.cursor;
+        while position < self.text.len() && !self.text[position].is_alphanumeric() {
+            position += 1;
+        }
+        while position < self.text.len() && self.text[position].is_alphanumeric() {
+            position += 1;
+        }
+        position
+    }
+}
@@ -0,0 +1,468 @@
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use crate::{RectManager, WreckedError, Style, Color};
+use crate::input::{InputEvent, Key, MouseAction, MouseButton};
+use super::get_local_position;
+
+/// Provides the nodes displayed by a TreeView. Nodes are identified by ids chosen by the data source.
+/// Children are only requested when their parent is first expanded, and labels only when their rows are visible.
+pub trait TreeDataSource {
+    /// The ids of the top-level nodes
+    fn get_roots(&self) -> Vec<usize>;
+
+    fn get_label(&self, node: usize) -> String;
+
+    /// Whether the node can be expanded. This is called for every visible node, so it should be cheap.
+    fn has_children(&self, node: usize) -> bool;
+
+    /// The ids of the node's children
+    fn get_children(&self, node: usize) -> Vec<usize>;
+}
+
+// A node that's currently shown; the tree is flattened into a list of these
+struct TreeRow {
+    node: usize,
+    depth: usize,
+    // Row of the parent node
+    parent: Option<usize>,
+    // Indent guides drawn before the node's marker
+    prefix: String
+}
+
+/// A scrolling tree of expandable nodes with a cursor.
+/// The expanded part of the tree is kept as a flat list of rows, and only the visible rows are drawn.
+/// # Example
+/// ```
+/// use wrecked::{RectManager, ROOT};
+/// use wrecked::widgets::{TreeView, TreeDataSource};
+/// use wrecked::input::{InputEvent, Key};
+///
+/// // Node n has the children 10n + 1 to 10n + 3, down to 3 levels
+/// struct Numbers;
+/// impl TreeDataSource for Numbers {
+///     fn get_roots(&self) -> Vec<usize> { vec![1, 2, 3] }
+///     fn get_label(&self, node: usize) -> String { format!("Node {}", node) }
+///     fn has_children(&self, node: usize) -> bool { node < 100 }
+///     fn get_children(&self, node: usize) -> Vec<usize> { (1 ..= 3).map(|i| node * 10 + i).collect() }
+/// }
+///
+/// let mut rectmanager = RectManager::new();
+/// let mut tree = TreeView::new(&mut rectmanager, ROOT, Numbers).ok().unwrap();
+/// rectmanager.resize(tree.get_rect_id(), 20, 10);
+///
+/// tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Right));
+/// tree.handle_event(&mut rectmanager, &InputEvent::key(Key::Down));
+/// assert_eq!(tree.get_cursor(), Some(11));
+/// assert_eq!(rectmanager.get_character(tree.get_rect_id(), 0, 1).ok(), Some('├'));
+///
+/// rectmanager.kill();
+/// ```
+pub struct TreeView<S: TreeDataSource> {
+    rect_id: usize,
+    source: S,
+    roots: Option<Vec<usize>>,
+    // Children of nodes that have been expanded
+    children: HashMap<usize, Vec<usize>>,
+    expanded: HashSet<usize>,
+    rows: Vec<TreeRow>,
+    // row of the cursor
+    cursor: usize,
+    // row at the top of the rect
+    top: usize,
+    cursor_style: Style,
+    guide_style: Style
+}
+
+impl<S: TreeDataSource> TreeView<S> {
+    /// Create a TreeView with a new rect attached to the given parent. All nodes start collapsed.
+    pub fn new(rectmanager: &mut RectManager, parent_id: usize, source: S) -> Result<TreeView<S>, WreckedError> {
+        let rect_id = rectmanager.new_rect(parent_id)?;
+
+        let mut tree = TreeView {
+            rect_id,
+            source,
+            roots: None,
+            children: HashMap::new(),
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            cursor: 0,
+            top: 0,
+            cursor_style: Style {
+                invert: true,
+                ..Style::default()
+            },
+            guide_style: Style {
+                foreground_color: Some(Color::BRIGHTBLACK),
+                ..Style::default()
+            }
+        };
+        tree.rebuild();
+
+        Ok(tree)
+    }
+
+    pub fn get_rect_id(&self) -> usize {
+        self.rect_id
+    }
+
+    pub fn get_source(&self) -> &S {
+        &self.source
+    }
+
+    /// Access the data source in order to change it. Call reload() afterwards.
+    pub fn get_source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Set the style of the row under the cursor.
+    pub fn set_cursor_style(&mut self, style: Style) {
+        self.cursor_style = style;
+    }
+
+    /// Set the style of the indent guides.
+    pub fn set_guide_style(&mut self, style: Style) {
+        self.guide_style = style;
+    }
+
+    /// Forget all loaded children and request them from the data source again. Expanded nodes stay expanded.
+    pub fn reload(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        self.roots = None;
+        self.children.clear();
+        self.rebuild();
+        self.refresh(rectmanager)
+    }
+
+    /// Forget the loaded children of a single node.
+    pub fn reload_node(&mut self, rectmanager: &mut RectManager, node: usize) -> Result<(), WreckedError> {
+        self.children.remove(&node);
+        self.rebuild();
+        self.refresh(rectmanager)
+    }
+
+    pub fn is_expanded(&self, node: usize) -> bool {
+        self.expanded.contains(&node)
+    }
+
+    pub fn expand(&mut self, rectmanager: &mut RectManager, node: usize) -> Result<(), WreckedError> {
+        if self.source.has_children(node) && self.expanded.insert(node) {
+            self.rebuild();
+            self.refresh(rectmanager)?;
+        }
+        Ok(())
+    }
+
+    /// Collapse a node. If the cursor was on one of its descendants, it moves to the node.
+    pub fn collapse(&mut self, rectmanager: &mut RectManager, node: usize) -> Result<(), WreckedError> {
+        if !self.expanded.remove(&node) {
+            return Ok(());
+        }
+
+        let mut row = self.rows.get(self.cursor).and_then(|row| row.parent);
+        while let Some(index) = row {
+            if self.rows[index].node == node {
+                self.cursor = index;
+                break;
+            }
+            row = self.rows[index].parent;
+        }
+
+        self.rebuild();
+        self.refresh(rectmanager)
+    }
+
+    pub fn toggle(&mut self, rectmanager: &mut RectManager, node: usize) -> Result<(), WreckedError> {
+        if self.is_expanded(node) {
+            self.collapse(rectmanager, node)
+        } else {
+            self.expand(rectmanager, node)
+        }
+    }
+
+    /// Get the number of visible (not hidden under a collapsed node) rows.
+    pub fn get_row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Get the node shown at a row.
+    pub fn get_node(&self, row: usize) -> Option<usize> {
+        self.rows.get(row).map(|row| row.node)
+    }
+
+    /// Get the depth of the node shown at a row. Top-level nodes have a depth of 0.
+    pub fn get_depth(&self, row: usize) -> Option<usize> {
+        self.rows.get(row).map(|row| row.depth)
+    }
+
+    /// Get the node under the cursor. None if the tree is empty.
+    pub fn get_cursor(&self) -> Option<usize> {
+        self.get_node(self.cursor)
+    }
+
+    /// Get the row of the cursor.
+    pub fn get_cursor_row(&self) -> usize {
+        self.cursor
+    }
+
+    /// Get the row shown at the top of the rect.
+    pub fn get_top(&self) -> usize {
+        self.top
+    }
+
+    /// Move the cursor to a row, scrolling the tree so that it stays visible.
+    pub fn set_cursor_row(&mut self, rectmanager: &mut RectManager, row: usize) -> Result<(), WreckedError> {
+        self.cursor = cmp::min(row, self.rows.len().saturating_sub(1));
+
+        let height = self.get_height(rectmanager);
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if height > 0 && self.cursor >= self.top + height {
+            self.top = self.cursor + 1 - height;
+        }
+
+        self.refresh(rectmanager)
+    }
+
+    /// Move the cursor to a node, if it's visible.
+    pub fn set_cursor(&mut self, rectmanager: &mut RectManager, node: usize) -> Result<(), WreckedError> {
+        match self.rows.iter().position(|row| row.node == node) {
+            Some(row) => self.set_cursor_row(rectmanager, row),
+            None => Ok(())
+        }
+    }
+
+    /// Scroll the tree without moving the cursor.
+    pub fn scroll_to(&mut self, rectmanager: &mut RectManager, top: usize) -> Result<(), WreckedError> {
+        let height = self.get_height(rectmanager);
+        self.top = cmp::min(top, self.rows.len().saturating_sub(height));
+        self.refresh(rectmanager)
+    }
+
+    /// Redraw the visible rows. Needs to be called after the size of the rect changes.
+    pub fn refresh(&mut self, rectmanager: &mut RectManager) -> Result<(), WreckedError> {
+        let length = self.rows.len();
+        self.cursor = cmp::min(self.cursor, length.saturating_sub(1));
+
+        let (width, height) = match rectmanager.get_rect_size(self.rect_id) {
+            Some(size) => size,
+            None => {
+                return Err(WreckedError::NotFound(self.rect_id));
+            }
+        };
+        self.top = cmp::min(self.top, length.saturating_sub(height));
+
+        for line in 0 .. height {
+            let index = self.top + line;
+            let y = line as isize;
+            let (text, guide_width) = match self.rows.get(index) {
+                Some(row) => {
+                    let marker = self.get_marker(row);
+                    let text = format!("{}{}{}", row.prefix, marker, self.source.get_label(row.node));
+                    // Leaf markers are drawn as part of the guides
+                    let guide_width = row.prefix.chars().count() + if marker == "─ " { 1 } else { 0 };
+                    (text, guide_width)
+                }
+                None => {
+                    (String::new(), 0)
+                }
+            };
+
+            let mut characters = text.chars();
+            for x in 0 .. width {
+                match characters.next() {
+                    Some(character) => {
+                        rectmanager.set_character(self.rect_id, x as isize, y, character)?;
+                    }
+                    None => {
+                        rectmanager.unset_character(self.rect_id, x as isize, y)?;
+                    }
+                }
+
+                let style = if index == self.cursor && index < length {
+                    Some(self.cursor_style)
+                } else if x < guide_width && self.guide_style != Style::default() {
+                    Some(self.guide_style)
+                } else {
+                    None
+                };
+                match style {
+                    Some(style) => {
+                        rectmanager.set_character_style(self.rect_id, x as isize, y, style)?;
+                    }
+                    None => {
+                        rectmanager.unset_character_style(self.rect_id, x as isize, y)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle keyboard navigation and mouse clicks.
+    /// Returns true if the event was used by the tree.
+    ///
+    /// * Up/Down/PageUp/PageDown/Home/End move the cursor
+    /// * Right expands the node under the cursor, or moves to its first child if it's already expanded
+    /// * Left collapses the node under the cursor, or moves to its parent if it's already collapsed
+    /// * Enter and Space expand or collapse the node under the cursor
+    /// * Clicking a row moves the cursor to it, and clicking a node's marker expands or collapses it
+    /// * The mouse wheel scrolls the tree
+    pub fn handle_event(&mut self, rectmanager: &mut RectManager, event: &InputEvent) -> Result<bool, WreckedError> {
+        if self.rows.is_empty() {
+            return Ok(false);
+        }
+        let height = self.get_height(rectmanager);
+        let last = self.rows.len() - 1;
+        let node = self.rows[self.cursor].node;
+
+        match event {
+            InputEvent::Key(key, _) => {
+                match key {
+                    Key::Up => self.set_cursor_row(rectmanager, self.cursor.saturating_sub(1))?,
+                    Key::Down => self.set_cursor_row(rectmanager, cmp::min(self.cursor + 1, last))?,
+                    Key::PageUp => self.set_cursor_row(rectmanager, self.cursor.saturating_sub(cmp::max(height, 1)))?,
+                    Key::PageDown => self.set_cursor_row(rectmanager, cmp::min(self.cursor + cmp::max(height, 1), last))?,
+                    Key::Home => self.set_cursor_row(rectmanager, 0)?,
+                    Key::End => self.set_cursor_row(rectmanager, last)?,
+                    Key::Right => {
+                        if !self.is_expanded(node) {
+                            self.expand(rectmanager, node)?;
+                        } else if self.rows.get(self.cursor + 1).and_then(|row| row.parent) == Some(self.cursor) {
+                            self.set_cursor_row(rectmanager, self.cursor + 1)?;
+                        }
+                    }
+                    Key::Left => {
+                        if self.is_expanded(node) {
+                            self.collapse(rectmanager, node)?;
+                        } else if let Some(parent) = self.rows[self.cursor].parent {
+                            self.set_cursor_row(rectmanager, parent)?;
+                        }
+                    }
+                    Key::Enter | Key::Char(' ') => {
+                        self.toggle(rectmanager, node)?;
+                    }
+                    _ => {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            InputEvent::Mouse(mouse) => {
+                let (x, y) = match get_local_position(rectmanager, self.rect_id, mouse.x, mouse.y) {
+                    Some((x, y)) if y < height => (x, y),
+                    _ => {
+                        return Ok(false);
+                    }
+                };
+
+                match (mouse.action, mouse.button) {
+                    (MouseAction::Press, MouseButton::Left) => {
+                        let index = self.top + y;
+                        if let Some(row) = self.rows.get(index) {
+                            let node = row.node;
+                            let marker_start = row.prefix.chars().count();
+                            self.set_cursor_row(rectmanager, index)?;
+                            if x >= marker_start && x < marker_start + 2 {
+                                self.toggle(rectmanager, node)?;
+                            }
+                        }
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelUp) => {
+                        self.scroll_to(rectmanager, self.top.saturating_sub(3))?;
+                        Ok(true)
+                    }
+                    (MouseAction::Press, MouseButton::WheelDown) => {
+                        self.scroll_to(rectmanager, self.top + 3)?;
+                        Ok(true)
+                    }
+                    _ => {
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_marker(&self, row: &TreeRow) -> &'static str {
+        if self.source.has_children(row.node) {
+            if self.is_expanded(row.node) {
+                "▾ "
+            } else {
+                "▸ "
+            }
+        } else if row.depth > 0 {
+            "─ "
+        } else {
+            "  "
+        }
+    }
+
+    // Flatten the expanded part of the tree into rows, keeping the cursor on the same node if it's still shown
+    fn rebuild(&mut self) {
+        let cursor_node = self.get_cursor();
+
+        let roots = match &self.roots {
+            Some(roots) => roots.clone(),
+            None => {
+                let roots = self.source.get_roots();
+                self.roots = Some(roots.clone());
+                roots
+            }
+        };
+
+        self.rows.clear();
+        self.add_rows(&roots, 0, None, "");
+
+        if let Some(node) = cursor_node {
+            if let Some(row) = self.rows.iter().position(|row| row.node == node) {
+                self.cursor = row;
+            }
+        }
+        self.cursor = cmp::min(self.cursor, self.rows.len().saturating_sub(1));
+    }
+
+    fn add_rows(&mut self, nodes: &[usize], depth: usize, parent: Option<usize>, guides: &str) {
+        for (i, node) in nodes.iter().enumerate() {
+            let is_last = i == nodes.len() - 1;
+            let prefix = if depth == 0 {
+                String::new()
+            } else if is_last {
+                format!("{}└─", guides)
+            } else {
+                format!("{}├─", guides)
+            };
+
+            self.rows.push(TreeRow {
+                node: *node,
+                depth,
+                parent,
+                prefix
+            });
+
+            if self.expanded.contains(node) {
+                let row = self.rows.len() - 1;
+                let source = &self.source;
+                let children = self.children.entry(*node)
+                    .or_insert_with(|| source.get_children(*node))
+                    .clone();
+
+                let child_guides = if depth == 0 {
+                    String::new()
+                } else if is_last {
+                    format!("{}  ", guides)
+                } else {
+                    format!("{}│ ", guides)
+                };
+                self.add_rows(&children, depth + 1, Some(row), &child_guides);
+            }
+        }
+    }
+
+    fn get_height(&self, rectmanager: &RectManager) -> usize {
+        match rectmanager.get_rect_size(self.rect_id) {
+            Some((_, height)) => height,
+            None => 0
+        }
+    }
+}